@@ -33,7 +33,8 @@ def run(host, port):
 
 class Client():
     """
-    username||database||query||key
+    login: username||||||key
+    then SQL text, with the current database kept by the server
     """
 
     def __init__(self):
@@ -49,7 +50,7 @@ class Client():
 
     def _use_database(self, name):
         self._database = name
-        print("database: %s" % self._database)
+        return ('use {0};\n').format(self._database)
 
     def _create_user(self, name, key):
         self._user = name
@@ -58,17 +59,14 @@ class Client():
 
     def _create_database(self, db_name):
         self._database = db_name
-        return ('{0}||||create database {1};\n').format(self._user, self._database)
+        return ('create database {0};\n').format(self._database)
 
     def _send_query(self, query):
         if self._user == "":
             print('Please set or create user!')
             return None
-        if self._database == "":
-            print('Please use or create database!')
-            return None
         self._query = query
-        return ('{0}||{1}||{2};\n').format(self._user, self._database, self._query)
+        return ('{0};\n').format(self._query)
 
     def check_live(self):
         return self._is_live
@@ -45,11 +45,11 @@ impl Database {
     }
 
     // load the metadate of the database and its tables
-    pub fn load_db(username: &str, db_name: &str) -> Result<Database, DatabaseError> {
+    pub fn load_db(username: &str, db_name: &str, file_base_path: Option<&str>) -> Result<Database, DatabaseError> {
         let mut db = Database::new(db_name);
         db.is_dirty = false;
         let metas =
-            DiskInterface::load_tables_meta(username, db_name, None).map_err(|e| DatabaseError::CausedByFile(e))?;
+            DiskInterface::load_tables_meta(username, db_name, file_base_path).map_err(DatabaseError::CausedByFile)?;
         for meta in metas {
             let name = (&meta.name).to_string();
             let mut table = Table::new(&name);
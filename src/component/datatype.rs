@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DataType {
     Char(u8),
@@ -22,6 +24,26 @@ impl DataType {
         };
         Some(d)
     }
+
+    pub fn is_numeric(&self) -> bool {
+        match *self {
+            DataType::Int | DataType::Float | DataType::Double => true,
+            DataType::Char(_) | DataType::Varchar(_) | DataType::Url => false,
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataType::Char(length) => write!(f, "char({})", length),
+            DataType::Double => write!(f, "double"),
+            DataType::Float => write!(f, "float"),
+            DataType::Int => write!(f, "int"),
+            DataType::Varchar(length) => write!(f, "varchar({})", length),
+            DataType::Url => write!(f, "url"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -35,4 +57,10 @@ mod tests {
         assert_eq!(DataType::Url, DataType::get("url", None).unwrap());
         assert!(DataType::get("date", None).is_none());
     }
+
+    #[test]
+    fn test_datatype_display() {
+        assert_eq!(DataType::Char(8).to_string(), "char(8)");
+        assert_eq!(DataType::Int.to_string(), "int");
+    }
 }
@@ -0,0 +1,685 @@
+use crate::component::datatype::DataType;
+use crate::component::field::Field;
+use crate::component::field::Operator;
+use crate::component::function;
+use crate::component::table::Row;
+use crate::sql::query::Node;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The value of an expression, typed by the datatype of fields and the form of literals
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Double(f64),
+    Str(String),
+    Null,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionError {
+    TypeMismatched(String),
+    IllegalValue(String),
+    FieldNotExisted(String),
+    UnknownFunction(String),
+    ArgumentMismatched(String),
+    NotPredicate(String),
+    IllegalPattern(String),
+    DivideByZero,
+    Overflow,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Int(ref i) => write!(f, "{}", i),
+            Value::Double(ref d) => write!(f, "{}", d),
+            Value::Str(ref s) => write!(f, "{}", s),
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExpressionError::TypeMismatched(ref s) => write!(f, "Expression Error: type mismatched in `{}`.", s),
+            ExpressionError::IllegalValue(ref s) => {
+                write!(
+                    f,
+                    "Expression Error: value {} doesn't match the datatype of the field.",
+                    s
+                )
+            }
+            ExpressionError::FieldNotExisted(ref s) => write!(f, "Expression Error: field {} not exists.", s),
+            ExpressionError::UnknownFunction(ref s) => write!(f, "Expression Error: unknown function {}.", s),
+            ExpressionError::ArgumentMismatched(ref s) => {
+                write!(f, "Expression Error: wrong number of arguments for {}.", s)
+            }
+            ExpressionError::NotPredicate(ref s) => write!(f, "Expression Error: `{}` is not a predicate.", s),
+            ExpressionError::IllegalPattern(ref s) => write!(f, "Expression Error: illegal pattern {}.", s),
+            ExpressionError::DivideByZero => write!(f, "Expression Error: divided by zero."),
+            ExpressionError::Overflow => write!(f, "Expression Error: integer overflow."),
+        }
+    }
+}
+
+impl Value {
+    fn to_double(&self) -> Result<f64, ExpressionError> {
+        match *self {
+            Value::Int(i) => Ok(i as f64),
+            Value::Double(d) => Ok(d),
+            Value::Str(ref s) => s
+                .parse::<f64>()
+                .map_err(|_| ExpressionError::TypeMismatched(format!("'{}' is not a number", s))),
+            Value::Null => Err(ExpressionError::TypeMismatched("null is not a number".to_string())),
+        }
+    }
+}
+
+/// Evaluate the predicate on the row, `None` is unknown
+///
+/// A predicate is formed by `and`, `or`, `not`, `is [not] null`, `[not] like`, `[not] in`,
+/// `[not] between` and comparisons of expressions.
+pub fn eval_predicate(
+    node: &Node,
+    fields: &HashMap<String, Field>,
+    row: &Row,
+) -> Result<Option<bool>, ExpressionError> {
+    let eval = |child: &Option<Box<Node>>| match child {
+        Some(c) => eval_predicate(c, fields, row),
+        None => Err(ExpressionError::NotPredicate(node.root.clone())),
+    };
+    match node.root.as_str() {
+        "and" => match (eval(&node.left)?, eval(&node.right)?) {
+            (Some(false), _) | (_, Some(false)) => Ok(Some(false)),
+            (Some(true), Some(true)) => Ok(Some(true)),
+            _ => Ok(None),
+        },
+        "or" => match (eval(&node.left)?, eval(&node.right)?) {
+            (Some(true), _) | (_, Some(true)) => Ok(Some(true)),
+            (Some(false), Some(false)) => Ok(Some(false)),
+            _ => Ok(None),
+        },
+        "not" => Ok(eval(&node.right)?.map(|b| !b)),
+        "is null" | "is not null" => match node.left {
+            Some(ref left) => {
+                let is_null = eval_expression(left, fields, row)? == Value::Null;
+                Ok(Some(is_null == (node.root == "is null")))
+            }
+            None => Err(ExpressionError::NotPredicate(node.root.clone())),
+        },
+        "like" | "not like" => match (&node.left, &node.right) {
+            (Some(left), Some(right)) => {
+                let result = eval_like(left, right, fields, row)?;
+                Ok(result.map(|b| b != (node.root == "not like")))
+            }
+            _ => Err(ExpressionError::NotPredicate(node.root.clone())),
+        },
+        "in" | "not in" => match node.left {
+            Some(ref left) => {
+                let left = eval_expression(left, fields, row)?;
+                let mut result = Some(false);
+                for arg in node.args.iter() {
+                    match compare_values(&left, &Operator::EQ, &eval_expression(arg, fields, row)?)? {
+                        Some(true) => {
+                            result = Some(true);
+                            break;
+                        }
+                        Some(false) => {}
+                        None => result = None,
+                    }
+                }
+                Ok(result.map(|b| b != (node.root == "not in")))
+            }
+            None => Err(ExpressionError::NotPredicate(node.root.clone())),
+        },
+        // the rows of the subquery are resolved to the arguments
+        "exists" => Ok(Some(!node.args.is_empty())),
+        "between" | "not between" => match (&node.left, node.args.len()) {
+            (Some(left), 2) => {
+                let left = eval_expression(left, fields, row)?;
+                let lower = eval_expression(&node.args[0], fields, row)?;
+                let upper = eval_expression(&node.args[1], fields, row)?;
+                let result = match (
+                    compare_values(&left, &Operator::GE, &lower)?,
+                    compare_values(&left, &Operator::LE, &upper)?,
+                ) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                };
+                Ok(result.map(|b| b != (node.root == "not between")))
+            }
+            _ => Err(ExpressionError::NotPredicate(node.root.clone())),
+        },
+        root => match (Operator::get(root), &node.left, &node.right) {
+            (Some(operator), Some(left), Some(right)) => {
+                let left = eval_expression(left, fields, row)?;
+                let right = eval_expression(right, fields, row)?;
+                compare_values(&left, &operator, &right)
+            }
+            _ => Err(ExpressionError::NotPredicate(root.to_string())),
+        },
+    }
+}
+
+/// Evaluate the expression on the row
+///
+/// An expression is a field, a literal, an arithmetic or a concatenation of expressions, a function
+/// call whose arguments are expressions, or a `case`.
+pub fn eval_expression(node: &Node, fields: &HashMap<String, Field>, row: &Row) -> Result<Value, ExpressionError> {
+    if node.root == "case" {
+        return eval_case(node, fields, row);
+    }
+    if !node.args.is_empty() {
+        let mut args = vec![];
+        for arg in node.args.iter() {
+            args.push(eval_expression(arg, fields, row)?);
+        }
+        return call_function(&node.root, args);
+    }
+    match (node.root.as_str(), &node.left, &node.right) {
+        (operator, Some(left), Some(right)) if is_arithmetic(operator) => {
+            let left = eval_expression(left, fields, row)?;
+            let right = eval_expression(right, fields, row)?;
+            eval_arithmetic(operator, left, right)
+        }
+        ("||", Some(left), Some(right)) => match (
+            eval_expression(left, fields, row)?,
+            eval_expression(right, fields, row)?,
+        ) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (left, right) => Ok(Value::Str(format!("{}{}", left, right))),
+        },
+        (name, None, None) => match fields.get(name) {
+            Some(field) => get_field_value(name, &field.datatype, row),
+            None => parse_literal(name),
+        },
+        (root, _, _) => Err(ExpressionError::TypeMismatched(root.to_string())),
+    }
+}
+
+/// Infer the datatype of the expression on the fields, `None` if it is unknown such as of a null
+///
+/// The arguments of function calls are checked by the function registry. Numbers of different
+/// types are regarded as doubles.
+pub fn infer_datatype(node: &Node, fields: &HashMap<String, Field>) -> Result<Option<DataType>, ExpressionError> {
+    let infer_all = |nodes: Vec<&Node>| -> Result<Vec<Option<DataType>>, ExpressionError> {
+        nodes.into_iter().map(|node| infer_datatype(node, fields)).collect()
+    };
+    if node.root == "case" {
+        // the results are after each condition, and the last one is the else result
+        let results = node
+            .args
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 1 || *i == node.args.len() - 1)
+            .map(|(_, result)| result)
+            .collect();
+        return Ok(function::common_type(&infer_all(results)?));
+    }
+    if !node.args.is_empty() {
+        return function::get(&node.root)?.check_args(&infer_all(node.args.iter().collect())?);
+    }
+    match (node.root.as_str(), &node.left, &node.right) {
+        (operator, Some(left), Some(right)) if is_arithmetic(operator) => {
+            let datatypes = infer_all(vec![left, right])?;
+            if datatypes.iter().flatten().any(|datatype| !datatype.is_numeric()) {
+                return Err(ExpressionError::TypeMismatched(operator.to_string()));
+            }
+            Ok(function::common_type(&datatypes))
+        }
+        ("||", Some(left), Some(right)) => {
+            infer_all(vec![left, right])?;
+            Ok(Some(DataType::Varchar(255)))
+        }
+        (name, None, None) if node.subquery.is_some() => {
+            debug!("the datatype of subquery {} is unknown", name);
+            Ok(None)
+        }
+        (name, None, None) => match fields.get(name) {
+            Some(field) => Ok(Some(field.datatype.clone())),
+            None => match parse_literal(name)? {
+                Value::Int(_) => Ok(Some(DataType::Int)),
+                Value::Double(_) => Ok(Some(DataType::Double)),
+                Value::Str(_) => Ok(Some(DataType::Varchar(255))),
+                Value::Null => Ok(None),
+            },
+        },
+        (root, _, _) => Err(ExpressionError::TypeMismatched(root.to_string())),
+    }
+}
+
+/// the result of the first branch whose condition is true, or the else result if there is, or null
+///
+/// The arguments of a `case` are the conditions and the results in pairs, then the else result.
+fn eval_case(node: &Node, fields: &HashMap<String, Field>, row: &Row) -> Result<Value, ExpressionError> {
+    for branch in node.args.chunks(2) {
+        match branch {
+            [condition, result] if eval_predicate(condition, fields, row)? == Some(true) => {
+                return eval_expression(result, fields, row);
+            }
+            [otherwise] => return eval_expression(otherwise, fields, row),
+            _ => {}
+        }
+    }
+    Ok(Value::Null)
+}
+
+/// Compare two values, `None` if any of them is null
+///
+/// Numbers are compared as numbers, and a string is regarded as a number when compared with a number.
+pub fn compare_values(left: &Value, operator: &Operator, right: &Value) -> Result<Option<bool>, ExpressionError> {
+    let ordering = match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => return Ok(None),
+        (Value::Int(l), Value::Int(r)) => l.cmp(r),
+        (Value::Str(l), Value::Str(r)) => l.cmp(r),
+        (l, r) => l
+            .to_double()?
+            .partial_cmp(&r.to_double()?)
+            .ok_or(ExpressionError::TypeMismatched(format!(
+                "{} {} {}",
+                l,
+                operator.as_str(),
+                r
+            )))?,
+    };
+    let result = match *operator {
+        Operator::LT => ordering == Ordering::Less,
+        Operator::LE => ordering != Ordering::Greater,
+        Operator::EQ => ordering == Ordering::Equal,
+        Operator::NE => ordering != Ordering::Equal,
+        Operator::GT => ordering == Ordering::Greater,
+        Operator::GE => ordering != Ordering::Less,
+    };
+    Ok(Some(result))
+}
+
+/// match the value with the pattern, `None` if any of them is null
+///
+/// The pattern could be `<pattern> escape <character>`, where the character makes the following
+/// `%` or `_` a normal character.
+fn eval_like(
+    node: &Node,
+    pattern: &Node,
+    fields: &HashMap<String, Field>,
+    row: &Row,
+) -> Result<Option<bool>, ExpressionError> {
+    let (pattern, escape) = match (pattern.root.as_str(), &pattern.left, &pattern.right) {
+        ("escape", Some(pattern), Some(escape)) => (pattern.as_ref(), Some(escape)),
+        _ => (pattern, None),
+    };
+    let value = eval_expression(node, fields, row)?;
+    let pattern = eval_expression(pattern, fields, row)?;
+    let escape = match escape {
+        Some(escape) => match eval_expression(escape, fields, row)? {
+            Value::Null => return Ok(None),
+            escape => {
+                let escape = escape.to_string();
+                let mut chars = escape.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => return Err(ExpressionError::IllegalPattern(format!("escape '{}'", escape))),
+                }
+            }
+        },
+        None => None,
+    };
+    if value == Value::Null || pattern == Value::Null {
+        return Ok(None);
+    }
+    let wildcards = parse_pattern(&pattern.to_string(), escape)?;
+    Ok(Some(is_like(&value.to_string(), &wildcards)))
+}
+
+#[derive(Debug, PartialEq)]
+enum Wildcard {
+    Many, // %
+    One,  // _
+    Char(char),
+}
+
+fn parse_pattern(pattern: &str, escape: Option<char>) -> Result<Vec<Wildcard>, ExpressionError> {
+    let mut wildcards = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let wildcard = match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(c) => Wildcard::Char(c),
+                None => {
+                    return Err(ExpressionError::IllegalPattern(format!(
+                        "'{}' ends with escape",
+                        pattern
+                    )))
+                }
+            },
+            '%' => Wildcard::Many,
+            '_' => Wildcard::One,
+            c => Wildcard::Char(c),
+        };
+        wildcards.push(wildcard);
+    }
+    Ok(wildcards)
+}
+
+/// if the whole text matches the wildcards
+fn is_like(text: &str, wildcards: &[Wildcard]) -> bool {
+    // matched[j] is true if the text scanned matches the first j wildcards
+    let mut matched = vec![false; wildcards.len() + 1];
+    matched[0] = true;
+    for (j, wildcard) in wildcards.iter().enumerate() {
+        matched[j + 1] = matched[j] && *wildcard == Wildcard::Many;
+    }
+    for c in text.chars() {
+        let mut next = vec![false; wildcards.len() + 1];
+        for (j, wildcard) in wildcards.iter().enumerate() {
+            next[j + 1] = match *wildcard {
+                Wildcard::Many => next[j] || matched[j + 1],
+                Wildcard::One => matched[j],
+                Wildcard::Char(x) => matched[j] && x == c,
+            };
+        }
+        matched = next;
+    }
+    matched[wildcards.len()]
+}
+
+#[inline]
+fn is_arithmetic(operator: &str) -> bool {
+    operator == "+" || operator == "-" || operator == "*" || operator == "/"
+}
+
+/// the value of the field in the row, parsed by the datatype
+fn get_field_value(name: &str, datatype: &DataType, row: &Row) -> Result<Value, ExpressionError> {
+    // a null is absent from the row
+    let data = match row.data.get(name) {
+        Some(data) => data,
+        None => return Ok(Value::Null),
+    };
+    let illegal = || ExpressionError::IllegalValue(data.to_string());
+    match datatype {
+        DataType::Int => Ok(Value::Int(data.parse::<i64>().map_err(|_| illegal())?)),
+        DataType::Float | DataType::Double => Ok(Value::Double(data.parse::<f64>().map_err(|_| illegal())?)),
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Url => Ok(Value::Str(data.to_string())),
+    }
+}
+
+/// If the name is a literal rather than a field
+pub fn is_literal(name: &str) -> bool {
+    parse_literal(name).is_ok()
+}
+
+/// parse a literal, which is a quoted string, `null` or a number
+pub fn parse_literal(literal: &str) -> Result<Value, ExpressionError> {
+    if literal.len() >= 2 && literal.starts_with('\'') && literal.ends_with('\'') {
+        return Ok(Value::Str(literal[1..literal.len() - 1].to_string()));
+    }
+    if literal == "null" {
+        return Ok(Value::Null);
+    }
+    if let Ok(i) = literal.parse::<i64>() {
+        return Ok(Value::Int(i));
+    }
+    if let Ok(d) = literal.parse::<f64>() {
+        return Ok(Value::Double(d));
+    }
+    // not quoted, so it should be a field
+    Err(ExpressionError::FieldNotExisted(literal.to_string()))
+}
+
+fn eval_arithmetic(operator: &str, left: Value, right: Value) -> Result<Value, ExpressionError> {
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Int(l), Value::Int(r)) => {
+            let result = match operator {
+                "+" => l.checked_add(r),
+                "-" => l.checked_sub(r),
+                "*" => l.checked_mul(r),
+                _ if r == 0 => return Err(ExpressionError::DivideByZero),
+                _ => l.checked_div(r),
+            };
+            result.map(Value::Int).ok_or(ExpressionError::Overflow)
+        }
+        (l, r) => {
+            let (l, r) = (l.to_double()?, r.to_double()?);
+            let result = match operator {
+                "+" => l + r,
+                "-" => l - r,
+                "*" => l * r,
+                _ if r == 0.0 => return Err(ExpressionError::DivideByZero),
+                _ => l / r,
+            };
+            Ok(Value::Double(result))
+        }
+    }
+}
+
+/// call the scalar function in the function registry
+fn call_function(name: &str, args: Vec<Value>) -> Result<Value, ExpressionError> {
+    function::get(name)?.call(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_fields() -> HashMap<String, Field> {
+        let mut fields = HashMap::new();
+        fields.insert("a1".to_string(), Field::new("a1", DataType::Int));
+        fields.insert("a2".to_string(), Field::new("a2", DataType::Varchar(10)));
+        fields.insert("a3".to_string(), Field::new("a3", DataType::Double));
+        fields
+    }
+
+    fn fake_row(values: Vec<(&str, &str)>) -> Row {
+        let mut row = Row::new();
+        for (name, value) in values {
+            row.data.insert(name.to_string(), value.to_string());
+        }
+        row
+    }
+
+    fn leaf(root: &str) -> Node {
+        Node::new(root.to_string())
+    }
+
+    fn binary(root: &str, left: Node, right: Node) -> Node {
+        Node::new(root.to_string()).left(left).right(right)
+    }
+
+    #[test]
+    fn test_eval_expression() {
+        let fields = fake_fields();
+        let row = fake_row(vec![("a1", "7"), ("a2", "abc"), ("a3", "1.5")]);
+
+        // a1 * 2 - 3
+        let node = binary("-", binary("*", leaf("a1"), leaf("2")), leaf("3"));
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Int(11));
+
+        // a1 / 2 is an integer division, a1 + a3 is a double
+        let node = binary("/", leaf("a1"), leaf("2"));
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Int(3));
+        let node = binary("+", leaf("a1"), leaf("a3"));
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Double(8.5));
+
+        let node = leaf("length").args(vec![leaf("a2")]);
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Int(3));
+        let node = leaf("abs").args(vec![binary("-", leaf("a3"), leaf("2"))]);
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Double(0.5));
+
+        assert_eq!(
+            eval_expression(&leaf("'a1'"), &fields, &row).unwrap(),
+            Value::Str("a1".to_string())
+        );
+
+        println!("errors");
+        assert!(eval_expression(&binary("+", leaf("a1"), leaf("a2")), &fields, &row).is_err());
+        assert!(eval_expression(&binary("/", leaf("a1"), leaf("0")), &fields, &row).is_err());
+        assert!(eval_expression(&leaf("a9"), &fields, &row).is_err());
+        assert!(eval_expression(&leaf("abs").args(vec![leaf("a2")]), &fields, &row).is_err());
+        assert!(eval_expression(&leaf("foo").args(vec![leaf("a1")]), &fields, &row).is_err());
+        let row = fake_row(vec![("a1", "x")]);
+        assert!(eval_expression(&leaf("a1"), &fields, &row).is_err());
+    }
+
+    #[test]
+    fn test_eval_case_concat() {
+        let fields = fake_fields();
+        let row = fake_row(vec![("a1", "7"), ("a2", "abc")]);
+
+        // case when a1 > 5 then 'big' when a1 > 0 then 'small' end
+        let case = leaf("case").args(vec![
+            binary(">", leaf("a1"), leaf("5")),
+            leaf("'big'"),
+            binary(">", leaf("a1"), leaf("0")),
+            leaf("'small'"),
+        ]);
+        assert_eq!(
+            eval_expression(&case, &fields, &row).unwrap(),
+            Value::Str("big".to_string())
+        );
+        let row = fake_row(vec![("a1", "-1")]);
+        assert_eq!(eval_expression(&case, &fields, &row).unwrap(), Value::Null);
+
+        // case when a3 > 1 then a1 else 0.5 end, whose condition is unknown on null
+        let case = leaf("case").args(vec![binary(">", leaf("a3"), leaf("1")), leaf("a1"), leaf("0.5")]);
+        assert_eq!(eval_expression(&case, &fields, &row).unwrap(), Value::Double(0.5));
+        assert_eq!(infer_datatype(&case, &fields).unwrap(), Some(DataType::Double));
+
+        let row = fake_row(vec![("a1", "7"), ("a2", "abc")]);
+        let node = binary("||", leaf("a2"), leaf("a1"));
+        assert_eq!(
+            eval_expression(&node, &fields, &row).unwrap(),
+            Value::Str("abc7".to_string())
+        );
+        let node = binary("||", leaf("a2"), leaf("a3"));
+        assert_eq!(eval_expression(&node, &fields, &row).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_infer_datatype() {
+        let fields = fake_fields();
+        let infer = |node: &Node| infer_datatype(node, &fields).unwrap();
+        assert_eq!(infer(&binary("*", leaf("a1"), leaf("2"))), Some(DataType::Int));
+        assert_eq!(infer(&binary("+", leaf("a1"), leaf("a3"))), Some(DataType::Double));
+        assert_eq!(
+            infer(&leaf("upper").args(vec![leaf("a2")])),
+            Some(DataType::Varchar(10))
+        );
+        assert_eq!(infer(&leaf("length").args(vec![leaf("'xyz'")])), Some(DataType::Int));
+        assert_eq!(
+            infer(&leaf("coalesce").args(vec![leaf("null"), leaf("a1")])),
+            Some(DataType::Int)
+        );
+        assert_eq!(infer(&leaf("null")), None);
+
+        println!("errors");
+        assert!(infer_datatype(&binary("+", leaf("a1"), leaf("a2")), &fields).is_err());
+        assert!(infer_datatype(&leaf("upper").args(vec![leaf("a1")]), &fields).is_err());
+        assert!(infer_datatype(&leaf("round").args(vec![leaf("a3"), leaf("1"), leaf("1")]), &fields).is_err());
+        assert!(infer_datatype(&leaf("foo").args(vec![leaf("a1")]), &fields).is_err());
+        assert!(infer_datatype(&leaf("a9"), &fields).is_err());
+    }
+
+    #[test]
+    fn test_eval_predicate() {
+        let fields = fake_fields();
+        // a3 is null
+        let row = fake_row(vec![("a1", "7"), ("a2", "abc")]);
+
+        // a1 + 1 > a1 and a2 = 'abc'
+        let node = binary(
+            "and",
+            binary(">", binary("+", leaf("a1"), leaf("1")), leaf("a1")),
+            binary("=", leaf("a2"), leaf("'abc'")),
+        );
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+
+        // a1 = '7' compares as numbers
+        let node = binary("=", leaf("a1"), leaf("'7'"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+
+        println!("null is unknown");
+        let node = binary("<", leaf("a3"), leaf("1"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+        let node = binary(
+            "or",
+            binary("<", leaf("a3"), leaf("1")),
+            binary("<", leaf("a1"), leaf("1")),
+        );
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+        let node = Node::new("not".to_string()).right(binary("<", leaf("a3"), leaf("1")));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+        let node = binary(
+            "and",
+            binary("<", leaf("a3"), leaf("1")),
+            binary("<", leaf("a1"), leaf("1")),
+        );
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(false));
+
+        println!("is null is never unknown");
+        let node = Node::new("is null".to_string()).left(leaf("a3"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = Node::new("is not null".to_string()).left(binary("+", leaf("a3"), leaf("1")));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(false));
+        let node = Node::new("is not null".to_string()).left(leaf("a1"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+
+        println!("errors");
+        assert!(eval_predicate(&binary("=", leaf("a1"), leaf("a2")), &fields, &row).is_err());
+        assert!(eval_predicate(&binary("+", leaf("a1"), leaf("1")), &fields, &row).is_err());
+    }
+
+    #[test]
+    fn test_is_like() {
+        let is = |text: &str, pattern: &str| is_like(text, &parse_pattern(pattern, Some('!')).unwrap());
+        assert!(is("abc", "abc"));
+        assert!(is("abc", "a%"));
+        assert!(is("abc", "%c"));
+        assert!(is("abc", "%%b%"));
+        assert!(is("abc", "a_c"));
+        assert!(is("", "%"));
+        assert!(is("a%c", "a!%c"));
+        assert!(!is("abc", "a!%c"));
+        assert!(!is("abc", "ab"));
+        assert!(!is("abc", "a_"));
+        assert!(!is("", "_"));
+        assert!(parse_pattern("a!", Some('!')).is_err());
+    }
+
+    #[test]
+    fn test_eval_like_in_between() {
+        let fields = fake_fields();
+        // a3 is null
+        let row = fake_row(vec![("a1", "7"), ("a2", "a_c")]);
+
+        let node = binary("like", leaf("a2"), leaf("'a%'"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = binary("not like", leaf("a2"), leaf("'%b%'"));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = binary("like", leaf("a2"), binary("escape", leaf("'a#_%'"), leaf("'#'")));
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = binary("like", leaf("a2"), binary("escape", leaf("'a#__'"), leaf("'##'")));
+        assert!(eval_predicate(&node, &fields, &row).is_err());
+
+        let node = leaf("in").left(leaf("a1")).args(vec![leaf("1"), leaf("7")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = leaf("not in").left(leaf("a1")).args(vec![leaf("1"), leaf("2")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = leaf("in").left(leaf("a1")).args(vec![leaf("1"), leaf("a3")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+        let node = leaf("in").left(leaf("a3")).args(vec![leaf("1"), leaf("7")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+
+        let node = leaf("between").left(leaf("a1")).args(vec![leaf("7"), leaf("8")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = leaf("not between").left(leaf("a1")).args(vec![leaf("1"), leaf("5")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(true));
+        let node = leaf("between").left(leaf("a1")).args(vec![leaf("a3"), leaf("5")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), Some(false));
+        let node = leaf("between").left(leaf("a1")).args(vec![leaf("a3"), leaf("8")]);
+        assert_eq!(eval_predicate(&node, &fields, &row).unwrap(), None);
+    }
+}
@@ -28,6 +28,32 @@ pub enum Operator {
     GE, // >=
 }
 
+impl Operator {
+    pub fn get(s: &str) -> Option<Operator> {
+        let o = match s {
+            "<" => Operator::LT,
+            "<=" => Operator::LE,
+            "=" => Operator::EQ,
+            "!=" | "<>" => Operator::NE,
+            ">" => Operator::GT,
+            ">=" => Operator::GE,
+            _ => return None,
+        };
+        Some(o)
+    }
+
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Operator::LT => "<",
+            Operator::LE => "<=",
+            Operator::EQ => "=",
+            Operator::NE => "!=",
+            Operator::GT => ">",
+            Operator::GE => ">=",
+        }
+    }
+}
+
 impl Field {
     pub fn new(name: &str, datatype: DataType) -> Field {
         Field {
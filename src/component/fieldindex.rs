@@ -0,0 +1,499 @@
+use crate::component::datatype::DataType;
+use crate::component::expression;
+use crate::component::expression::Value;
+use crate::component::field::Operator;
+use crate::component::table::{Row, Table, TableError};
+use crate::index::btree::{BPlusTree, RangeIter};
+use crate::index::tree::{NodeType, Tree};
+use crate::sql::query::Node;
+use crate::storage::bytescoder::BytesCoder;
+use crate::storage::diskinterface::{DiskError, DiskInterface};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+/// the rows where a predicate is true, and the rows where it is false
+pub type FilteredRows = (HashSet<usize>, HashSet<usize>);
+
+/// An index of a field on the rows of a table in memory
+///
+/// The key of a row is its value of the field in the order-preserving encoding of
+/// `BytesCoder::attr_to_key` followed by its row id, so the rows of the same value are different keys
+/// next to each other. The record pointers are the row ids. The deleted rows and the rows whose value is
+/// null are not in the index.
+pub struct FieldIndex {
+    tree: Box<BPlusTree<Vec<u8>>>,
+    key_type: DataType,   // numbers are compared as doubles, and the others as strings
+    value_size: usize,    // the size of a value in a key, before the row id
+    rows: HashSet<usize>, // all the rows in the index
+}
+
+/// The indexes of the fields built on the rows of a table, `None` for a field which can't be indexed
+///
+/// The copies of the table with the same rows, such as the qualified tables of queries, share the
+/// indexes, so an index is built once. A table with changed rows updates its indexes with the rows,
+/// unless they are shared with the copies, which keep the ones of their rows.
+#[derive(Clone, Default)]
+pub struct FieldIndexes(Arc<Mutex<HashMap<String, Option<Arc<FieldIndex>>>>>);
+
+/// the size of the row id at the end of a key
+const ROW_ID_SIZE: usize = 4;
+
+/// the values where a predicate on a field is true
+enum KeyRange {
+    Between(Bound<Value>, Bound<Value>),
+    Prefix(String),
+}
+
+impl FieldIndex {
+    /// build the index of the field on the rows of the table
+    ///
+    /// Return `None` if any of the values can't be compared.
+    pub fn build(table: &Table, name: &str) -> Result<Option<FieldIndex>, TableError> {
+        let field = table
+            .fields
+            .get(name)
+            .ok_or(TableError::SelectFieldNotExisted(name.to_string()))?;
+        let key_type = match field.datatype {
+            DataType::Int | DataType::Float | DataType::Double => DataType::Double,
+            ref datatype => datatype.clone(),
+        };
+        let value_size = DiskInterface::get_datatype_size(&key_type) as usize;
+        let mut tree = BPlusTree::new(
+            0,
+            NodeType::Leaf,
+            key_type.clone(),
+            4,
+            value_size + ROW_ID_SIZE,
+            Some(8),
+        );
+        let mut rows = HashSet::new();
+        for i in table.get_all_rows_set() {
+            let value = match table.rows[i].data.get(name) {
+                Some(value) => value,
+                None => continue,
+            };
+            let key = match row_key(&key_type, value, i) {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            tree.insert(key, (i as u32, 0)).map_err(TableError::CausedByFile)?;
+            rows.insert(i);
+        }
+        Ok(Some(FieldIndex {
+            tree,
+            key_type,
+            value_size,
+            rows,
+        }))
+    }
+
+    /// remove the rows from the index and add the other rows to it, such as the old and the new versions of
+    /// the updated rows
+    ///
+    /// Return `false` if any of the rows can't be in the index, which is no longer valid then.
+    fn update(&mut self, name: &str, removed: &[(usize, &Row)], added: &[(usize, &Row)]) -> bool {
+        for (i, row) in removed {
+            if !self.rows.remove(i) {
+                continue;
+            }
+            let key = match row.data.get(name).and_then(|value| row_key(&self.key_type, value, *i)) {
+                Some(key) => key,
+                None => return false,
+            };
+            if self.tree.delete(key).is_err() {
+                return false;
+            }
+        }
+        for (i, row) in added {
+            if row.is_delete {
+                continue;
+            }
+            let value = match row.data.get(name) {
+                Some(value) => value,
+                None => continue,
+            };
+            let key = match row_key(&self.key_type, value, *i) {
+                Some(key) => key,
+                None => return false,
+            };
+            if self.tree.insert(key, (*i as u32, 0)).is_err() {
+                return false;
+            }
+            self.rows.insert(*i);
+        }
+        true
+    }
+
+    /// filter the rows by the predicate on the field, and return the rows where it is true and the rows
+    /// where it is false
+    ///
+    /// Return `None` if the predicate is not a range of literals on the field, which could be of
+    /// `<`, `<=`, `=`, `!=`, `>`, `>=`, `[not] between` and `[not] like` with a prefix pattern.
+    pub fn filter_rows(&self, predicate: &Node) -> Result<Option<FilteredRows>, TableError> {
+        let (range, is_negated) = match parse_range(predicate) {
+            Some((_, range, is_negated)) => (range, is_negated),
+            None => return Ok(None),
+        };
+        let matched = match range {
+            KeyRange::Between(low, high) => {
+                let (low, high) = match (self.key_bound(low, true), self.key_bound(high, false)) {
+                    (Some(low), Some(high)) => (low, high),
+                    _ => return Ok(None),
+                };
+                collect_rows(self.tree.range(low, high))?
+            }
+            KeyRange::Prefix(_) if self.key_type == DataType::Double => return Ok(None),
+            // a prefix longer than the field would run into the row ids
+            KeyRange::Prefix(prefix) if prefix.len() > self.value_size => HashSet::new(),
+            // the padding of the values is after the prefix
+            KeyRange::Prefix(prefix) => collect_rows(self.tree.prefix(prefix.into_bytes()))?,
+        };
+        let unmatched: HashSet<usize> = self.rows.difference(&matched).cloned().collect();
+        if is_negated {
+            Ok(Some((unmatched, matched)))
+        } else {
+            Ok(Some((matched, unmatched)))
+        }
+    }
+
+    /// the lower or upper bound of keys of the bound of values, `None` if the value is compared with the
+    /// field in another way, such as a number with a string
+    ///
+    /// A value is less than the keys of it, and the keys of it are less than the value followed by the
+    /// greatest row id.
+    fn key_bound(&self, bound: Bound<Value>, is_lower: bool) -> Option<Bound<Vec<u8>>> {
+        let (value, is_included) = match bound {
+            Bound::Included(value) => (value, true),
+            Bound::Excluded(value) => (value, false),
+            Bound::Unbounded => return Some(Bound::Unbounded),
+        };
+        let (mut key, is_included) = match (value, &self.key_type) {
+            (Value::Int(i), DataType::Double) => (BytesCoder::double_to_key(i as f64), is_included),
+            (Value::Double(d), DataType::Double) if !d.is_nan() => (BytesCoder::double_to_key(d), is_included),
+            (Value::Str(s), key_type) if *key_type != DataType::Double => match BytesCoder::attr_to_key(key_type, &s) {
+                Ok(key) => (key, is_included),
+                // a string longer than the field is greater than its prefix of the size, and less than
+                // the values greater than the prefix
+                Err(_) => (s.as_bytes()[..self.value_size].to_vec(), !is_lower),
+            },
+            _ => return None,
+        };
+        if is_included != is_lower {
+            key.extend_from_slice(&[0xff; ROW_ID_SIZE]);
+        }
+        if is_included {
+            Some(Bound::Included(key))
+        } else {
+            Some(Bound::Excluded(key))
+        }
+    }
+}
+
+impl FieldIndexes {
+    /// the index of the field kept by the name, which is built on the rows of the table on the first use
+    pub fn get(&self, table: &Table, key: &str, name: &str) -> Result<Option<Arc<FieldIndex>>, TableError> {
+        let mut indexes = self.0.lock().unwrap();
+        if !indexes.contains_key(key) {
+            let index = FieldIndex::build(table, name)?.map(Arc::new);
+            indexes.insert(key.to_string(), index);
+        }
+        Ok(indexes[key].clone())
+    }
+
+    /// update the indexes built, by removing the rows and adding the other rows
+    ///
+    /// The indexes shared with the copies of the table are dropped instead, and so is an index which
+    /// can't be updated, so they are built again on the next use.
+    pub fn update(&mut self, removed: &[(usize, &Row)], added: &[(usize, &Row)]) {
+        if Arc::strong_count(&self.0) > 1 {
+            *self = FieldIndexes::default();
+            return;
+        }
+        let mut indexes = self.0.lock().unwrap();
+        indexes.retain(|name, index| match index.as_mut().and_then(Arc::get_mut) {
+            Some(index) => index.update(name, removed, added),
+            None => false,
+        });
+    }
+}
+
+impl fmt::Debug for FieldIndexes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let indexes = self.0.lock().unwrap();
+        f.debug_list().entries(indexes.keys()).finish()
+    }
+}
+
+/// the key of the value of a row, which is `None` if the value can't be compared
+fn row_key(key_type: &DataType, value: &str, row_id: usize) -> Option<Vec<u8>> {
+    // NaN is neither less nor greater than any number, but it has a place in the order of keys
+    if *key_type == DataType::Double && value.parse::<f64>().is_ok_and(|d| d.is_nan()) {
+        return None;
+    }
+    let mut key = BytesCoder::attr_to_key(key_type, value).ok()?;
+    key.extend_from_slice(&(row_id as u32).to_be_bytes());
+    Some(key)
+}
+
+/// the field of the predicate if it is a range of literals on the field
+pub fn predicate_field(predicate: &Node) -> Option<&str> {
+    parse_range(predicate).map(|(name, _, _)| name)
+}
+
+fn collect_rows(iter: Result<RangeIter<'_, Vec<u8>>, DiskError>) -> Result<HashSet<usize>, TableError> {
+    let mut rows = HashSet::new();
+    for entry in iter.map_err(TableError::CausedByFile)? {
+        let (_, row) = entry.map_err(TableError::CausedByFile)?;
+        rows.insert(row.0 as usize);
+    }
+    Ok(rows)
+}
+
+/// the field, the range and if the range is negated, of a predicate of literals on a field
+fn parse_range(predicate: &Node) -> Option<(&str, KeyRange, bool)> {
+    let root = predicate.root.as_str();
+    match root {
+        "between" | "not between" if predicate.args.len() == 2 => {
+            let name = field_name(predicate.left.as_deref()?)?;
+            let low = literal(&predicate.args[0])?;
+            let high = literal(&predicate.args[1])?;
+            Some((
+                name,
+                KeyRange::Between(Bound::Included(low), Bound::Included(high)),
+                root == "not between",
+            ))
+        }
+        "like" | "not like" => {
+            let name = field_name(predicate.left.as_deref()?)?;
+            let prefix = match literal(predicate.right.as_deref()?)? {
+                Value::Str(pattern) => like_prefix(&pattern)?,
+                _ => return None,
+            };
+            Some((name, prefix, root == "not like"))
+        }
+        _ => {
+            let (left, right) = (predicate.left.as_deref()?, predicate.right.as_deref()?);
+            let operator = Operator::get(root)?;
+            // the field is always on the left, such as `1 < a1` is `a1 > 1`
+            let (name, value, operator) = match (field_name(left), field_name(right)) {
+                (Some(name), None) => (name, literal(right)?, operator),
+                (None, Some(name)) => (name, literal(left)?, flip(operator)),
+                _ => return None,
+            };
+            let (range, is_negated) = match operator {
+                Operator::LT => (KeyRange::Between(Bound::Unbounded, Bound::Excluded(value)), false),
+                Operator::LE => (KeyRange::Between(Bound::Unbounded, Bound::Included(value)), false),
+                Operator::GT => (KeyRange::Between(Bound::Excluded(value), Bound::Unbounded), false),
+                Operator::GE => (KeyRange::Between(Bound::Included(value), Bound::Unbounded), false),
+                Operator::EQ => (
+                    KeyRange::Between(Bound::Included(value.clone()), Bound::Included(value)),
+                    false,
+                ),
+                Operator::NE => (
+                    KeyRange::Between(Bound::Included(value.clone()), Bound::Included(value)),
+                    true,
+                ),
+            };
+            Some((name, range, is_negated))
+        }
+    }
+}
+
+/// the name of a node which is a field, whose name is neither a literal nor an expression
+fn field_name(node: &Node) -> Option<&str> {
+    if is_leaf(node) && !expression::is_literal(&node.root) {
+        Some(&node.root)
+    } else {
+        None
+    }
+}
+
+/// the value of a node which is a literal other than null
+fn literal(node: &Node) -> Option<Value> {
+    if !is_leaf(node) {
+        return None;
+    }
+    match expression::parse_literal(&node.root) {
+        Ok(Value::Null) | Err(_) => None,
+        Ok(value) => Some(value),
+    }
+}
+
+fn is_leaf(node: &Node) -> bool {
+    node.left.is_none() && node.right.is_none() && node.args.is_empty() && node.subquery.is_none()
+}
+
+/// the range of a pattern which is a prefix followed by `%`, or a value without any wildcard
+fn like_prefix(pattern: &str) -> Option<KeyRange> {
+    let prefix = pattern.trim_end_matches('%');
+    if prefix.contains('%') || prefix.contains('_') {
+        return None;
+    }
+    if prefix.len() == pattern.len() {
+        let value = Value::Str(prefix.to_string());
+        Some(KeyRange::Between(
+            Bound::Included(value.clone()),
+            Bound::Included(value),
+        ))
+    } else {
+        Some(KeyRange::Prefix(prefix.to_string()))
+    }
+}
+
+fn flip(operator: Operator) -> Operator {
+    match operator {
+        Operator::LT => Operator::GT,
+        Operator::LE => Operator::GE,
+        Operator::GT => Operator::LT,
+        Operator::GE => Operator::LE,
+        operator => operator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::field;
+    use crate::component::field::Field;
+
+    fn leaf(root: &str) -> Node {
+        Node::new(root.to_string())
+    }
+
+    fn binary(root: &str, left: Node, right: Node) -> Node {
+        leaf(root).left(left).right(right)
+    }
+
+    fn fake_table() -> Table {
+        let mut table = Table::new("t1");
+        table.insert_new_field(Field::new_all(
+            "a1",
+            DataType::Int,
+            true,
+            None,
+            field::Checker::None,
+            false,
+        ));
+        table.insert_new_field(Field::new_all(
+            "a2",
+            DataType::Varchar(10),
+            false,
+            None,
+            field::Checker::None,
+            false,
+        ));
+        for (a1, a2) in [
+            (-3, Some("ab")),
+            (7, Some("abc")),
+            (0, Some("b")),
+            (12, None),
+            (5, Some("abd")),
+            (-10, Some("a")),
+            (9, Some("ba")),
+        ]
+        .iter()
+        {
+            let a1 = a1.to_string();
+            table.insert_row(vec![("a1", Some(&a1)), ("a2", *a2)]).unwrap();
+        }
+        let deleted: HashSet<usize> = [4].iter().cloned().collect();
+        table.delete_rows(&deleted);
+        table
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let table = fake_table();
+        let numbers = FieldIndex::build(&table, "a1").unwrap().unwrap();
+        let texts = FieldIndex::build(&table, "a2").unwrap().unwrap();
+        let predicates = vec![
+            binary(">", leaf("a1"), leaf("0")),
+            binary(">=", leaf("a1"), leaf("-3")),
+            binary("<", leaf("a1"), leaf("7.5")),
+            binary("<=", leaf("a1"), leaf("5")),
+            binary("=", leaf("a1"), leaf("9")),
+            binary("!=", leaf("a1"), leaf("9")),
+            binary("<", leaf("0"), leaf("a1")),
+            leaf("between").left(leaf("a1")).args(vec![leaf("-5"), leaf("7")]),
+            leaf("not between").left(leaf("a1")).args(vec![leaf("-5"), leaf("7")]),
+            leaf("between").left(leaf("a1")).args(vec![leaf("7"), leaf("-5")]),
+            binary(">", leaf("a2"), leaf("'ab'")),
+            binary("like", leaf("a2"), leaf("'ab%'")),
+            binary("not like", leaf("a2"), leaf("'ab%'")),
+            binary("like", leaf("a2"), leaf("'b'")),
+            binary("like", leaf("a2"), leaf("'%'")),
+        ];
+        for predicate in predicates.iter() {
+            let index = if predicate_field(predicate) == Some("a1") {
+                &numbers
+            } else {
+                &texts
+            };
+            let (set, false_set) = index.filter_rows(predicate).unwrap().unwrap();
+            assert_eq!(set, table.filter_rows(predicate).unwrap(), "{:?}", predicate);
+            let negation = leaf("not").right(predicate.clone());
+            assert_eq!(false_set, table.filter_rows(&negation).unwrap(), "{:?}", predicate);
+        }
+
+        // the rows whose value is null are neither true nor false
+        let (set, false_set) = texts
+            .filter_rows(&binary("<", leaf("a2"), leaf("'c'")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(set.len() + false_set.len(), 5);
+
+        // not a range of literals on the field
+        let predicates = [
+            binary(">", leaf("a1"), leaf("a2")),
+            binary(">", binary("+", leaf("a1"), leaf("1")), leaf("0")),
+            binary(">", leaf("a1"), leaf("null")),
+            binary(">", leaf("a1"), leaf("'1'")),
+            binary("like", leaf("a2"), leaf("'a_'")),
+            binary("like", leaf("a2"), leaf("'%b'")),
+        ];
+        for predicate in predicates.iter() {
+            let index = if predicate.left.as_ref().unwrap().root == "a2" {
+                &texts
+            } else {
+                &numbers
+            };
+            assert!(index.filter_rows(predicate).unwrap().is_none(), "{:?}", predicate);
+        }
+    }
+
+    #[test]
+    fn test_build_duplicated() {
+        let mut table = fake_table();
+        table.insert_row(vec![("a1", Some("-3")), ("a2", Some("ab"))]).unwrap();
+        table.insert_row(vec![("a1", Some("9")), ("a2", Some("abc"))]).unwrap();
+        // a value of the size of the field, which is a prefix of the longer literals
+        table
+            .insert_row(vec![("a1", Some("100")), ("a2", Some("abcdefghij"))])
+            .unwrap();
+        let numbers = FieldIndex::build(&table, "a1").unwrap().unwrap();
+        let texts = FieldIndex::build(&table, "a2").unwrap().unwrap();
+        let predicates = vec![
+            binary("=", leaf("a1"), leaf("-3")),
+            binary(">", leaf("a1"), leaf("-3")),
+            binary("<=", leaf("a1"), leaf("9")),
+            binary("<", leaf("a1"), leaf("9")),
+            binary("=", leaf("a2"), leaf("'ab'")),
+            binary(">", leaf("a2"), leaf("'ab'")),
+            binary("like", leaf("a2"), leaf("'ab%'")),
+            binary("<", leaf("a2"), leaf("'abcdefghijklmn'")),
+            binary(">", leaf("a2"), leaf("'abcdefghijklmn'")),
+            binary("like", leaf("a2"), leaf("'abcdefghijklmn%'")),
+        ];
+        for predicate in predicates.iter() {
+            let index = if predicate_field(predicate) == Some("a1") {
+                &numbers
+            } else {
+                &texts
+            };
+            let (set, _) = index.filter_rows(predicate).unwrap().unwrap();
+            assert_eq!(set, table.filter_rows(predicate).unwrap(), "{:?}", predicate);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use crate::component::datatype::DataType;
+use crate::component::expression::ExpressionError;
+use crate::component::expression::Value;
+use std::collections::HashMap;
+
+/// The kind of values an argument of a function accepts
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArgType {
+    Numeric, // int, float, double
+    Text,    // char, varchar, url
+    Any,
+}
+
+/// A built-in scalar function, which maps the values of its arguments to a value
+pub struct ScalarFunction {
+    pub name: &'static str,
+    pub args: Vec<ArgType>,  // the required arguments, then the optional ones
+    pub num_optional: usize, // number of the optional arguments at the end of `args`
+    pub is_variadic: bool,   // if the last argument could be repeated
+    pub is_strict: bool,     // if the result is null when any argument is null
+    return_type: fn(&[Option<DataType>]) -> Option<DataType>,
+    call: fn(&[Value]) -> Result<Value, ExpressionError>,
+}
+
+lazy_static! {
+    /// The registry of built-in scalar functions by name
+    pub static ref FUNCTIONS: HashMap<&'static str, ScalarFunction> = {
+        let mut m = HashMap::new();
+
+        // The following is maintained by hand according to the functions in `Token`
+
+        /* string functions */
+        m.insert("upper", strict("upper", vec![ArgType::Text], 0, first_type, |args| {
+            Ok(Value::Str(to_str(&args[0]).to_uppercase()))
+        }));
+        m.insert("lower", strict("lower", vec![ArgType::Text], 0, first_type, |args| {
+            Ok(Value::Str(to_str(&args[0]).to_lowercase()))
+        }));
+        m.insert("length", strict("length", vec![ArgType::Text], 0, |_| Some(DataType::Int), |args| {
+            Ok(Value::Int(to_str(&args[0]).chars().count() as i64))
+        }));
+        m.insert("trim", strict("trim", vec![ArgType::Text], 0, first_type, |args| {
+            Ok(Value::Str(to_str(&args[0]).trim().to_string()))
+        }));
+        m.insert("substr", strict("substr", vec![ArgType::Text, ArgType::Numeric, ArgType::Numeric], 1, first_type, substr));
+
+        /* numeric functions */
+        m.insert("abs", strict("abs", vec![ArgType::Numeric], 0, first_type, |args| match args[0] {
+            Value::Int(i) => Ok(Value::Int(i.checked_abs().ok_or(ExpressionError::Overflow)?)),
+            Value::Double(d) => Ok(Value::Double(d.abs())),
+            _ => Ok(Value::Null),
+        }));
+        m.insert("round", strict("round", vec![ArgType::Numeric, ArgType::Numeric], 1, first_type, round));
+
+        /* null functions */
+        m.insert("coalesce", ScalarFunction {
+            name: "coalesce",
+            args: vec![ArgType::Any],
+            num_optional: 0,
+            is_variadic: true,
+            is_strict: false,
+            return_type: common_type,
+            call: |args| Ok(args.iter().find(|arg| **arg != Value::Null).cloned().unwrap_or(Value::Null)),
+        });
+        m.insert("nullif", ScalarFunction {
+            name: "nullif",
+            args: vec![ArgType::Any, ArgType::Any],
+            num_optional: 0,
+            is_variadic: false,
+            is_strict: false,
+            return_type: first_type,
+            call: |args| match args[0] == args[1] && args[0] != Value::Null {
+                true => Ok(Value::Null),
+                false => Ok(args[0].clone()),
+            },
+        });
+
+        m
+    };
+}
+
+/// Get the function by name
+pub fn get(name: &str) -> Result<&'static ScalarFunction, ExpressionError> {
+    FUNCTIONS
+        .get(name)
+        .ok_or(ExpressionError::UnknownFunction(name.to_string()))
+}
+
+impl ScalarFunction {
+    /// Check the datatypes of the arguments, and return the datatype of the result
+    ///
+    /// A `None` datatype is unknown, such as of a null, which could be any argument.
+    pub fn check_args(&self, datatypes: &[Option<DataType>]) -> Result<Option<DataType>, ExpressionError> {
+        self.check_num_args(datatypes.len())?;
+        for (i, datatype) in datatypes.iter().enumerate() {
+            let is_accepted = match (self.arg_type(i), datatype) {
+                (ArgType::Numeric, Some(datatype)) => datatype.is_numeric(),
+                (ArgType::Text, Some(datatype)) => !datatype.is_numeric(),
+                (ArgType::Any, _) | (_, None) => true,
+            };
+            if !is_accepted {
+                return Err(ExpressionError::TypeMismatched(format!(
+                    "{:?} as argument {} of {}",
+                    datatype.as_ref().unwrap(),
+                    i + 1,
+                    self.name
+                )));
+            }
+        }
+        Ok((self.return_type)(datatypes))
+    }
+
+    /// Call the function with the values of the arguments
+    pub fn call(&self, args: Vec<Value>) -> Result<Value, ExpressionError> {
+        self.check_num_args(args.len())?;
+        for (i, arg) in args.iter().enumerate() {
+            let is_mismatched = matches!(
+                (self.arg_type(i), arg),
+                (ArgType::Numeric, Value::Str(_)) | (ArgType::Text, Value::Int(_)) | (ArgType::Text, Value::Double(_))
+            );
+            if is_mismatched {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                return Err(ExpressionError::TypeMismatched(format!(
+                    "{}({})",
+                    self.name,
+                    args.join(", ")
+                )));
+            }
+        }
+        if self.is_strict && args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        (self.call)(&args)
+    }
+
+    fn check_num_args(&self, num_args: usize) -> Result<(), ExpressionError> {
+        let num_required = self.args.len() - self.num_optional;
+        let is_too_many = !self.is_variadic && num_args > self.args.len();
+        if num_args < num_required || is_too_many {
+            return Err(ExpressionError::ArgumentMismatched(self.name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// the type of the argument at the position, the last one is repeated if the function is variadic
+    fn arg_type(&self, i: usize) -> ArgType {
+        self.args.get(i).cloned().unwrap_or(*self.args.last().unwrap())
+    }
+}
+
+fn strict(
+    name: &'static str,
+    args: Vec<ArgType>,
+    num_optional: usize,
+    return_type: fn(&[Option<DataType>]) -> Option<DataType>,
+    call: fn(&[Value]) -> Result<Value, ExpressionError>,
+) -> ScalarFunction {
+    ScalarFunction {
+        name,
+        args,
+        num_optional,
+        is_variadic: false,
+        is_strict: true,
+        return_type,
+        call,
+    }
+}
+
+fn first_type(datatypes: &[Option<DataType>]) -> Option<DataType> {
+    datatypes[0].clone()
+}
+
+/// the type of the first known argument, and numbers of different types are regarded as doubles
+pub fn common_type(datatypes: &[Option<DataType>]) -> Option<DataType> {
+    let mut common: Option<DataType> = None;
+    for datatype in datatypes.iter().flatten() {
+        common = match common {
+            None => Some(datatype.clone()),
+            Some(ref c) if c.is_numeric() && datatype.is_numeric() && c != datatype => Some(DataType::Double),
+            Some(c) => Some(c),
+        };
+    }
+    common
+}
+
+fn to_str(value: &Value) -> String {
+    value.to_string()
+}
+
+fn to_int(value: &Value) -> Result<i64, ExpressionError> {
+    match *value {
+        Value::Int(i) => Ok(i),
+        Value::Double(d) => Ok(d as i64),
+        _ => Err(ExpressionError::TypeMismatched(format!("{} is not a number", value))),
+    }
+}
+
+/// the substring from the 1-based position `start` with at most `length` characters
+///
+/// The characters before the first one are counted in `length`, such as `substr('abc', 0, 2)` is `a`.
+fn substr(args: &[Value]) -> Result<Value, ExpressionError> {
+    let text = to_str(&args[0]);
+    let start = to_int(&args[1])?;
+    let end = match args.get(2) {
+        Some(length) => {
+            let length = to_int(length)?;
+            if length < 0 {
+                return Err(ExpressionError::TypeMismatched(format!(
+                    "negative length {} of substr",
+                    length
+                )));
+            }
+            start.saturating_add(length)
+        }
+        None => i64::MAX,
+    };
+    let result: String = text
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| (*i as i64) + 1 >= start && (*i as i64) + 1 < end)
+        .map(|(_, c)| c)
+        .collect();
+    Ok(Value::Str(result))
+}
+
+/// round the number to `digits` decimal places, which could be negative such as to tens by -1
+fn round(args: &[Value]) -> Result<Value, ExpressionError> {
+    let digits = match args.get(1) {
+        Some(digits) => to_int(digits)?,
+        None => 0,
+    };
+    let factor = 10f64.powi(digits.clamp(-18, 18) as i32);
+    match args[0] {
+        Value::Int(i) if digits >= 0 => Ok(Value::Int(i)),
+        Value::Int(i) => Ok(Value::Int(((i as f64 * factor).round() / factor) as i64)),
+        Value::Double(d) => Ok(Value::Double((d * factor).round() / factor)),
+        _ => Ok(Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: Vec<Value>) -> Result<Value, ExpressionError> {
+        get(name)?.call(args)
+    }
+
+    #[test]
+    fn test_call_functions() {
+        let s = |s: &str| Value::Str(s.to_string());
+        assert_eq!(call("upper", vec![s("aBc")]).unwrap(), s("ABC"));
+        assert_eq!(call("trim", vec![s("  a b ")]).unwrap(), s("a b"));
+        assert_eq!(call("substr", vec![s("abcde"), Value::Int(2)]).unwrap(), s("bcde"));
+        assert_eq!(
+            call("substr", vec![s("abcde"), Value::Int(2), Value::Int(2)]).unwrap(),
+            s("bc")
+        );
+        assert_eq!(
+            call("substr", vec![s("abc"), Value::Int(0), Value::Int(2)]).unwrap(),
+            s("a")
+        );
+        assert_eq!(
+            call("round", vec![Value::Double(2.567), Value::Int(2)]).unwrap(),
+            Value::Double(2.57)
+        );
+        assert_eq!(call("round", vec![Value::Double(-2.5)]).unwrap(), Value::Double(-3.0));
+        assert_eq!(
+            call("round", vec![Value::Int(1250), Value::Int(-2)]).unwrap(),
+            Value::Int(1300)
+        );
+        assert_eq!(call("length", vec![Value::Null]).unwrap(), Value::Null);
+        assert_eq!(
+            call("coalesce", vec![Value::Null, Value::Int(2), Value::Int(3)]).unwrap(),
+            Value::Int(2)
+        );
+        assert_eq!(call("nullif", vec![Value::Int(1), Value::Int(1)]).unwrap(), Value::Null);
+        assert_eq!(call("nullif", vec![s("a"), s("b")]).unwrap(), s("a"));
+
+        println!("errors");
+        assert!(call("upper", vec![Value::Int(1)]).is_err());
+        assert!(call("abs", vec![s("1")]).is_err());
+        assert!(call("round", vec![Value::Int(1), Value::Int(1), Value::Int(1)]).is_err());
+        assert!(call("substr", vec![s("abc")]).is_err());
+        assert!(call("coalesce", vec![]).is_err());
+        assert!(call("concat", vec![s("a")]).is_err());
+    }
+
+    #[test]
+    fn test_check_args() {
+        let upper = get("upper").unwrap();
+        assert_eq!(
+            upper.check_args(&[Some(DataType::Char(7))]).unwrap(),
+            Some(DataType::Char(7))
+        );
+        assert!(upper.check_args(&[Some(DataType::Int)]).is_err());
+        assert_eq!(upper.check_args(&[None]).unwrap(), None);
+
+        let coalesce = get("coalesce").unwrap();
+        assert_eq!(
+            coalesce
+                .check_args(&[None, Some(DataType::Int), Some(DataType::Double)])
+                .unwrap(),
+            Some(DataType::Double)
+        );
+        assert_eq!(
+            get("length")
+                .unwrap()
+                .check_args(&[Some(DataType::Varchar(10))])
+                .unwrap(),
+            Some(DataType::Int)
+        );
+        assert!(get("substr")
+            .unwrap()
+            .check_args(&[Some(DataType::Varchar(10))])
+            .is_err());
+    }
+}
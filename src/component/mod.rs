@@ -1,4 +1,7 @@
 pub mod database;
 pub mod datatype;
+pub mod expression;
 pub mod field;
+pub mod fieldindex;
+pub mod function;
 pub mod table;
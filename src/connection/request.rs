@@ -1,7 +1,9 @@
+use crate::component::table::SelectData;
 use crate::manager::pool::{Pool, PoolError};
 use crate::sql::parser::{Parser, ParserError};
 use crate::storage::diskinterface::{DiskError, DiskInterface};
 use crate::Response;
+use std::collections::HashMap;
 use std::fmt;
 
 use std::sync::{Arc, Mutex};
@@ -11,6 +13,18 @@ pub struct Request {
     pub username: String,
     pub addr: String,
     pub key: i32,
+
+    /* session */
+    pub dbname: String,                     // the current database, set by `use`
+    pub variables: HashMap<String, String>, // session variables, set by `set var = value`
+}
+
+/// A command which changes the state of the session, instead of running on the worker
+#[derive(Debug, PartialEq)]
+enum SessionCommand {
+    Use(String),
+    Set(String, String),
+    ShowVariables,
 }
 
 #[derive(Debug)]
@@ -19,6 +33,7 @@ pub enum RequestError {
     CauseByParser(ParserError),
     DiskError(DiskError),
     UserNotExist(String),
+    DbNotExist(String),
     CreateDBBeforeCmd,
     BadRequest,
     InvalidKey,
@@ -31,6 +46,7 @@ impl fmt::Display for RequestError {
             RequestError::CauseByParser(ref e) => write!(f, "error caused by parser: {}", e),
             RequestError::DiskError(ref e) => write!(f, "error caused by file: {}", e),
             RequestError::UserNotExist(ref s) => write!(f, "user: {} not found", s),
+            RequestError::DbNotExist(ref s) => write!(f, "database: {} not found", s),
             RequestError::CreateDBBeforeCmd => write!(f, "please create a database before any other commands"),
             RequestError::BadRequest => write!(f, "BadRequest, invalid request format"),
             RequestError::InvalidKey => write!(f, "invalid key format"),
@@ -44,6 +60,8 @@ impl Request {
             username: "".to_string(),
             addr: new_addr,
             key: 0,
+            dbname: "".to_string(),
+            variables: HashMap::new(),
         }
     }
     pub fn parse(input: &str, mutex: &Arc<Mutex<Pool>>, req: &mut Request) -> Result<Response, RequestError> {
@@ -51,10 +69,8 @@ impl Request {
          * request format
          * case0: init (must be first request in each connection)
          * username||||||key
-         * case1: normal
-         * username||dbname||command;
-         * case2: user without a database
-         * username||||create database dbname;
+         * case1: SQL text on the current database of the session, which is set by `use dbname;`
+         * command;
          *
          */
         let split_str: Vec<&str> = input.split("||").collect();
@@ -83,17 +99,35 @@ impl Request {
             });
         }
 
-        if split_str.len() != 3 {
-            return Err(RequestError::BadRequest);
+        match parse_session_command(input) {
+            Some(Ok(SessionCommand::Use(db_name))) => {
+                Request::use_database(&db_name, mutex, req)?;
+                return Ok(Response::OK {
+                    msg: format!("Database changed to {}", db_name),
+                });
+            }
+            Some(Ok(SessionCommand::Set(name, value))) => {
+                req.variables.insert(name, value);
+                return Ok(Response::OK {
+                    msg: "Query OK!".to_string(),
+                });
+            }
+            Some(Ok(SessionCommand::ShowVariables)) => {
+                return Ok(Response::OK {
+                    msg: req.show_variables(),
+                });
+            }
+            Some(Err(e)) => return Err(e),
+            None => {}
         }
 
-        let username = split_str[0];
-        let dbname = split_str[1];
-        let cmd = format!("{};", split_str[2]);
+        let username = req.username.clone();
+        let dbname = req.dbname.clone();
+        let cmd = format!("{};", input);
 
         // load sql object from memory pool
         let mut pool = mutex.lock().unwrap();
-        let mut sql = match pool.get(username, dbname, req.addr.clone()) {
+        let sql = match pool.get(&username, &dbname, req.addr.clone()) {
             Ok(tsql) => tsql,
             Err(ret) => return Err(RequestError::PoolError(ret)),
         };
@@ -107,37 +141,82 @@ impl Request {
                 Ok(_parser) => _parser,
                 Err(ret) => return Err(RequestError::CauseByParser(ret)),
             };
-            match parser.parse(&mut sql) {
-                Err(ret) => return Err(RequestError::CauseByParser(ret)),
-                Ok(_) => {}
-            }
+            parser.parse(sql).map_err(RequestError::CauseByParser)?;
         } else {
-            // check cmd if it is "create database dbname;"
-            let mut iter = cmd.split_whitespace();
-            if iter.next() != Some("create") || iter.next() != Some("database") {
-                return Err(RequestError::CreateDBBeforeCmd);
+            // check cmd if it is "create database dbname;" or "show databases;"
+            let lower = cmd.to_lowercase();
+            let mut iter = lower.split_whitespace();
+            match (iter.next(), iter.next().map(|s| s.trim_end_matches(';'))) {
+                (Some("create"), Some("database")) => {}
+                (Some("show"), Some("databases")) => {}
+                _ => return Err(RequestError::CreateDBBeforeCmd),
             }
             let parser = match Parser::new(&cmd) {
                 Ok(_parser) => _parser,
                 Err(ret) => return Err(RequestError::CauseByParser(ret)),
             };
-            match parser.parse(&mut sql) {
-                Err(ret) => return Err(RequestError::CauseByParser(ret)),
-                Ok(_) => {}
-            }
+            parser.parse(sql).map_err(RequestError::CauseByParser)?;
         }
-        if !sql.result_json.is_empty() {
+        let response = if !sql.result_json.is_empty() {
             let return_json = sql.result_json.clone();
             sql.result_json.clear();
-            return Ok(Response::OK {
+            Response::OK {
                 msg: return_json.to_string(),
-            });
+            }
+        } else {
+            Response::OK {
+                msg: "Query OK!".to_string(),
+            }
+        };
+
+        // the current database follows `create database` and `drop database`, and a dropped one is
+        // removed from storage at once
+        if sql.database.is_delete {
+            req.dbname = "".to_string();
+            pool.write_back(req.addr.clone()).map_err(RequestError::PoolError)?;
+        } else {
+            req.dbname = sql.database.name.clone();
         }
-        Ok(Response::OK {
-            msg: "Query OK!".to_string(),
-        })
+        Ok(response)
         //Ok(Response::OK { msg: format!("{}, user:{}",input, sql.username) })
     }
+
+    /// Switch the current database of the session
+    ///
+    /// The worker of the connection is written back first if it has another database, so the changes
+    /// are kept, and the database is loaded by the next command.
+    fn use_database(db_name: &str, mutex: &Arc<Mutex<Pool>>, req: &mut Request) -> Result<(), RequestError> {
+        let mut pool = mutex.lock().unwrap();
+        let is_current = match pool.cache.get(&req.addr) {
+            Some(sql) => sql.database.name == db_name && !sql.database.is_delete,
+            None => false,
+        };
+        if !is_current {
+            let dbs = DiskInterface::get_dbs(&req.username, Some(dotenv!("FILE_BASE_PATH")))
+                .map_err(RequestError::DiskError)?;
+            if !dbs.contains(&db_name.to_string()) {
+                return Err(RequestError::DbNotExist(db_name.to_string()));
+            }
+            if pool.cache.contains_key(&req.addr) {
+                pool.write_back(req.addr.clone()).map_err(RequestError::PoolError)?;
+            }
+        }
+        req.dbname = db_name.to_string();
+        Ok(())
+    }
+    /// List the session variables sorted by name, for `show variables`
+    fn show_variables(&self) -> String {
+        let mut variables: Vec<(&String, &String)> = self.variables.iter().collect();
+        variables.sort();
+
+        let mut data = SelectData::new();
+        data.fields = vec!["variable".to_string(), "value".to_string()];
+        data.rows = variables
+            .into_iter()
+            .map(|(name, value)| vec![Some(name.clone()), Some(value.clone())])
+            .collect();
+        serde_json::to_string(&data).unwrap()
+    }
     fn user_verify(name: &str) -> Result<(), RequestError> {
         // auto create new users for now
         if name == "" {
@@ -157,3 +236,114 @@ impl Request {
         Ok(())
     }
 }
+
+/// Parse `use db`, `set var = value` and `show variables`, which are kept in the session
+///
+/// Return `None` if the command is not a session command.
+fn parse_session_command(cmd: &str) -> Option<Result<SessionCommand, RequestError>> {
+    let cmd = cmd.trim().trim_end_matches(';').trim();
+    let (keyword, rest) = match cmd.find(char::is_whitespace) {
+        Some(pos) => (cmd[..pos].to_lowercase(), cmd[pos..].trim()),
+        None => (cmd.to_lowercase(), ""),
+    };
+    match keyword.as_str() {
+        "use" => {
+            let mut names = rest.split_whitespace();
+            let db_name = match names.next() {
+                Some(db_name) => db_name,
+                None => return Some(Err(syntax_error("no db name".to_string()))),
+            };
+            if let Some(extra) = names.next() {
+                return Some(Err(syntax_error(format!("unexpected {} after the db name", extra))));
+            }
+            Some(Ok(SessionCommand::Use(db_name.to_lowercase())))
+        }
+        "set" => {
+            let pos = match rest.find('=') {
+                Some(pos) => pos,
+                None => return Some(Err(syntax_error("miss = in set".to_string()))),
+            };
+            let name = rest[..pos].trim().to_lowercase();
+            let value = rest[pos + 1..].trim().trim_matches('\'').to_string();
+            if name.is_empty() {
+                return Some(Err(syntax_error("no variable name".to_string())));
+            }
+            if name.contains(char::is_whitespace) {
+                return Some(Err(syntax_error(format!("invalid variable name {}", name))));
+            }
+            Some(Ok(SessionCommand::Set(name, value)))
+        }
+        "show" if rest.eq_ignore_ascii_case("variables") => Some(Ok(SessionCommand::ShowVariables)),
+        _ => None,
+    }
+}
+
+/// the error of a session command in the wrong syntax, as the one of a query
+fn syntax_error(message: String) -> RequestError {
+    RequestError::CauseByParser(ParserError::SyntaxError(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_session_command() {
+        assert_eq!(
+            parse_session_command("use db1;").unwrap().unwrap(),
+            SessionCommand::Use("db1".to_string())
+        );
+        assert_eq!(
+            parse_session_command("USE DB1").unwrap().unwrap(),
+            SessionCommand::Use("db1".to_string())
+        );
+        assert_eq!(
+            parse_session_command("set Timeout = 30;").unwrap().unwrap(),
+            SessionCommand::Set("timeout".to_string(), "30".to_string())
+        );
+        assert_eq!(
+            parse_session_command("set name='Tiger'").unwrap().unwrap(),
+            SessionCommand::Set("name".to_string(), "Tiger".to_string())
+        );
+        for (cmd, message) in [
+            ("use", "no db name"),
+            ("use db1 extra;", "unexpected extra after the db name"),
+            ("set timeout 30", "miss = in set"),
+            ("set = 30", "no variable name"),
+            ("set time out = 30", "invalid variable name time out"),
+        ]
+        .iter()
+        {
+            match parse_session_command(cmd).unwrap() {
+                Err(RequestError::CauseByParser(ParserError::SyntaxError(ref s))) => assert_eq!(s, message),
+                _ => panic!("{} should be a syntax error", cmd),
+            }
+        }
+        assert_eq!(
+            parse_session_command("SHOW VARIABLES;").unwrap().unwrap(),
+            SessionCommand::ShowVariables
+        );
+        assert!(parse_session_command("show databases;").is_none());
+        assert!(parse_session_command("select a1 from t1;").is_none());
+        assert!(parse_session_command("update t1 set a1 = 1;").is_none());
+    }
+
+    #[test]
+    fn test_show_variables() {
+        let mut req = Request::new("127.0.0.1:23333".to_string());
+        assert_eq!(req.show_variables(), r#"{"fields":["variable","value"],"rows":[]}"#);
+
+        for cmd in &["set timeout = 30;", "set name = 'Tiger';", "set timeout = 60;"] {
+            match parse_session_command(cmd) {
+                Some(Ok(SessionCommand::Set(name, value))) => {
+                    req.variables.insert(name, value);
+                }
+                _ => panic!("{} is not a set command", cmd),
+            }
+        }
+        assert_eq!(
+            req.show_variables(),
+            r#"{"fields":["variable","value"],"rows":[["name","Tiger"],["timeout","60"]]}"#
+        );
+    }
+}
@@ -1,7 +1,13 @@
 use crate::component::datatype::DataType;
-use crate::index::tree::{NodeType, PagePtr, RowPtr, Tree};
-use crate::storage::page::{INDEX_INTERNAL_HEADER_SIZE, INDEX_LEAF_HEADER_SIZE};
-use std::cmp::PartialOrd;
+use crate::index::tree::{IndexKey, NodeType, PagePtr, RowPtr, Tree, NULL_PAGE};
+use crate::storage::diskinterface::DiskError;
+use crate::storage::page::{
+    Header, HeaderBytes, IndexFileHeader, IndexInternalHeader, IndexInternalPage, IndexLeafHeader, IndexLeafPage,
+    PageFile, INDEX_FILE_HEADER_SIZE, INDEX_INTERNAL_HEADER_SIZE, INDEX_LEAF_HEADER_SIZE,
+};
+use std::mem;
+use std::ops::Bound;
+use std::vec;
 
 /// B+ Tree
 ///
@@ -24,6 +30,14 @@ use std::cmp::PartialOrd;
 /// P0: Previous page pointer
 /// P1: Next page pointer
 /// ```
+///
+/// The tree is its root node, which owns the child nodes in `nodes`, whose page ids are in `ptrs`. A
+/// node other than the root has at least half of its capacity of keys, and all the leaves are at the
+/// same height. The root allocates the page ids of the new nodes.
+///
+/// A tree in an index file keeps each node in the page of its page id, and the root and the height
+/// in the page 0. The nodes are read from the pages when they are visited for the first time, and
+/// the pages of the nodes are written as soon as the nodes are modified.
 pub struct BPlusTree<T> {
     pid: u32,
     node_type: NodeType,
@@ -37,9 +51,78 @@ pub struct BPlusTree<T> {
     rows: Option<Vec<RowPtr>>,
     nodes: Vec<Box<Self>>,
     height: u32,
+    next_pid: u32,          // the next page id to allocate, kept by the root
+    is_loaded: bool,        // false if the node has not been read from its page
+    file: Option<PageFile>, // the index file, kept by the root
+}
+
+/// the separator key and the new right node of a split node
+type Split<T> = (T, Box<BPlusTree<T>>);
+
+/// the old record pointer, the split and the pending leaf of inserting into a subtree
+type Inserted<T> = (Option<RowPtr>, Option<Split<T>>, Option<PagePtr>);
+
+/// the keys, the record pointers and the next page pointer of a leaf
+type LeafEntries<T> = (Vec<T>, Vec<RowPtr>, PagePtr);
+
+/// An iterator over the keys in a range of the tree and their record pointers, in the order of the keys
+///
+/// It starts from the leaf where the lower bound should be, then walks the leaves by their next page
+/// pointers until a key is beyond the upper bound or doesn't have the prefix.
+pub struct RangeIter<'a, T> {
+    tree: &'a BPlusTree<T>,
+    entries: vec::IntoIter<(T, RowPtr)>, // the rest of the current leaf
+    last: Option<T>,                     // the last key of the current leaf
+    next: PagePtr,                       // the leaf after the current leaf
+    high: Bound<T>,
+    prefix: Option<T>,
+    is_done: bool,
+}
+
+impl<'a, T: IndexKey> RangeIter<'a, T> {
+    fn is_in_range(&self, key: &T) -> bool {
+        let is_below = match &self.high {
+            Bound::Included(high) => key <= high,
+            Bound::Excluded(high) => key < high,
+            Bound::Unbounded => true,
+        };
+        is_below && self.prefix.as_ref().is_none_or(|prefix| key.has_prefix(prefix))
+    }
+}
+
+impl<'a, T: IndexKey> Iterator for RangeIter<'a, T> {
+    type Item = Result<(T, RowPtr), DiskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.is_done {
+            if let Some((key, row)) = self.entries.next() {
+                if self.is_in_range(&key) {
+                    return Some(Ok((key, row)));
+                }
+                self.is_done = true;
+                break;
+            }
+            if self.next == NULL_PAGE {
+                self.is_done = true;
+                break;
+            }
+            match self.tree.next_leaf(self.next, self.last.as_ref()) {
+                Ok((keys, rows, next)) => {
+                    self.last = keys.last().cloned().or(self.last.take());
+                    self.entries = keys.into_iter().zip(rows).collect::<Vec<_>>().into_iter();
+                    self.next = next;
+                }
+                Err(e) => {
+                    self.is_done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
 }
 
-impl<T: PartialOrd> Tree<T> for BPlusTree<T> {
+impl<T: IndexKey> Tree<T> for BPlusTree<T> {
     fn new(
         pid: u32,
         node_type: NodeType,
@@ -68,11 +151,14 @@ impl<T: PartialOrd> Tree<T> for BPlusTree<T> {
                     rows,
                     nodes,
                     height: 1,
+                    next_pid: pid + 1,
+                    is_loaded: true,
+                    file: None,
                 })
             }
             NodeType::Leaf => {
                 let capacity = get_leaf_capacity(ptr_size, key_size, row_ptr_size.unwrap());
-                let ptrs: Vec<PagePtr> = Vec::with_capacity(2);
+                let ptrs: Vec<PagePtr> = vec![NULL_PAGE, NULL_PAGE];
                 let keys: Vec<T> = Vec::with_capacity(capacity);
                 let rows: Option<Vec<RowPtr>> = Some(Vec::with_capacity(capacity));
                 let nodes: Vec<Box<Self>> = Vec::with_capacity(2);
@@ -89,25 +175,610 @@ impl<T: PartialOrd> Tree<T> for BPlusTree<T> {
                     rows,
                     nodes,
                     height: 1,
+                    next_pid: pid + 1,
+                    is_loaded: true,
+                    file: None,
                 })
             }
         }
     }
-    fn height(&self) -> u32 {
-        self.height
+    fn insert(&mut self, val: T, row: RowPtr) -> Result<Option<RowPtr>, DiskError> {
+        let file = self.file.take();
+        let result = self.insert_root(val, row, file.as_ref());
+        self.file = file;
+        result
+    }
+    fn delete(&mut self, val: T) -> Result<Option<RowPtr>, DiskError> {
+        let file = self.file.take();
+        let result = self.delete_root(val, file.as_ref());
+        self.file = file;
+        result
     }
-    fn insert(&mut self, val: T) {}
-    fn delete(&mut self, val: T) {}
-    fn search(&self, val: T) -> RowPtr {
-        (0, 0)
+    fn search(&self, val: T) -> Result<Option<RowPtr>, DiskError> {
+        self.with_leaf(Some(&val), |leaf| leaf.find_row(&val))
     }
 }
 
-impl<T: PartialOrd> BPlusTree<T> {
+impl<T: IndexKey> BPlusTree<T> {
+    /// create a tree of an empty leaf in a new index file
+    pub fn create(
+        path: &str,
+        key_type: DataType,
+        ptr_size: usize,
+        key_size: usize,
+        row_ptr_size: usize,
+    ) -> Result<Box<Self>, DiskError> {
+        let file = PageFile::create(path)?;
+        let mut tree = Self::new(1, NodeType::Leaf, key_type, ptr_size, key_size, Some(row_ptr_size));
+        tree.write(Some(&file))?;
+        tree.write_header(Some(&file))?;
+        tree.file = Some(file);
+        Ok(tree)
+    }
+
+    /// open the tree in an index file, with only the root read from its page
+    pub fn open(path: &str) -> Result<Box<Self>, DiskError> {
+        let file = PageFile::open(path)?;
+        let page = file.read_page(0)?;
+        let header = IndexFileHeader::from_bytes(&HeaderBytes(page[..INDEX_FILE_HEADER_SIZE].to_vec()));
+        let template = Self::new(
+            header.root,
+            NodeType::Leaf,
+            header.key_type,
+            header.ptr_size,
+            header.key_size,
+            Some(header.row_ptr_size),
+        );
+        let mut tree = template.read_node(&file, header.root, header.height)?;
+        tree.next_pid = header.next_pid;
+        tree.file = Some(file);
+        Ok(tree)
+    }
+
+    /// flush the pages of the index file to the disk
+    pub fn sync(&self) -> Result<(), DiskError> {
+        match &self.file {
+            Some(file) => file.sync(),
+            None => Ok(()),
+        }
+    }
+
+    /// the keys and their record pointers in order
+    pub fn entries(&self) -> Result<Vec<(T, RowPtr)>, DiskError> {
+        let mut entries = vec![];
+        self.collect_entries(self.file.as_ref(), &mut entries)?;
+        Ok(entries)
+    }
+
+    /// the keys in the range and their record pointers in order
+    pub fn range(&self, low: Bound<T>, high: Bound<T>) -> Result<RangeIter<'_, T>, DiskError> {
+        let start = match &low {
+            Bound::Included(val) | Bound::Excluded(val) => Some(val),
+            Bound::Unbounded => None,
+        };
+        let (keys, rows, next) = self.with_leaf(start, |leaf| {
+            (leaf.keys.clone(), leaf.rows.clone().unwrap(), leaf.ptrs[1])
+        })?;
+        let last = keys.last().cloned();
+        let entries: Vec<(T, RowPtr)> = keys
+            .into_iter()
+            .zip(rows)
+            .filter(|(key, _)| match &low {
+                Bound::Included(low) => key >= low,
+                Bound::Excluded(low) => key > low,
+                Bound::Unbounded => true,
+            })
+            .collect();
+        Ok(RangeIter {
+            tree: self,
+            entries: entries.into_iter(),
+            last,
+            next,
+            high,
+            prefix: None,
+            is_done: false,
+        })
+    }
+
+    /// the keys with the prefix and their record pointers in order
+    pub fn prefix(&self, prefix: T) -> Result<RangeIter<'_, T>, DiskError> {
+        let mut iter = self.range(Bound::Included(prefix.clone()), Bound::Unbounded)?;
+        iter.prefix = Some(prefix);
+        Ok(iter)
+    }
+
     fn node_type(&self) -> &NodeType {
         &self.node_type
     }
 
+    /// the minimum number of keys of a node other than the root
+    fn min_keys(&self) -> usize {
+        self.capacity / 2
+    }
+
+    /// a new node of the same key and pointer sizes at the same height, with a page id allocated
+    fn new_node(&self, node_type: NodeType, next_pid: &mut u32) -> Box<Self> {
+        let mut node = Self::new(
+            *next_pid,
+            node_type,
+            self.key_type.clone(),
+            self.ptr_size,
+            self.key_size,
+            self.row_ptr_size,
+        );
+        node.height = self.height;
+        *next_pid += 1;
+        node
+    }
+
+    /// a node of the page at the height, which is not read from the page yet
+    fn stub_node(&self, pid: PagePtr, height: u32) -> Box<Self> {
+        let node_type = if height == 1 {
+            NodeType::Leaf
+        } else {
+            NodeType::Internal
+        };
+        let mut node = Self::new(
+            pid,
+            node_type,
+            self.key_type.clone(),
+            self.ptr_size,
+            self.key_size,
+            self.row_ptr_size,
+        );
+        node.height = height;
+        node.is_loaded = false;
+        node
+    }
+
+    /// read the node of the page at the height, whose children are not read yet
+    fn read_node(&self, file: &PageFile, pid: PagePtr, height: u32) -> Result<Box<Self>, DiskError> {
+        let mut node = self.stub_node(pid, height);
+        let page = file.read_page(pid)?;
+        let keys = match node.node_type {
+            NodeType::Internal => {
+                let (_, keys, ptrs) = IndexInternalPage::from_bytes(&page).entries()?;
+                node.nodes = ptrs.iter().map(|pid| self.stub_node(*pid, height - 1)).collect();
+                node.ptrs = ptrs;
+                keys
+            }
+            NodeType::Leaf => {
+                let (_, keys, rows, prev, next) = IndexLeafPage::from_bytes(&page).entries()?;
+                node.rows = Some(rows);
+                node.ptrs = vec![prev, next];
+                keys
+            }
+        };
+        node.keys = keys.iter().map(|key| T::from_bytes(key)).collect::<Result<_, _>>()?;
+        node.is_loaded = true;
+        Ok(node)
+    }
+
+    /// read the node from its page if it is not loaded
+    fn load(&mut self, file: Option<&PageFile>) -> Result<(), DiskError> {
+        if self.is_loaded {
+            return Ok(());
+        }
+        let file = file.ok_or(DiskError::TableIdxFileNotExists)?;
+        *self = *self.read_node(file, self.pid, self.height)?;
+        Ok(())
+    }
+
+    /// write the node to its page, if the tree is in an index file
+    fn write(&self, file: Option<&PageFile>) -> Result<(), DiskError> {
+        let file = match file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| key.to_bytes(self.key_size))
+            .collect::<Result<Vec<_>, _>>()?;
+        let page = match self.node_type {
+            NodeType::Internal => {
+                let header = IndexInternalHeader {
+                    pid: self.pid,
+                    capacity: self.capacity,
+                    node_type: NodeType::Internal,
+                    key_type: self.key_type.clone(),
+                    ptr_size: self.ptr_size,
+                    key_size: self.key_size,
+                    num_keys: keys.len(),
+                };
+                IndexInternalPage::from_entries(header, &keys, &self.ptrs)?.to_bytes()
+            }
+            NodeType::Leaf => {
+                let header = IndexLeafHeader {
+                    pid: self.pid,
+                    capacity: self.capacity,
+                    node_type: NodeType::Leaf,
+                    key_type: self.key_type.clone(),
+                    ptr_size: self.ptr_size,
+                    key_size: self.key_size,
+                    row_ptr_size: self.row_ptr_size.unwrap(),
+                    num_keys: keys.len(),
+                };
+                let rows = self.rows.as_ref().unwrap();
+                IndexLeafPage::from_entries(header, &keys, rows, self.ptrs[0], self.ptrs[1])?.to_bytes()
+            }
+        };
+        file.write_page(self.pid, &page)
+    }
+
+    /// write the root, the height and the next page id of the tree to the page 0
+    fn write_header(&self, file: Option<&PageFile>) -> Result<(), DiskError> {
+        let file = match file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let header = IndexFileHeader {
+            root: self.pid,
+            height: self.height,
+            next_pid: self.next_pid,
+            key_type: self.key_type.clone(),
+            ptr_size: self.ptr_size,
+            key_size: self.key_size,
+            row_ptr_size: self.row_ptr_size.unwrap_or(0),
+        };
+        file.write_page(0, &header.to_bytes().0)
+    }
+
+    fn collect_entries(&self, file: Option<&PageFile>, entries: &mut Vec<(T, RowPtr)>) -> Result<(), DiskError> {
+        match self.node_type {
+            NodeType::Leaf => {
+                let rows = self.rows.as_ref().unwrap();
+                entries.extend(self.keys.iter().cloned().zip(rows.iter().cloned()));
+            }
+            NodeType::Internal => {
+                for node in self.nodes.iter() {
+                    if node.is_loaded {
+                        node.collect_entries(file, entries)?;
+                    } else {
+                        let file = file.ok_or(DiskError::TableIdxFileNotExists)?;
+                        self.read_node(file, node.pid, node.height)?
+                            .collect_entries(Some(file), entries)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// call the function on the leaf where the key should be, or on the first leaf if there is no key
+    ///
+    /// The nodes which are not loaded are read from their pages without being kept.
+    fn with_leaf<R>(&self, val: Option<&T>, f: impl FnOnce(&Self) -> R) -> Result<R, DiskError> {
+        let route = |node: &Self| match val {
+            Some(val) => Self::find_ptr(&node.keys, 0, node.keys.len(), val.clone()),
+            None => 0,
+        };
+        let mut node = self;
+        while node.node_type == NodeType::Internal {
+            node = &node.nodes[route(node)];
+            if !node.is_loaded {
+                // the nodes below are not loaded either
+                let file = self.file.as_ref().ok_or(DiskError::TableIdxFileNotExists)?;
+                let mut node = self.read_node(file, node.pid, node.height)?;
+                while node.node_type == NodeType::Internal {
+                    node = self.read_node(file, node.ptrs[route(&node)], node.height - 1)?;
+                }
+                return Ok(f(&node));
+            }
+        }
+        Ok(f(node))
+    }
+
+    /// the entries of the leaf of the page id, which is after the leaf whose last key is `after`
+    ///
+    /// The pages of a tree in an index file are always up to date, so the leaf is read from its page.
+    /// Otherwise the leaf is found in memory by the key.
+    fn next_leaf(&self, pid: PagePtr, after: Option<&T>) -> Result<LeafEntries<T>, DiskError> {
+        if let Some(file) = &self.file {
+            let leaf = self.read_node(file, pid, 1)?;
+            return Ok((leaf.keys, leaf.rows.unwrap(), leaf.ptrs[1]));
+        }
+        let leaf = after
+            .and_then(|after| self.find_leaf_after(pid, after))
+            .ok_or(DiskError::IndexKeyNotFound)?;
+        Ok((leaf.keys.clone(), leaf.rows.clone().unwrap(), leaf.ptrs[1]))
+    }
+
+    /// the leaf of the page id in memory, which is either in the subtree where the key is, or the first
+    /// leaf of the next subtree
+    fn find_leaf_after(&self, pid: PagePtr, after: &T) -> Option<&Self> {
+        if self.node_type == NodeType::Leaf {
+            return Some(self).filter(|leaf| leaf.pid == pid);
+        }
+        let pos = Self::find_ptr(&self.keys, 0, self.keys.len(), after.clone());
+        if let Some(leaf) = self.nodes[pos].find_leaf_after(pid, after) {
+            return Some(leaf);
+        }
+        let mut node = self.nodes.get(pos + 1)?.as_ref();
+        while node.node_type == NodeType::Internal {
+            node = &node.nodes[0];
+        }
+        Some(node).filter(|leaf| leaf.pid == pid)
+    }
+
+    /// the record pointer of the key in a leaf
+    fn find_row(&self, val: &T) -> Option<RowPtr> {
+        match self.find_key(val) {
+            Ok(pos) => Some(self.rows.as_ref().unwrap()[pos]),
+            Err(_) => None,
+        }
+    }
+
+    /// binary search the key in a leaf, `Ok` with its position if found, or `Err` with the position
+    /// to insert it
+    fn find_key(&self, val: &T) -> Result<usize, usize> {
+        let pos = Self::find_ptr(&self.keys, 0, self.keys.len(), val.clone());
+        if pos > 0 && self.keys[pos - 1] == *val {
+            Ok(pos - 1)
+        } else {
+            Err(pos)
+        }
+    }
+
+    fn insert_root(&mut self, val: T, row: RowPtr, file: Option<&PageFile>) -> Result<Option<RowPtr>, DiskError> {
+        let old_next_pid = self.next_pid;
+        let mut next_pid = self.next_pid;
+        let (old, split, _) = self.insert_node(val, row, &mut next_pid, file)?;
+
+        // the root is split, so a new root grows above both halves
+        if let Some((key, right)) = split {
+            let mut root = self.new_node(NodeType::Internal, &mut next_pid);
+            root.height = self.height + 1;
+            let left = mem::replace(self, *root);
+            self.keys.push(key);
+            self.ptrs.push(left.pid);
+            self.ptrs.push(right.pid);
+            self.nodes.push(Box::new(left));
+            self.nodes.push(right);
+            self.write(file)?;
+        }
+        if next_pid != old_next_pid {
+            self.next_pid = next_pid;
+            self.write_header(file)?;
+        }
+        Ok(old)
+    }
+
+    fn delete_root(&mut self, val: T, file: Option<&PageFile>) -> Result<Option<RowPtr>, DiskError> {
+        let (old, _) = self.delete_node(&val, file)?;
+
+        // the root with only one child is replaced by the child
+        if self.node_type == NodeType::Internal && self.keys.is_empty() {
+            let next_pid = self.next_pid;
+            let child = self.nodes.pop().unwrap();
+            *self = *child;
+            self.next_pid = next_pid;
+            self.write_header(file)?;
+        }
+        Ok(old)
+    }
+
+    /// insert the key into the subtree
+    ///
+    /// Return the old record pointer of the key, the separator key and the new right node if the
+    /// node is split, and the page id of the new leaf if the leaf after it, which is not in the
+    /// subtree, should point back to it.
+    fn insert_node(
+        &mut self,
+        val: T,
+        row: RowPtr,
+        next_pid: &mut u32,
+        file: Option<&PageFile>,
+    ) -> Result<Inserted<T>, DiskError> {
+        if self.node_type == NodeType::Leaf {
+            let pos = match self.find_key(&val) {
+                Ok(pos) => {
+                    let old = mem::replace(&mut self.rows.as_mut().unwrap()[pos], row);
+                    self.write(file)?;
+                    return Ok((Some(old), None, None));
+                }
+                Err(pos) => pos,
+            };
+            self.keys.insert(pos, val);
+            self.rows.as_mut().unwrap().insert(pos, row);
+            if self.keys.len() <= self.capacity {
+                self.write(file)?;
+                return Ok((None, None, None));
+            }
+
+            // split the leaf in halves, and link the right one between it and its next leaf
+            let mid = self.keys.len() / 2;
+            let mut right = self.new_node(NodeType::Leaf, next_pid);
+            right.keys = self.keys.split_off(mid);
+            right.rows = Some(self.rows.as_mut().unwrap().split_off(mid));
+            right.ptrs = vec![self.pid, self.ptrs[1]];
+            self.ptrs[1] = right.pid;
+            self.write(file)?;
+            right.write(file)?;
+            let pending = if right.ptrs[1] != NULL_PAGE {
+                Some(right.pid)
+            } else {
+                None
+            };
+            return Ok((None, Some((right.keys[0].clone(), right)), pending));
+        }
+
+        let pos = Self::find_ptr(&self.keys, 0, self.keys.len(), val.clone());
+        self.nodes[pos].load(file)?;
+        let (old, split, pending) = self.nodes[pos].insert_node(val, row, next_pid, file)?;
+        let (key, child) = match split {
+            Some(split) => split,
+            None => return Ok((old, None, self.link_next_leaf(pos, pending, file)?)),
+        };
+        self.keys.insert(pos, key);
+        self.ptrs.insert(pos + 1, child.pid);
+        self.nodes.insert(pos + 1, child);
+        let pending = self.link_next_leaf(pos + 1, pending, file)?;
+        if self.keys.len() <= self.capacity {
+            self.write(file)?;
+            return Ok((old, None, pending));
+        }
+
+        // split the internal node, and move the middle key up to the parent
+        let mid = self.keys.len() / 2;
+        let mut right = self.new_node(NodeType::Internal, next_pid);
+        right.keys = self.keys.split_off(mid + 1);
+        right.ptrs = self.ptrs.split_off(mid + 1);
+        right.nodes = self.nodes.split_off(mid + 1);
+        let key = self.keys.pop().unwrap();
+        self.write(file)?;
+        right.write(file)?;
+        Ok((old, Some((key, right)), pending))
+    }
+
+    /// delete the key from the subtree
+    ///
+    /// Return the record pointer of the key, and the page id of the merged leaf if the leaf after it,
+    /// which is not in the subtree, should point back to it.
+    fn delete_node(
+        &mut self,
+        val: &T,
+        file: Option<&PageFile>,
+    ) -> Result<(Option<RowPtr>, Option<PagePtr>), DiskError> {
+        if self.node_type == NodeType::Leaf {
+            return match self.find_key(val) {
+                Ok(pos) => {
+                    self.keys.remove(pos);
+                    let row = self.rows.as_mut().unwrap().remove(pos);
+                    self.write(file)?;
+                    Ok((Some(row), None))
+                }
+                Err(_) => Ok((None, None)),
+            };
+        }
+
+        let pos = Self::find_ptr(&self.keys, 0, self.keys.len(), val.clone());
+        self.nodes[pos].load(file)?;
+        let (old, pending) = self.nodes[pos].delete_node(val, file)?;
+        let pending = self.link_next_leaf(pos, pending, file)?;
+        if self.nodes[pos].keys.len() >= self.nodes[pos].min_keys() {
+            return Ok((old, pending));
+        }
+        let merged = self.rebalance(pos, file)?;
+        self.write(file)?;
+        Ok((old, pending.or(merged)))
+    }
+
+    /// fix the child `pos` with too few keys, by borrowing a key from a sibling, or merging with a
+    /// sibling which has no key to spare
+    ///
+    /// Return the page id of the merged leaf if the leaf after it, which is not under this node, should
+    /// point back to it.
+    fn rebalance(&mut self, pos: usize, file: Option<&PageFile>) -> Result<Option<PagePtr>, DiskError> {
+        let min = self.nodes[pos].min_keys();
+        if pos > 0 {
+            self.nodes[pos - 1].load(file)?;
+        }
+        if pos + 1 < self.nodes.len() {
+            self.nodes[pos + 1].load(file)?;
+        }
+
+        // borrow the last key of the left sibling
+        if pos > 0 && self.nodes[pos - 1].keys.len() > min {
+            let (left, right) = self.nodes.split_at_mut(pos);
+            let (left, child) = (&mut left[pos - 1], &mut right[0]);
+            match child.node_type {
+                NodeType::Leaf => {
+                    child.keys.insert(0, left.keys.pop().unwrap());
+                    let row = left.rows.as_mut().unwrap().pop().unwrap();
+                    child.rows.as_mut().unwrap().insert(0, row);
+                    self.keys[pos - 1] = child.keys[0].clone();
+                }
+                NodeType::Internal => {
+                    let key = mem::replace(&mut self.keys[pos - 1], left.keys.pop().unwrap());
+                    child.keys.insert(0, key);
+                    child.ptrs.insert(0, left.ptrs.pop().unwrap());
+                    child.nodes.insert(0, left.nodes.pop().unwrap());
+                }
+            }
+            left.write(file)?;
+            child.write(file)?;
+            return Ok(None);
+        }
+
+        // borrow the first key of the right sibling
+        if pos + 1 < self.nodes.len() && self.nodes[pos + 1].keys.len() > min {
+            let (left, right) = self.nodes.split_at_mut(pos + 1);
+            let (child, right) = (&mut left[pos], &mut right[0]);
+            match child.node_type {
+                NodeType::Leaf => {
+                    child.keys.push(right.keys.remove(0));
+                    let row = right.rows.as_mut().unwrap().remove(0);
+                    child.rows.as_mut().unwrap().push(row);
+                    self.keys[pos] = right.keys[0].clone();
+                }
+                NodeType::Internal => {
+                    let key = mem::replace(&mut self.keys[pos], right.keys.remove(0));
+                    child.keys.push(key);
+                    child.ptrs.push(right.ptrs.remove(0));
+                    child.nodes.push(right.nodes.remove(0));
+                }
+            }
+            child.write(file)?;
+            right.write(file)?;
+            return Ok(None);
+        }
+
+        // merge the right one of the two siblings into the left one
+        let pos = if pos > 0 { pos - 1 } else { pos };
+        let key = self.keys.remove(pos);
+        let right = self.nodes.remove(pos + 1);
+        self.ptrs.remove(pos + 1);
+        let left = &mut self.nodes[pos];
+        match left.node_type {
+            NodeType::Leaf => {
+                left.keys.extend(right.keys);
+                left.rows.as_mut().unwrap().extend(right.rows.unwrap());
+                left.ptrs[1] = right.ptrs[1];
+                left.write(file)?;
+                if right.ptrs[1] != NULL_PAGE {
+                    let pid = left.pid;
+                    return self.link_next_leaf(pos, Some(pid), file);
+                }
+            }
+            NodeType::Internal => {
+                left.keys.push(key);
+                left.keys.extend(right.keys);
+                left.ptrs.extend(right.ptrs);
+                left.nodes.extend(right.nodes);
+                left.write(file)?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// point the leaf after the subtree of the child `pos` back to the leaf `pending`
+    ///
+    /// Return `pending` if the leaf after is not under this node, so the parent should link it.
+    fn link_next_leaf(
+        &mut self,
+        pos: usize,
+        pending: Option<PagePtr>,
+        file: Option<&PageFile>,
+    ) -> Result<Option<PagePtr>, DiskError> {
+        let pid = match pending {
+            Some(pid) => pid,
+            None => return Ok(None),
+        };
+        if pos + 1 >= self.nodes.len() {
+            return Ok(Some(pid));
+        }
+        let mut node = &mut self.nodes[pos + 1];
+        node.load(file)?;
+        while node.node_type == NodeType::Internal {
+            node = &mut node.nodes[0];
+            node.load(file)?;
+        }
+        node.ptrs[0] = pid;
+        node.write(file)?;
+        Ok(None)
+    }
+
     /// find_ptr: upper-bounded binary searching the key to find the page
     ///
     /// Internal Node:
@@ -119,7 +790,7 @@ impl<T: PartialOrd> BPlusTree<T> {
     /// - Find Key=1 -> P0
     /// - Find Key=2 -> P1
     /// - Find Key=3 -> P1
-    fn find_ptr(arr: &Vec<T>, left: usize, right: usize, val: T) -> usize {
+    fn find_ptr(arr: &[T], left: usize, right: usize, val: T) -> usize {
         let mut l = left as i32;
         let mut r = right as i32;
         let mut pos = 0;
@@ -158,6 +829,267 @@ fn get_leaf_capacity(ptr_size: usize, key_size: usize, row_ptr_size: usize) -> u
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::page::get_page_size;
+    use proptest::prelude::*;
+    use std::collections::{BTreeMap, HashSet};
+    use std::fs;
+    use std::ops::RangeBounds;
+    use std::path::Path;
+
+    /// a tree of int keys, whose large key size makes the nodes small, so a few keys grow the tree
+    fn small_tree(key_size: usize) -> Box<BPlusTree<i32>> {
+        BPlusTree::new(0, NodeType::Leaf, DataType::Int, 4, key_size, Some(8))
+    }
+
+    /// check the invariants of the tree, and return the keys and records of the leaves in order
+    fn check_tree(tree: &BPlusTree<i32>) -> Vec<(i32, RowPtr)> {
+        let mut leaves = vec![];
+        let mut pids = HashSet::new();
+        check_node(tree, true, None, None, &mut leaves, &mut pids);
+
+        // the leaves are linked in order
+        for (i, leaf) in leaves.iter().enumerate() {
+            let prev = if i > 0 { leaves[i - 1].pid } else { NULL_PAGE };
+            let next = if i + 1 < leaves.len() {
+                leaves[i + 1].pid
+            } else {
+                NULL_PAGE
+            };
+            assert_eq!(leaf.ptrs, vec![prev, next]);
+        }
+        leaves
+            .iter()
+            .flat_map(|leaf| leaf.keys.iter().cloned().zip(leaf.rows.clone().unwrap()))
+            .collect()
+    }
+
+    fn check_node<'a>(
+        node: &'a BPlusTree<i32>,
+        is_root: bool,
+        low: Option<i32>,
+        high: Option<i32>,
+        leaves: &mut Vec<&'a BPlusTree<i32>>,
+        pids: &mut HashSet<u32>,
+    ) {
+        assert!(pids.insert(node.pid));
+        assert!(node.pid < node.next_pid || !is_root);
+        assert!(node.keys.len() <= node.capacity);
+        if !is_root {
+            assert!(node.keys.len() >= node.min_keys());
+        }
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(node.keys.iter().all(|k| low.is_none_or(|l| *k >= l)));
+        assert!(node.keys.iter().all(|k| high.is_none_or(|h| *k < h)));
+        match node.node_type {
+            NodeType::Leaf => {
+                assert_eq!(node.height, 1);
+                leaves.push(node);
+            }
+            NodeType::Internal => {
+                assert_eq!(node.nodes.len(), node.keys.len() + 1);
+                assert_eq!(node.ptrs, node.nodes.iter().map(|n| n.pid).collect::<Vec<u32>>());
+                for (i, child) in node.nodes.iter().enumerate() {
+                    assert_eq!(child.height + 1, node.height);
+                    let low = if i > 0 { Some(node.keys[i - 1]) } else { low };
+                    let high = if i < node.keys.len() { Some(node.keys[i]) } else { high };
+                    check_node(child, false, low, high, leaves, pids);
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(i32, u32),
+        Delete(i32),
+    }
+
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => (0..200i32, any::<u32>()).prop_map(|(key, page)| Op::Insert(key, page)),
+            2 => (0..200i32).prop_map(Op::Delete),
+        ]
+    }
+
+    proptest! {
+        /// the tree should work as a `BTreeMap` for any sequence of inserts and deletes
+        #[test]
+        fn test_b_plus_tree_oracle(
+            key_size in prop_oneof![Just(1000usize), Just(2000usize)],
+            ops in prop::collection::vec(op_strategy(), 1..400),
+        ) {
+            let mut tree = small_tree(key_size);
+            let mut oracle: BTreeMap<i32, RowPtr> = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, page) => {
+                        let row = (page, key as usize);
+                        prop_assert_eq!(tree.insert(key, row).unwrap(), oracle.insert(key, row));
+                    }
+                    Op::Delete(key) => prop_assert_eq!(tree.delete(key).unwrap(), oracle.remove(&key)),
+                }
+                let entries = check_tree(&tree);
+                prop_assert_eq!(entries, oracle.iter().map(|(k, r)| (*k, *r)).collect::<Vec<_>>());
+            }
+            for key in 0..200 {
+                prop_assert_eq!(tree.search(key).unwrap(), oracle.get(&key).cloned());
+            }
+        }
+    }
+
+    fn bound_strategy() -> impl Strategy<Value = Bound<i32>> {
+        prop_oneof![
+            (0..200i32).prop_map(Bound::Included),
+            (0..200i32).prop_map(Bound::Excluded),
+            Just(Bound::Unbounded),
+        ]
+    }
+
+    proptest! {
+        /// a range scan should return the same keys as the range of a `BTreeMap`
+        #[test]
+        fn test_range_oracle(
+            key_size in prop_oneof![Just(1000usize), Just(2000usize)],
+            keys in prop::collection::vec(0..200i32, 0..200),
+            low in bound_strategy(),
+            high in bound_strategy(),
+        ) {
+            let mut tree = small_tree(key_size);
+            let mut oracle: BTreeMap<i32, RowPtr> = BTreeMap::new();
+            for key in keys {
+                let row = (key as u32, 0);
+                tree.insert(key, row).unwrap();
+                oracle.insert(key, row);
+            }
+            let entries: Vec<(i32, RowPtr)> = tree.range(low, high).unwrap().map(|e| e.unwrap()).collect();
+            let expected: Vec<(i32, RowPtr)> = oracle
+                .iter()
+                .filter(|(key, _)| RangeBounds::contains(&(low, high), *key))
+                .map(|(k, r)| (*k, *r))
+                .collect();
+            prop_assert_eq!(entries, expected);
+        }
+    }
+
+    #[test]
+    fn test_prefix() {
+        let mut tree: Box<BPlusTree<String>> =
+            BPlusTree::new(0, NodeType::Leaf, DataType::Varchar(255), 4, 1000, Some(8));
+        let words = ["ab", "abc", "abd", "b", "a", "abcd", "aa", "ac", "abb", "ba"];
+        for (i, word) in words.iter().enumerate() {
+            tree.insert(word.to_string(), (i as u32, 0)).unwrap();
+        }
+        let keys: Vec<String> = tree.prefix("ab".to_string()).unwrap().map(|e| e.unwrap().0).collect();
+        assert_eq!(keys, vec!["ab", "abb", "abc", "abcd", "abd"]);
+        assert_eq!(tree.prefix("abc".to_string()).unwrap().count(), 2);
+        assert_eq!(tree.prefix("c".to_string()).unwrap().count(), 0);
+        assert_eq!(tree.prefix("".to_string()).unwrap().count(), words.len());
+    }
+
+    #[test]
+    fn test_insert_delete() {
+        let mut tree = small_tree(1000);
+        assert_eq!(tree.capacity, 4);
+        for i in 0..100 {
+            let key = i * 37 % 100;
+            assert!(tree.insert(key, (key as u32, 0)).unwrap().is_none());
+        }
+        assert_eq!(tree.insert(50, (0, 50)).unwrap(), Some((50, 0)));
+        check_tree(&tree);
+        assert!(tree.height >= 4);
+        assert_eq!(tree.search(50).unwrap(), Some((0, 50)));
+        assert_eq!(tree.search(99).unwrap(), Some((99, 0)));
+        assert!(tree.search(100).unwrap().is_none());
+
+        for key in (0..100).filter(|key| key % 2 == 0) {
+            assert!(tree.delete(key).unwrap().is_some());
+        }
+        assert!(tree.delete(50).unwrap().is_none());
+        assert_eq!(check_tree(&tree).len(), 50);
+        assert!(tree.search(50).unwrap().is_none());
+        assert_eq!(tree.search(51).unwrap(), Some((51, 0)));
+
+        for key in (0..100).filter(|key| key % 2 == 1) {
+            assert!(tree.delete(key).unwrap().is_some());
+        }
+        assert!(check_tree(&tree).is_empty());
+        assert_eq!(tree.height, 1);
+    }
+
+    /// read all the nodes of a tree opened from an index file
+    fn load_all(node: &mut BPlusTree<i32>, file: &PageFile) {
+        node.load(Some(file)).unwrap();
+        for child in node.nodes.iter_mut() {
+            load_all(child, file);
+        }
+    }
+
+    #[test]
+    fn test_save_open() {
+        let dir = "data12";
+        if Path::new(dir).exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+        fs::create_dir(dir).unwrap();
+        let path = format!("{}/tree.idx", dir);
+
+        let mut tree: Box<BPlusTree<i32>> = BPlusTree::create(&path, DataType::Int, 4, 1000, 8).unwrap();
+        for i in 0..60 {
+            let key = i * 37 % 60;
+            assert!(tree.insert(key, (key as u32, 0)).unwrap().is_none());
+        }
+        let entries = check_tree(&tree);
+        drop(tree);
+
+        // only the root is read when the tree is opened, and searching does not keep the nodes
+        let mut tree: Box<BPlusTree<i32>> = BPlusTree::open(&path).unwrap();
+        assert!(tree.height >= 3);
+        assert!(tree.nodes.iter().all(|node| !node.is_loaded));
+        assert_eq!(tree.search(42).unwrap(), Some((42, 0)));
+        assert!(tree.search(60).unwrap().is_none());
+        assert!(tree.nodes.iter().all(|node| !node.is_loaded));
+        assert_eq!(tree.entries().unwrap(), entries);
+
+        // a range scan reads the leaves from their pages
+        let keys: Vec<i32> = tree
+            .range(Bound::Excluded(10), Bound::Included(50))
+            .unwrap()
+            .map(|e| e.unwrap().0)
+            .collect();
+        assert_eq!(keys, (11..=50).collect::<Vec<i32>>());
+        assert!(tree.nodes.iter().all(|node| !node.is_loaded));
+
+        // an insert only rewrites the pages on its path
+        let before = fs::read(&path).unwrap();
+        assert!(tree.insert(60, (60, 0)).unwrap().is_none());
+        let after = fs::read(&path).unwrap();
+        let page_size = get_page_size();
+        let changed = (0..after.len() / page_size)
+            .filter(|pid| {
+                before.get(pid * page_size..(pid + 1) * page_size)
+                    != Some(&after[pid * page_size..(pid + 1) * page_size])
+            })
+            .count();
+        assert!(changed >= 1);
+        assert!(changed <= 2 * tree.height as usize + 2);
+        assert!(changed < before.len() / page_size / 2);
+        assert_eq!(tree.nodes.iter().filter(|node| node.is_loaded).count(), 1);
+
+        for key in (0..60).filter(|key| key % 3 != 0) {
+            assert_eq!(tree.delete(key).unwrap(), Some((key as u32, 0)));
+        }
+        drop(tree);
+
+        let mut tree: Box<BPlusTree<i32>> = BPlusTree::open(&path).unwrap();
+        let file = tree.file.take().unwrap();
+        load_all(&mut tree, &file);
+        let entries = check_tree(&tree);
+        let expected: Vec<(i32, RowPtr)> = (0..=60)
+            .filter(|key| key % 3 == 0)
+            .map(|key| (key, (key as u32, 0)))
+            .collect();
+        assert_eq!(entries, expected);
+    }
 
     #[test]
     fn test_new_b_plus_tree() {
@@ -1,9 +1,14 @@
 use crate::component::datatype::DataType;
+use crate::storage::diskinterface::DiskError;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 pub type PagePtr = u32;
 pub type PageOffset = usize;
 pub type RowPtr = (PagePtr, PageOffset);
 
+/// the page pointer to nothing, such as the previous page of the first leaf
+pub const NULL_PAGE: PagePtr = PagePtr::MAX;
+
 pub trait Tree<T> {
     fn new(
         pid: u32,
@@ -13,10 +18,84 @@ pub trait Tree<T> {
         key_size: usize,
         row_ptr_size: Option<usize>,
     ) -> Box<Self>;
-    fn height(&self) -> u32;
-    fn insert(&mut self, val: T);
-    fn delete(&mut self, val: T);
-    fn search(&self, val: T) -> RowPtr;
+    /// insert the key with its record pointer, and return the old record pointer if the key exists
+    fn insert(&mut self, val: T, row: RowPtr) -> Result<Option<RowPtr>, DiskError>;
+    /// delete the key, and return its record pointer if the key exists
+    fn delete(&mut self, val: T) -> Result<Option<RowPtr>, DiskError>;
+    /// the record pointer of the key
+    fn search(&self, val: T) -> Result<Option<RowPtr>, DiskError>;
+}
+
+/// a key which could be stored in the pages of a tree
+pub trait IndexKey: PartialOrd + Clone {
+    /// the key in `key_size` bytes, padded with zeros
+    fn to_bytes(&self, key_size: usize) -> Result<Vec<u8>, DiskError>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DiskError>;
+    /// if the key starts with the prefix, which is only equal for the keys not of sequences
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self == prefix
+    }
+}
+
+impl IndexKey for i32 {
+    fn to_bytes(&self, key_size: usize) -> Result<Vec<u8>, DiskError> {
+        let mut bytes = vec![];
+        bytes.write_i32::<BigEndian>(*self)?;
+        pad_key(bytes, key_size)
+    }
+
+    fn from_bytes(mut bytes: &[u8]) -> Result<Self, DiskError> {
+        Ok(bytes.read_i32::<BigEndian>()?)
+    }
+}
+
+impl IndexKey for f64 {
+    fn to_bytes(&self, key_size: usize) -> Result<Vec<u8>, DiskError> {
+        let mut bytes = vec![];
+        bytes.write_f64::<BigEndian>(*self)?;
+        pad_key(bytes, key_size)
+    }
+
+    fn from_bytes(mut bytes: &[u8]) -> Result<Self, DiskError> {
+        Ok(bytes.read_f64::<BigEndian>()?)
+    }
+}
+
+impl IndexKey for String {
+    fn to_bytes(&self, key_size: usize) -> Result<Vec<u8>, DiskError> {
+        pad_key(self.as_bytes().to_vec(), key_size)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DiskError> {
+        let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |pos| pos + 1);
+        String::from_utf8(bytes[..len].to_vec()).map_err(|_| DiskError::BytesError)
+    }
+
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self.starts_with(prefix.as_str())
+    }
+}
+
+impl IndexKey for Vec<u8> {
+    fn to_bytes(&self, key_size: usize) -> Result<Vec<u8>, DiskError> {
+        pad_key(self.clone(), key_size)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DiskError> {
+        Ok(bytes.to_vec())
+    }
+
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self.starts_with(prefix)
+    }
+}
+
+fn pad_key(mut bytes: Vec<u8>, key_size: usize) -> Result<Vec<u8>, DiskError> {
+    if bytes.len() > key_size {
+        return Err(DiskError::BytesError);
+    }
+    bytes.resize(key_size, 0);
+    Ok(bytes)
 }
 
 #[derive(PartialEq)]
@@ -1,6 +1,7 @@
 use crate::component::table::Row;
 use crate::sql::worker::{SQLError, SQL};
-use crate::storage::diskinterface::{DiskError, DiskInterface};
+use crate::storage::diskinterface::{DiskError, DiskInterface, IndexMeta};
+use crate::storage::index::Index;
 use std::fmt;
 
 use std::collections::{BTreeMap, VecDeque};
@@ -88,7 +89,7 @@ impl Pool {
             Some(tsql) => tsql,
             None => return Err(PoolError::EntryNotExist),
         };
-        match Pool::hierarchic_check(sql) {
+        match Pool::hierarchic_check(sql, None) {
             Ok(_) => {}
             Err(e) => return Err(e),
         }
@@ -106,76 +107,202 @@ impl Pool {
             }
         }
     }
-    fn hierarchic_check(sql: &SQL) -> Result<(), PoolError> {
+    /// write the dirty database, tables, rows and indexes of the session back to storage under
+    /// `file_base_path`, or the default path if it is `None`
+    fn hierarchic_check(sql: &SQL, file_base_path: Option<&str>) -> Result<(), PoolError> {
+        // a session without any database has nothing to write
+        if sql.database.name.is_empty() {
+            return Ok(());
+        }
         // 1. check dirty bit of database
         if sql.database.is_delete {
-            match DiskInterface::remove_db(&sql.user.name, &sql.database.name, Some(dotenv!("FILE_BASE_PATH"))) {
+            // a new database has nothing in storage to remove
+            if sql.database.is_dirty {
+                return Ok(());
+            }
+            match DiskInterface::remove_db(&sql.user.name, &sql.database.name, file_base_path) {
                 Ok(_) => return Ok(()),
                 Err(e) => return Err(PoolError::DiskError(e)),
             }
         }
         if sql.database.is_dirty {
-            match DiskInterface::create_db(&sql.user.name, &sql.database.name, Some(dotenv!("FILE_BASE_PATH"))) {
+            match DiskInterface::create_db(&sql.user.name, &sql.database.name, file_base_path) {
                 Ok(_) => {}
                 Err(e) => return Err(PoolError::DiskError(e)),
             }
         }
         // 2. check dirty bit of tables
         for (name, table) in sql.database.tables.iter() {
+            // a stored table which is dropped, or truncated and created again, is removed from storage
+            if table.is_stored && (table.is_delete || table.is_dirty) {
+                DiskInterface::drop_table(&sql.user.name, &sql.database.name, name, file_base_path)
+                    .map_err(PoolError::DiskError)?;
+            }
             if table.is_delete {
-                match DiskInterface::drop_table(
-                    &sql.user.name,
-                    &sql.database.name,
-                    &name,
-                    Some(dotenv!("FILE_BASE_PATH")),
-                ) {
-                    Ok(_) => {}
-                    Err(e) => return Err(PoolError::DiskError(e)),
-                }
                 continue;
             }
             if table.is_dirty {
-                match DiskInterface::create_table(
-                    &sql.user.name,
-                    &sql.database.name,
-                    &table,
-                    Some(dotenv!("FILE_BASE_PATH")),
-                ) {
+                match DiskInterface::create_table(&sql.user.name, &sql.database.name, table, file_base_path) {
                     Ok(_) => {}
                     Err(e) => return Err(PoolError::DiskError(e)),
                 }
             }
+            // the rows in storage are migrated to the new schema, before the dirty ones are written
+            if table.is_altered {
+                DiskInterface::alter_table(&sql.user.name, &sql.database.name, table, file_base_path)
+                    .map_err(PoolError::DiskError)?;
+            }
+            // the indexes created or dropped only change the meta, and the other indexes are kept
+            if table.is_index_altered {
+                DiskInterface::alter_indexes(&sql.user.name, &sql.database.name, table, file_base_path)
+                    .map_err(PoolError::DiskError)?;
+            }
+            // the indexes in storage drop the keys of the stored rows before the rows are modified or
+            // deleted, and then have the keys of the rows written
+            let (mut indexes, missing_indexes) = Pool::load_indexes(sql, name, file_base_path)?;
+            let mut stale_rows: Vec<(u32, Row)> = vec![];
+            let mut fresh_rows: Vec<(u32, Row)> = vec![];
+
             // 3. check dirty bit of rows
-            let mut new_row: Vec<Row> = table.rows.clone();
-            let l = new_row.len();
-            for i in 0..l {
-                if !new_row[i].is_dirty {
-                    // remove rows which are not dirty
-                    new_row.remove(i);
+            // rows before `dirty_cursor` are already in the storage, so modify or delete them in place.
+            let cursor = table.dirty_cursor as usize;
+            for (i, row) in table.rows[..cursor].iter().enumerate() {
+                if !row.is_dirty {
+                    continue;
                 }
-            }
-            if !new_row.is_empty() {
-                match DiskInterface::append_rows(
+                let row_range = vec![i as u32, i as u32 + 1];
+                if !indexes.is_empty() {
+                    let old_rows =
+                        DiskInterface::fetch_rows(&sql.user.name, &sql.database.name, name, &row_range, file_base_path)
+                            .map_err(PoolError::DiskError)?;
+                    stale_rows.extend(old_rows.into_iter().map(|old_row| (i as u32, old_row)));
+                }
+                if row.is_delete {
+                    match DiskInterface::delete_rows(
+                        &sql.user.name,
+                        &sql.database.name,
+                        name,
+                        &row_range,
+                        file_base_path,
+                    ) {
+                        Ok(_) => {}
+                        Err(e) => return Err(PoolError::DiskError(e)),
+                    }
+                    continue;
+                }
+                match DiskInterface::modify_rows(
                     &sql.user.name,
                     &sql.database.name,
-                    &name,
-                    &new_row,
-                    Some(dotenv!("FILE_BASE_PATH")),
+                    name,
+                    &row_range,
+                    std::slice::from_ref(row),
+                    file_base_path,
                 ) {
                     Ok(_) => {}
                     Err(e) => return Err(PoolError::DiskError(e)),
                 }
+                fresh_rows.push((i as u32, row.clone()));
+            }
+            // rows after `dirty_cursor` are new rows, and the deleted ones never reach the storage
+            let new_row: Vec<Row> = table.rows[cursor..]
+                .iter()
+                .filter(|row| !row.is_delete)
+                .cloned()
+                .collect();
+            if !new_row.is_empty() {
+                match DiskInterface::append_rows(&sql.user.name, &sql.database.name, name, &new_row, file_base_path) {
+                    Ok(_) => {}
+                    Err(e) => return Err(PoolError::DiskError(e)),
+                }
+            }
+            let new_row_ids = (cursor as u32)..;
+            fresh_rows.extend(new_row_ids.zip(new_row));
+
+            // 4. update the keys of the rows in the indexes, where all the stale keys are dropped first,
+            // so a value moved to another row is not duplicated in a unique index
+            for index in indexes.iter_mut() {
+                for (row_id, row) in stale_rows.iter() {
+                    index.delete(*row_id, row).map_err(PoolError::DiskError)?;
+                }
+                for (row_id, row) in fresh_rows.iter() {
+                    index.insert(*row_id, row).map_err(PoolError::DiskError)?;
+                }
+                DiskInterface::save_index(index, file_base_path).map_err(PoolError::DiskError)?;
+            }
+            // the indexes which aren't in storage are built from the rows written, such as a new one or
+            // the ones of an altered table
+            for index_meta in missing_indexes {
+                let index = DiskInterface::build_index_from_table_bin(
+                    &sql.user.name,
+                    &sql.database.name,
+                    name,
+                    &index_meta.name,
+                    file_base_path,
+                )
+                .map_err(PoolError::DiskError)?;
+                DiskInterface::save_index(&index, file_base_path).map_err(PoolError::DiskError)?;
             }
         }
         Ok(())
     }
+
+    /// load the indexes of the table which are in storage, and list the ones which aren't
+    fn load_indexes(
+        sql: &SQL,
+        table_name: &str,
+        file_base_path: Option<&str>,
+    ) -> Result<(Vec<Index>, Vec<IndexMeta>), PoolError> {
+        let table_meta = DiskInterface::load_table_meta(&sql.user.name, &sql.database.name, table_name, file_base_path)
+            .map_err(PoolError::DiskError)?;
+        let mut indexes = vec![];
+        let mut missing_indexes = vec![];
+        for index_meta in table_meta.get_indexes() {
+            match DiskInterface::load_index(
+                &sql.user.name,
+                &sql.database.name,
+                table_name,
+                &index_meta.name,
+                file_base_path,
+            ) {
+                Ok(index) => indexes.push(index),
+                Err(DiskError::TableIdxFileNotExists) => missing_indexes.push(index_meta),
+                Err(e) => return Err(PoolError::DiskError(e)),
+            }
+        }
+        Ok((indexes, missing_indexes))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::component::database::Database;
+    use crate::component::datatype::DataType;
+    use crate::sql::parser::Parser;
+    use crate::storage::bytescoder::BytesCoder;
+    use crate::storage::index::RowPair;
+    use std::fs;
+    use std::path::Path;
     use std::thread;
 
+    /// load the database as a new session does, with the rows of its tables under `file_base_path`
+    fn load_sql(username: &str, db_name: &str, file_base_path: &str) -> SQL {
+        let mut sql = SQL::new(username).unwrap();
+        sql.database = Database::load_db(username, db_name, Some(file_base_path)).unwrap();
+        for table in sql.database.tables.values_mut() {
+            table
+                .load_all_rows_data(username, db_name, Some(file_base_path))
+                .unwrap();
+        }
+        sql
+    }
+
+    fn run(sql: &mut SQL, queries: &[&str]) {
+        for query in queries {
+            Parser::new(query).unwrap().parse(sql).unwrap();
+        }
+    }
+
     #[test]
     fn test_multithread_correctness() {}
 
@@ -186,7 +313,73 @@ mod tests {
     fn test_db_writeback() {}
 
     #[test]
-    fn test_table_writeback() {}
+    fn test_table_writeback() {
+        let file_base_path = "data16";
+        if Path::new(file_base_path).exists() {
+            fs::remove_dir_all(file_base_path).unwrap();
+        }
+        DiskInterface::create_file_base(Some(file_base_path)).unwrap();
+        DiskInterface::create_username("crazyguy", Some(file_base_path)).unwrap();
+
+        println!("a new table is written with its rows, and the indexes are built from them");
+        let mut sql = SQL::new("crazyguy").unwrap();
+        sql.create_database("PoolDB", false).unwrap();
+        run(
+            &mut sql,
+            &[
+                "create table t1 (a1 int primary key, a2 varchar(10));",
+                "create unique index i1 on t1 (a2);",
+                "insert into t1(a1, a2) values (1, 'a'), (2, 'b'), (3, 'c');",
+            ],
+        );
+        Pool::hierarchic_check(&sql, Some(file_base_path)).unwrap();
+
+        println!("update, delete and append, where the values of the unique index move between the rows");
+        let mut sql = load_sql("crazyguy", "PoolDB", file_base_path);
+        run(
+            &mut sql,
+            &[
+                "update t1 set a2 = 'x' where a1 = 1;",
+                "update t1 set a2 = 'a' where a1 = 3;",
+                "delete from t1 where a1 = 2;",
+                "insert into t1(a1, a2) values (4, 'b'), (5, 'e');",
+                "delete from t1 where a1 = 5;",
+            ],
+        );
+        Pool::hierarchic_check(&sql, Some(file_base_path)).unwrap();
+
+        let mut sql = load_sql("crazyguy", "PoolDB", file_base_path);
+        let table = &sql.database.tables["t1"];
+        assert_eq!(table.dirty_cursor, 4); // the deleted new row is not written
+        assert!(table.rows[1].is_delete);
+        run(&mut sql, &["select a1, a2 from t1;"]);
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\"],\"rows\":[[\"1\",\"x\"],[\"3\",\"a\"],[\"4\",\"b\"]]}".to_string()
+        );
+
+        // the keys of the indexes are the ones of the rows written, in the order of the keys
+        let key = |datatype: &DataType, value: &str| BytesCoder::attr_to_key(datatype, value).unwrap();
+        let index = DiskInterface::load_index("crazyguy", "PoolDB", "t1", "i1", Some(file_base_path)).unwrap();
+        let varchar = DataType::Varchar(10);
+        assert_eq!(
+            index.entries().unwrap(),
+            vec![
+                RowPair::new(2, key(&varchar, "a")),
+                RowPair::new(3, key(&varchar, "b")),
+                RowPair::new(0, key(&varchar, "x")),
+            ]
+        );
+        let index = DiskInterface::load_index("crazyguy", "PoolDB", "t1", "a1", Some(file_base_path)).unwrap();
+        assert_eq!(
+            index.entries().unwrap(),
+            vec![
+                RowPair::new(0, key(&DataType::Int, "1")),
+                RowPair::new(2, key(&DataType::Int, "3")),
+                RowPair::new(3, key(&DataType::Int, "4")),
+            ]
+        );
+    }
 
     #[test]
     fn test_create_row_writeback() {}
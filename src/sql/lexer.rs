@@ -60,7 +60,7 @@ impl Scanner {
                         } else if x == quote {
                             let word = self.message.get(self.pos.cursor_l + 1..self.pos.cursor_r - 1).unwrap(); // delete quotes
                             self.tokens
-                                .push(symbol::sym(word, symbol::Token::Identifier, symbol::Group::Identifier));
+                                .push(symbol::sym(word, symbol::Token::Identifier, symbol::Group::Literal));
                             is_quoted = false;
                             self.pos.cursor_l = self.pos.cursor_r;
                             quote = '\0';
@@ -194,8 +194,20 @@ impl Scanner {
                             }
                             // A special case
                             '*' => {
+                                let word = self.message.get(self.pos.cursor_l..self.pos.cursor_r).unwrap();
+                                let mut name = String::from("*");
+                                if word.ends_with('.') {
+                                    // all fields of a table, such as `t1.*`
+                                    name = format!("{}*", word);
+                                } else if !word.is_empty() {
+                                    let token = match symbol::SYMBOLS.get(word) {
+                                        Some(token) => token.clone(),
+                                        None => symbol::sym(word, symbol::Token::Identifier, symbol::Group::Identifier),
+                                    };
+                                    self.tokens.push(token);
+                                }
                                 self.tokens.push(symbol::sym(
-                                    "*",
+                                    &name,
                                     symbol::Token::Identifier,
                                     symbol::Group::Identifier,
                                 ));
@@ -227,7 +239,7 @@ fn is_identifier_char(ch: char) -> bool {
 }
 
 fn is_operator(ch: char) -> bool {
-    ch == '>' || ch == '=' || ch == '<' || ch == '-' || ch == '+'
+    ch == '>' || ch == '=' || ch == '<' || ch == '-' || ch == '+' || ch == '/' || ch == '|'
 }
 
 fn is_delimiter(ch: char) -> bool {
@@ -249,7 +261,7 @@ mod tests {
         println!("test{:?}", x.name);
         assert_eq!(
             format!("{:?}, {:?}, {:?}", x.name, x.token, x.group),
-            "\"123://\", Identifier, Identifier"
+            "\"123://\", Identifier, Literal"
         );
 
         let message = "'qqq\"' ,123";
@@ -260,7 +272,7 @@ mod tests {
         println!("test{:?}", x.name);
         assert_eq!(
             format!("{:?}, {:?}, {:?}", x.name, x.token, x.group),
-            "\"qqq\\\"\", Identifier, Identifier"
+            "\"qqq\\\"\", Identifier, Literal"
         );
 
         let message = "\"qqq\', 123 ";
@@ -330,7 +342,7 @@ mod tests {
         let x = iter.next().unwrap();
         assert_eq!(
             format!("{:?}, {:?}, {:?}", x.name, x.token, x.group),
-            "\"is null\", IsNull, Keyword"
+            "\"is null\", IsNull, Operator"
         );
         let x = iter.next().unwrap();
         assert_eq!(
@@ -422,7 +434,7 @@ mod tests {
         let x = iter.next().unwrap();
         assert_eq!(
             format!("{:?}, {:?}, {:?}", x.name, x.token, x.group),
-            "\"cardinal\", Identifier, Identifier"
+            "\"cardinal\", Identifier, Literal"
         );
         let x = iter.next().unwrap();
         assert_eq!(
@@ -432,7 +444,7 @@ mod tests {
         let x = iter.next().unwrap();
         assert_eq!(
             format!("{:?}, {:?}, {:?}", x.name, x.token, x.group),
-            "\"norway\", Identifier, Identifier"
+            "\"norway\", Identifier, Literal"
         );
         let x = iter.next().unwrap();
         assert_eq!(
@@ -469,6 +481,18 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn test_scan_star() {
+        let message = "select t1.*, a1*2, * from t1;";
+        let mut s = Scanner::new(message);
+        let tokens = s.scan_tokens().unwrap();
+        let names: Vec<&str> = tokens.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["select", "t1.*", ",", "a1", "*", "2", ",", "*", "from", "t1", ";"]
+        );
+    }
+
     #[test]
     fn test_scan_tokens_error() {
         let message = "create table $1234";
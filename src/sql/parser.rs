@@ -1,17 +1,32 @@
 use crate::component::datatype::DataType;
+use crate::component::expression;
+use crate::component::field::Checker;
 use crate::component::field::Field;
+use crate::component::field::Operator;
+use crate::component::table::Constraint;
 use crate::component::table::Table;
 use crate::sql::lexer::LexerError;
 use crate::sql::lexer::Scanner;
+use crate::sql::query::AggregateFunction;
+use crate::sql::query::Aggregation;
+use crate::sql::query::AlterAction;
+use crate::sql::query::Assignment;
+use crate::sql::query::InsertValues;
 use crate::sql::query::Join;
 use crate::sql::query::Node;
+use crate::sql::query::NodePtr;
 use crate::sql::query::QueryData;
+use crate::sql::query::SetOperator;
+use crate::sql::query::SortDirection;
 use crate::sql::query::TopType;
+use crate::sql::symbol::sym;
 use crate::sql::symbol::Group;
 use crate::sql::symbol::Symbol;
 use crate::sql::symbol::Token;
+use crate::sql::symbol::SYMBOLS;
 use crate::sql::worker::SQLError;
 use crate::sql::worker::SQL;
+use crate::storage::diskinterface::IndexMeta;
 use std::fmt;
 use std::iter::Peekable;
 use std::slice::Iter;
@@ -62,23 +77,43 @@ impl Parser {
             Some(symbol) => match symbol.token {
                 Token::CreateDatabase => {
                     let _ = iter.next(); // "create database"
+                    let if_not_exists = parse_if_token(&mut iter, Token::IfNotExists);
 
                     let db_name_sym = iter
                         .next()
                         .ok_or(ParserError::SyntaxError(String::from("no db name")))?;
                     check_id(db_name_sym)?;
 
-                    sql.create_database(&db_name_sym.name)
+                    sql.create_database(&db_name_sym.name, if_not_exists)
                         .map_err(|e| ParserError::SQLError(e))?;
 
                     return Ok(());
                 }
                 Token::CreateTable => {
                     debug!("-> create table");
-                    let table = parser_create_table(&mut iter)?;
-                    sql.create_table(&table).map_err(|e| ParserError::SQLError(e))?;
+                    let (table, if_not_exists) = parser_create_table(&mut iter)?;
+                    sql.create_table(&table, if_not_exists).map_err(ParserError::SQLError)?;
                     return Ok(());
                 }
+                Token::AlterTable => {
+                    debug!("-> alter table");
+                    let (table_name, action) = parser_alter_table(&mut iter)?;
+                    sql.alter_table(&table_name, action).map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::CreateIndex | Token::CreateUniqueIndex => {
+                    debug!("-> create index");
+                    let (table_name, index) = parser_create_index(&mut iter)?;
+                    sql.create_index(&table_name, index).map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::DropIndex => {
+                    debug!("-> drop index");
+                    let (table_name, index_name) = parser_drop_index(&mut iter)?;
+                    sql.drop_index(&table_name, &index_name)
+                        .map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
                 Token::InsertInto => {
                     debug!("-> insert into table");
                     let (table_name, attrs, rows) = parser_insert_into_table(&mut iter)?;
@@ -86,6 +121,20 @@ impl Parser {
                         .map_err(|e| ParserError::SQLError(e))?;
                     Ok(())
                 }
+                Token::Update => {
+                    debug!("-> update table");
+                    let (table_name, assignments, predicate) = parser_update_table(&mut iter)?;
+                    sql.update_table(&table_name, assignments, predicate)
+                        .map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::Delete => {
+                    debug!("-> delete from table");
+                    let (table_name, predicate) = parser_delete_from_table(&mut iter)?;
+                    sql.delete_from_table(&table_name, predicate)
+                        .map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
                 Token::Select => {
                     debug!("-> select table");
                     sql.querydata = parse_select(&mut iter)?;
@@ -95,27 +144,67 @@ impl Parser {
                 Token::DropTable => {
                     debug!("-> drop table");
                     let _ = iter.next(); // "drop table"
+                    let if_exists = parse_if_token(&mut iter, Token::IfExists);
                     let tb_name_sym = iter
                         .next()
                         .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
                     check_id(tb_name_sym)?;
 
-                    sql.drop_table(&tb_name_sym.name)
+                    sql.drop_table(&tb_name_sym.name, if_exists)
                         .map_err(|e| ParserError::SQLError(e))?;
                     Ok(())
                 }
                 Token::DropDatabase => {
                     debug!("-> drop database");
                     let _ = iter.next(); // "drop database"
+                    let if_exists = parse_if_token(&mut iter, Token::IfExists);
                     let db_name_sym = iter
                         .next()
                         .ok_or(ParserError::SyntaxError(String::from("no db name")))?;
                     check_id(db_name_sym)?;
 
-                    sql.drop_database(&db_name_sym.name)
+                    sql.drop_database(&db_name_sym.name, if_exists)
+                        .map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::TruncateTable => {
+                    debug!("-> truncate table");
+                    let _ = iter.next(); // "truncate table"
+                    let tb_name_sym = iter
+                        .next()
+                        .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
+                    check_id(tb_name_sym)?;
+
+                    sql.truncate_table(&tb_name_sym.name)
                         .map_err(|e| ParserError::SQLError(e))?;
                     Ok(())
                 }
+                Token::ShowDatabases => {
+                    debug!("-> show databases");
+                    sql.show_databases().map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::ShowTables => {
+                    debug!("-> show tables");
+                    sql.show_tables().map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
+                Token::Describe | Token::Desc | Token::ShowColumns => {
+                    debug!("-> describe table");
+                    if symbol.token == Token::ShowColumns {
+                        let _ = iter.next(); // "show columns"
+                        assert_token(iter.next(), Token::From)?;
+                    } else {
+                        let _ = iter.next(); // "describe"
+                    }
+                    let tb_name_sym = iter
+                        .next()
+                        .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
+                    check_id(tb_name_sym)?;
+
+                    sql.describe_table(&tb_name_sym.name).map_err(ParserError::SQLError)?;
+                    Ok(())
+                }
                 _ => {
                     return Err(ParserError::SyntaxError(String::from("unknown keyword")));
                 }
@@ -127,8 +216,11 @@ impl Parser {
     }
 }
 
-fn parser_create_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<Table, ParserError> {
+/// Parse `create table [if not exists] <name> (...)`, and return the table with whether `if not
+/// exists` is given
+fn parser_create_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<(Table, bool), ParserError> {
     let _ = iter.next();
+    let if_not_exists = parse_if_token(iter, Token::IfNotExists);
 
     let table_name_sym = iter
         .next()
@@ -145,91 +237,30 @@ fn parser_create_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<Table, Parse
     loop {
         debug!("   -- new field:");
 
-        let mut field;
+        let field;
 
         match iter.peek() {
             // setting a field
             Some(s) if s.group == Group::Identifier => {
-                // 1. column
-                let var_name = iter
-                    .next()
-                    .ok_or(ParserError::SyntaxError(String::from("miss column name")))?
-                    .name
-                    .clone();
-                debug!("   --- field name: {}", var_name);
-
-                // 2. datatype
-                let var_type_sym = iter
-                    .next()
-                    .ok_or(ParserError::SyntaxError(String::from("miss column type")))?;
-                debug!("   --- field type: {}", var_type_sym.name);
-
-                // 2.1 case: varchar, char
-                if var_type_sym.token == Token::Varchar || var_type_sym.token == Token::Char {
-                    assert_token(iter.next(), Token::ParentLeft)?;
-
-                    let varchar_len_str = iter
-                        .next()
-                        .ok_or(ParserError::SyntaxError(String::from("miss column type length")))?
-                        .name
-                        .clone();
-                    let varchar_len = varchar_len_str
-                        .parse::<u8>()
-                        .map_err(|_| ParserError::SyntaxError(String::from("type length invalid")))?;
-                    debug!("   --- field type length: {}", varchar_len);
-
-                    let datatype = DataType::get(&var_type_sym.name, Some(varchar_len))
-                        .ok_or(ParserError::SyntaxError(String::from("invalid type")))?;
-                    field = Field::new(&var_name, datatype);
-
-                    assert_token(iter.next(), Token::ParentRight)?;
-
-                // 2.2 case: other type
-                } else {
-                    let datatype = DataType::get(&var_type_sym.name, None)
-                        .ok_or(ParserError::SyntaxError(String::from("invalid type")))?;
-                    field = Field::new(&var_name, datatype);
-                }
-                // 3. column properties
-                loop {
-                    match iter.peek() {
-                        Some(s) if s.token == Token::Comma => {
-                            iter.next();
-                            debug!("   go next field");
-                            break;
-                        }
-                        Some(s) if s.token == Token::NotNull => {
-                            iter.next();
-                            field.not_null = true
-                        }
-                        Some(s) if s.token == Token::Default => {
-                            iter.next();
-                            let default_value = iter
-                                .next()
-                                .ok_or(ParserError::SyntaxError(String::from("miss default value")))?
-                                .name
-                                .clone();
-                            field.default = Some(default_value);
-                        }
-                        Some(s) if s.token == Token::Check => {
-                            // TODO: handle check syntax. Do not use `check` in sql now.
-                            return Err(ParserError::SyntaxError(String::from("check syntax error")));
-                        }
-                        Some(s) if s.token == Token::Encrypt => {
-                            iter.next();
-                            field.encrypt = true;
-                        }
-                        // end of table block
-                        Some(s) if s.token == Token::ParentRight => break,
-                        Some(_) | None => return Err(ParserError::SyntaxError(String::from(""))),
-                    }
+                field = parser_field(iter, &mut table)?;
+                if check_token(iter.peek(), Token::Comma) {
+                    iter.next();
+                    debug!("   go next field");
                 }
             }
 
             // setting table properties
             Some(s) if s.group == Group::Keyword => {
-                // TODO: primary key, foreign key & reference
-                return Err(ParserError::SyntaxError(String::from("")));
+                parser_table_constraint(iter, &mut table)?;
+                match iter.peek() {
+                    Some(s) if s.token == Token::Comma => {
+                        iter.next();
+                    }
+                    // end of table block
+                    Some(s) if s.token == Token::ParentRight => {}
+                    Some(_) | None => return Err(ParserError::SyntaxError(String::from(""))),
+                }
+                continue;
             }
 
             // finish table block
@@ -245,12 +276,454 @@ fn parser_create_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<Table, Parse
         debug!("   - insert new field into table");
     }
 
-    Ok(table)
+    // keep the checks in simple form `<column> <operator> <literal>` in the fields
+    let checks = table.checks.clone();
+    table.checks = vec![];
+    for check in checks {
+        if let (Some(operator), Some(left), Some(right)) = (Operator::get(&check.root), &check.left, &check.right) {
+            // a comparison with null is unknown, which is left to the expression
+            let is_literal = right.left.is_none()
+                && right.right.is_none()
+                && right.args.is_empty()
+                && right.root != "null"
+                && !table.fields.contains_key(&right.root);
+            match table.fields.get_mut(&left.root) {
+                Some(field) if is_literal => {
+                    if let Checker::None = field.check {
+                        field.check = Checker::Some(operator, right.root.trim_matches('\'').to_string());
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+        }
+        table.checks.push(check);
+    }
+
+    Ok((table, if_not_exists))
+}
+
+/// Parse `alter table`
+///
+/// Syntax:
+///
+/// ```sql
+/// ALTER TABLE <table> ADD [COLUMN] <column> <datatype> [<column properties>]
+/// ALTER TABLE <table> ADD [CONSTRAINT <name>] <table constraint>
+/// ALTER TABLE <table> DROP CONSTRAINT <name>
+/// ALTER TABLE <table> DROP COLUMN <column>
+/// ALTER TABLE <table> ALTER COLUMN <column> <datatype> [NOT NULL] [DEFAULT <value>]
+/// ALTER TABLE <table> ALTER COLUMN <column> SET DEFAULT <value>
+/// ALTER TABLE <table> ALTER COLUMN <column> DROP DEFAULT
+/// ```
+fn parser_alter_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, AlterAction), ParserError> {
+    let _ = iter.next(); // "alter table"
+
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
+    check_id(table_name_sym)?;
+    let table_name = table_name_sym.name.clone();
+    debug!("   - table name: {}", table_name);
+
+    // the constraints are kept in a table without fields
+    let mut constraints = Table::new(&table_name);
+    let action = match iter.next() {
+        Some(s) if s.token == Token::Add && check_token(iter.peek(), Token::Column) => {
+            iter.next(); // column
+            let field = parser_field(iter, &mut constraints)?;
+            AlterAction::AddField(field, constraints)
+        }
+        Some(s) if s.token == Token::Add && iter.peek().is_some_and(|s| s.group == Group::Identifier) => {
+            let field = parser_field(iter, &mut constraints)?;
+            AlterAction::AddField(field, constraints)
+        }
+        Some(s) if s.token == Token::Add => {
+            parser_table_constraint(iter, &mut constraints)?;
+            AlterAction::AddConstraint(constraints)
+        }
+        Some(s) if s.token == Token::AddConstraint => {
+            let name = parser_constraint_name(iter)?;
+            parser_constraint(iter, &mut constraints, Some(name))?;
+            AlterAction::AddConstraint(constraints)
+        }
+        Some(s) if s.token == Token::DropColumn => {
+            let column_sym = iter
+                .next()
+                .ok_or(ParserError::SyntaxError(String::from("miss column name")))?;
+            check_id(column_sym)?;
+            AlterAction::DropField(column_sym.name.clone())
+        }
+        Some(s) if s.token == Token::AlterColumn => {
+            let column_sym = iter
+                .next()
+                .ok_or(ParserError::SyntaxError(String::from("miss column name")))?;
+            check_id(column_sym)?;
+            let name = column_sym.name.clone();
+            match iter.peek() {
+                Some(s) if s.token == Token::Set => {
+                    iter.next(); // set
+                    assert_token(iter.next(), Token::Default)?;
+                    AlterAction::SetDefault(name, parse_value(iter)?)
+                }
+                Some(s) if s.token == Token::DropDefault => {
+                    iter.next(); // drop default
+                    AlterAction::SetDefault(name, None)
+                }
+                Some(_) => {
+                    let mut field = Field::new(&name, parser_datatype(iter)?);
+                    loop {
+                        match iter.peek() {
+                            Some(s) if s.token == Token::NotNull => {
+                                iter.next();
+                                field.not_null = true;
+                            }
+                            Some(s) if s.token == Token::Default => {
+                                iter.next();
+                                field.default = parse_value(iter)?;
+                            }
+                            Some(_) | None => break,
+                        }
+                    }
+                    AlterAction::AlterField(field)
+                }
+                None => return Err(ParserError::SyntaxError(String::from("miss column type"))),
+            }
+        }
+        Some(s) if s.token == Token::DropConstraint => AlterAction::DropConstraint(parser_constraint_name(iter)?),
+        Some(_) | None => return Err(ParserError::SyntaxError(String::from("invalid alter table syntax"))),
+    };
+
+    match iter.next() {
+        Some(s) if s.token == Token::Semicolon => {}
+        Some(_) => return Err(ParserError::SyntaxError(String::from("invalid alter table syntax"))),
+        None => {}
+    }
+    debug!("   - action: {:?}", action);
+    Ok((table_name, action))
+}
+
+/// Parse `create index`, and return the table name with the index
+///
+/// Syntax:
+///
+/// ```sql
+/// CREATE [UNIQUE] INDEX <index> ON <table> (<column>)
+/// ```
+fn parser_create_index(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, IndexMeta), ParserError> {
+    let is_unique = iter.next().is_some_and(|s| s.token == Token::CreateUniqueIndex);
+
+    let index_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no index name")))?;
+    check_id(index_name_sym)?;
+    assert_token(iter.next(), Token::On)?;
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
+    check_id(table_name_sym)?;
+
+    assert_token(iter.next(), Token::ParentLeft)?;
+    let attr_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no index column")))?;
+    check_id(attr_sym)?;
+    if check_token(iter.peek(), Token::Comma) {
+        return Err(ParserError::SyntaxError(String::from(
+            "an index could only be on a column",
+        )));
+    }
+    assert_token(iter.next(), Token::ParentRight)?;
+
+    match iter.next() {
+        Some(s) if s.token == Token::Semicolon => {}
+        Some(_) => return Err(ParserError::SyntaxError(String::from("invalid create index syntax"))),
+        None => {}
+    }
+
+    let index = IndexMeta::new(&index_name_sym.name, &attr_sym.name, is_unique);
+    debug!("   - index: {:?}", index);
+    Ok((table_name_sym.name.clone(), index))
+}
+
+/// Parse `drop index`, and return the table name with the index name
+///
+/// Syntax:
+///
+/// ```sql
+/// DROP INDEX <index> ON <table>
+/// ```
+fn parser_drop_index(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, String), ParserError> {
+    let _ = iter.next(); // "drop index"
+
+    let index_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no index name")))?;
+    check_id(index_name_sym)?;
+    assert_token(iter.next(), Token::On)?;
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("no table name")))?;
+    check_id(table_name_sym)?;
+    match iter.next() {
+        Some(s) if s.token == Token::Semicolon => {}
+        Some(_) => return Err(ParserError::SyntaxError(String::from("invalid drop index syntax"))),
+        None => {}
+    }
+
+    Ok((table_name_sym.name.clone(), index_name_sym.name.clone()))
+}
+
+/// Parse a column definition of `create table` or `alter table ... add`, till a `,`, `)` or the end
+///
+/// The constraints on the column, such as `primary key`, are set to the table.
+fn parser_field(iter: &mut Peekable<Iter<Symbol>>, table: &mut Table) -> Result<Field, ParserError> {
+    // 1. column
+    let var_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss column name")))?;
+    check_id(var_name_sym)?;
+    let var_name = var_name_sym.name.clone();
+    debug!("   --- field name: {}", var_name);
+
+    // 2. datatype
+    let datatype = parser_datatype(iter)?;
+    let mut field = Field::new(&var_name, datatype);
+
+    // 3. column properties
+    loop {
+        match iter.peek() {
+            Some(s) if s.token == Token::NotNull => {
+                iter.next();
+                field.not_null = true
+            }
+            Some(s) if s.token == Token::Default => {
+                iter.next();
+                field.default = parse_value(iter)?;
+            }
+            Some(s) if s.token == Token::Check => {
+                iter.next();
+                let check = parser_check(iter)?;
+                table.checks.push(*check);
+            }
+            Some(s) if s.token == Token::Encrypt => {
+                iter.next();
+                field.encrypt = true;
+            }
+            Some(s) if s.token == Token::PrimaryKey => {
+                iter.next();
+                set_primary_key(table, vec![var_name.clone()])?;
+            }
+            Some(s) if s.token == Token::Unique => {
+                iter.next();
+                table.unique.push(vec![var_name.clone()]);
+            }
+            Some(s) if s.token == Token::References => {
+                iter.next();
+                let (reference_table, reference_attr) = parser_reference(iter)?;
+                set_foreign_key(table, var_name.clone(), reference_table, reference_attr)?;
+            }
+            // end of the column
+            Some(s) if s.token == Token::Comma || s.token == Token::ParentRight || s.token == Token::Semicolon => break,
+            None => break,
+            Some(_) => return Err(ParserError::SyntaxError(String::from(""))),
+        }
+    }
+    Ok(field)
+}
+
+/// Parse a datatype, such as `int` or `char(7)`
+fn parser_datatype(iter: &mut Peekable<Iter<Symbol>>) -> Result<DataType, ParserError> {
+    let var_type_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss column type")))?;
+    debug!("   --- field type: {}", var_type_sym.name);
+
+    // 2.1 case: varchar, char
+    if var_type_sym.token == Token::Varchar || var_type_sym.token == Token::Char {
+        assert_token(iter.next(), Token::ParentLeft)?;
+
+        let varchar_len_str = iter
+            .next()
+            .ok_or(ParserError::SyntaxError(String::from("miss column type length")))?
+            .name
+            .clone();
+        let varchar_len = varchar_len_str
+            .parse::<u8>()
+            .map_err(|_| ParserError::SyntaxError(String::from("type length invalid")))?;
+        debug!("   --- field type length: {}", varchar_len);
+
+        let datatype = DataType::get(&var_type_sym.name, Some(varchar_len))
+            .ok_or(ParserError::SyntaxError(String::from("invalid type")))?;
+
+        assert_token(iter.next(), Token::ParentRight)?;
+        Ok(datatype)
+
+    // 2.2 case: other type
+    } else {
+        DataType::get(&var_type_sym.name, None).ok_or(ParserError::SyntaxError(String::from("invalid type")))
+    }
+}
+
+/// Parse a table constraint of `create table` or `alter table ... add`
+///
+/// Syntax:
+///
+/// ```sql
+/// [CONSTRAINT <name>] PRIMARY KEY (<column>, <column>)
+/// [CONSTRAINT <name>] UNIQUE (<column>, <column>)
+/// [CONSTRAINT <name>] FOREIGN KEY (<column>) REFERENCES <table>(<column>)
+/// [CONSTRAINT <name>] CHECK (<predicate>)
+/// ```
+fn parser_table_constraint(iter: &mut Peekable<Iter<Symbol>>, table: &mut Table) -> Result<(), ParserError> {
+    let mut name = None;
+    if check_token(iter.peek(), Token::Constraint) {
+        iter.next();
+        name = Some(parser_constraint_name(iter)?);
+    }
+    parser_constraint(iter, table, name)
+}
+
+/// Parse the name after `CONSTRAINT`
+fn parser_constraint_name(iter: &mut Peekable<Iter<Symbol>>) -> Result<String, ParserError> {
+    let constraint_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss constraint name")))?;
+    check_id(constraint_name_sym)?;
+    Ok(constraint_name_sym.name.clone())
+}
+
+/// Parse a table constraint after its name, which is kept in the table if it is named
+fn parser_constraint(
+    iter: &mut Peekable<Iter<Symbol>>,
+    table: &mut Table,
+    name: Option<String>,
+) -> Result<(), ParserError> {
+    if let Some(ref name) = name {
+        if table.named_constraints.contains_key(name) {
+            return Err(ParserError::SyntaxError(format!("constraint {} is duplicated", name)));
+        }
+    }
+
+    let constraint = match iter.next() {
+        Some(s) if s.token == Token::PrimaryKey => {
+            let attrs = get_id_list(iter, true)?;
+            debug!("   - primary key: {:?}", attrs);
+            set_primary_key(table, attrs)?;
+            Constraint::PrimaryKey
+        }
+        Some(s) if s.token == Token::Unique => {
+            let attrs = get_id_list(iter, true)?;
+            debug!("   - unique: {:?}", attrs);
+            table.unique.push(attrs.clone());
+            Constraint::Unique(attrs)
+        }
+        Some(s) if s.token == Token::ForeignKey => {
+            let attrs = get_id_list(iter, true)?;
+            debug!("   - foreign key: {:?}", attrs);
+            if attrs.len() != 1 {
+                return Err(ParserError::SyntaxError(String::from(
+                    "foreign key should be a single column",
+                )));
+            }
+            assert_token(iter.next(), Token::References)?;
+            let (reference_table, reference_attr) = parser_reference(iter)?;
+            set_foreign_key(table, attrs[0].clone(), reference_table, reference_attr)?;
+            Constraint::ForeignKey
+        }
+        Some(s) if s.token == Token::Check => {
+            let check = parser_check(iter)?;
+            table.checks.push((*check).clone());
+            Constraint::Check(*check)
+        }
+        Some(_) | None => return Err(ParserError::SyntaxError(String::from("unknown table constraint"))),
+    };
+
+    if let Some(name) = name {
+        debug!("   - constraint name: {}", name);
+        table.named_constraints.insert(name, constraint);
+    }
+    Ok(())
+}
+
+/// Parse `(<predicate>)` after `CHECK`
+fn parser_check(iter: &mut Peekable<Iter<Symbol>>) -> Result<Box<Node>, ParserError> {
+    assert_token(iter.next(), Token::ParentLeft)?;
+
+    // collect the symbols until the right parenthesis of the check
+    let mut symbols: Vec<&Symbol> = vec![];
+    let mut depth = 0;
+    loop {
+        match iter.next() {
+            Some(s) if s.token == Token::ParentRight && depth == 0 => break,
+            Some(s) => {
+                match s.token {
+                    Token::ParentLeft => depth += 1,
+                    Token::ParentRight => depth -= 1,
+                    _ => {}
+                }
+                symbols.push(s);
+            }
+            None => return Err(ParserError::SyntaxError(String::from("invalid check syntax"))),
+        }
+    }
+    if symbols.is_empty() {
+        return Err(ParserError::SyntaxError(String::from("empty check")));
+    }
+    debug!(
+        "   - check: {:?}",
+        symbols.iter().map(|s| &s.name).collect::<Vec<&String>>()
+    );
+
+    parse_predicate(symbols)
+}
+
+/// Parse `<table>(<column>)` after `REFERENCES`
+fn parser_reference(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, String), ParserError> {
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss reference table name")))?;
+    check_id(table_name_sym)?;
+
+    let attrs = get_id_list(iter, true)?;
+    if attrs.len() != 1 {
+        return Err(ParserError::SyntaxError(String::from(
+            "reference should be a single column",
+        )));
+    }
+    debug!("   - references: {}({})", table_name_sym.name, attrs[0]);
+
+    Ok((table_name_sym.name.clone(), attrs[0].clone()))
+}
+
+fn set_primary_key(table: &mut Table, attrs: Vec<String>) -> Result<(), ParserError> {
+    if !table.primary_key.is_empty() {
+        return Err(ParserError::SyntaxError(String::from("multiple primary keys")));
+    }
+    table.primary_key = attrs;
+    Ok(())
+}
+
+fn set_foreign_key(
+    table: &mut Table,
+    attr: String,
+    reference_table: String,
+    reference_attr: String,
+) -> Result<(), ParserError> {
+    if !table.foreign_key.is_empty() {
+        return Err(ParserError::SyntaxError(String::from(
+            "only one foreign key is supported",
+        )));
+    }
+    table.foreign_key = vec![attr];
+    table.reference_table = Some(reference_table);
+    table.reference_attr = Some(reference_attr);
+    Ok(())
 }
 
 fn parser_insert_into_table(
     iter: &mut Peekable<Iter<Symbol>>,
-) -> Result<(String, Vec<String>, Vec<Vec<String>>), ParserError> {
+) -> Result<(String, Vec<String>, Vec<InsertValues>), ParserError> {
     let _ = iter.next();
 
     let table_name_sym = iter
@@ -267,11 +740,11 @@ fn parser_insert_into_table(
 
     assert_token(iter.next(), Token::Values)?;
 
-    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut rows: Vec<InsertValues> = Vec::new();
     loop {
         match iter.peek() {
             Some(s) if s.token == Token::ParentLeft => {
-                let row = get_id_list(iter, true)?;
+                let row = get_value_list(iter)?;
                 debug!("   -- row: {:?}", row);
                 if attrs.len() != row.len() {
                     return Err(ParserError::SyntaxError(String::from(
@@ -292,6 +765,83 @@ fn parser_insert_into_table(
     Ok((table_name, attrs, rows))
 }
 
+/// Parse update query
+///
+/// The value of each column is an expression, which is evaluated on each row.
+///
+/// Syntax:
+///
+/// ```sql
+/// UPDATE <table> SET <column> = <expression>, <column> = <expression> WHERE <where_condition>
+/// ```
+fn parser_update_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, Vec<Assignment>, NodePtr), ParserError> {
+    let _ = iter.next(); // update
+
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss table name")))?;
+    check_id(table_name_sym)?;
+
+    let table_name = table_name_sym.name.clone();
+    debug!("   - table name: {}", table_name);
+
+    assert_token(iter.next(), Token::Set)?;
+
+    let mut assignments: Vec<Assignment> = vec![];
+    loop {
+        let column_sym = iter
+            .next()
+            .ok_or(ParserError::SyntaxError(String::from("miss column name")))?;
+        check_id(column_sym)?;
+
+        assert_token(iter.next(), Token::EQ)?;
+
+        let value = parse_condition(iter, None)?.ok_or(ParserError::SyntaxError(String::from("miss column value")))?;
+
+        debug!("   -- set {} = {}", column_sym.name, value.root);
+        assignments.push((column_sym.name.clone(), value));
+
+        match iter.peek() {
+            Some(s) if s.token == Token::Comma => {
+                iter.next();
+                continue;
+            }
+            Some(_) | None => break,
+        }
+    }
+
+    let predicate = parse_where(iter)?;
+
+    assert_token(iter.next(), Token::Semicolon)?;
+    Ok((table_name, assignments, predicate))
+}
+
+/// Parse delete query
+///
+/// Syntax:
+///
+/// ```sql
+/// DELETE FROM <table> WHERE <where_condition>
+/// ```
+fn parser_delete_from_table(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, NodePtr), ParserError> {
+    let _ = iter.next(); // delete
+
+    assert_token(iter.next(), Token::From)?;
+
+    let table_name_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss table name")))?;
+    check_id(table_name_sym)?;
+
+    let table_name = table_name_sym.name.clone();
+    debug!("   - table name: {}", table_name);
+
+    let predicate = parse_where(iter)?;
+
+    assert_token(iter.next(), Token::Semicolon)?;
+    Ok((table_name, predicate))
+}
+
 /// Parse select query
 ///
 /// Syntax:
@@ -306,11 +856,49 @@ fn parser_insert_into_table(
 /// WITH {CUBE | ROLLUP}
 /// HAVING <having_condition>
 /// ORDER BY <order_by_list>
+/// LIMIT <number> OFFSET <number>
 /// ```
 ///
 #[inline]
 fn parse_select(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserError> {
-    let _ = iter.next(); // select
+    let query_data = parse_query(iter)?;
+    assert_token(iter.next(), Token::Semicolon)?;
+    Ok(query_data)
+}
+
+/// Parse a select query without the ending `;`, which could be a subquery
+///
+/// A subquery is in parentheses, which could be in the select list as a scalar, in `FROM` or `JOIN`
+/// as a derived table, or in a predicate as a scalar, the values of `IN`, or the rows of `EXISTS`.
+///
+/// The queries could be combined by `UNION`, `UNION ALL`, `INTERSECT` and `EXCEPT` from left to right,
+/// and `ORDER BY` and `LIMIT` at the end are applied to the combined result.
+fn parse_query(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserError> {
+    let mut query_data = parse_query_block(iter)?;
+
+    while let Some(operator) = iter
+        .peek()
+        .filter(|s| s.group == Group::Keyword)
+        .and_then(|s| SetOperator::get(&s.name))
+    {
+        iter.next();
+        debug!("   - set operation: {:?}", operator);
+        let query = parse_query_block(iter)?;
+        query_data.set_operations.push((operator, query));
+    }
+    if !query_data.set_operations.is_empty() && query_data.top != TopType::None {
+        return Err(ParserError::SyntaxError(String::from(
+            "top of the first query is not allowed in set operations",
+        )));
+    }
+
+    parse_order_by_limit(iter, &mut query_data)?;
+    Ok(query_data)
+}
+
+/// Parse a select query before `ORDER BY` and `LIMIT`
+fn parse_query_block(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserError> {
+    assert_token(iter.next(), Token::Select)?;
 
     let mut query_data = QueryData::new();
 
@@ -329,11 +917,15 @@ fn parse_select(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserEr
 
         if check_token(iter.peek(), Token::Percent) {
             iter.next();
-            query_data.top = TopType::Percent(
-                top_spec
-                    .parse::<f32>()
-                    .map_err(|_| ParserError::SyntaxError(String::from("invalid select top syntax")))?,
-            );
+            let percent = top_spec
+                .parse::<f32>()
+                .map_err(|_| ParserError::SyntaxError(String::from("invalid select top syntax")))?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(ParserError::SyntaxError(String::from(
+                    "top percent should be in 0 to 100",
+                )));
+            }
+            query_data.top = TopType::Percent(percent);
         } else {
             query_data.top = TopType::Number(
                 top_spec
@@ -343,11 +935,18 @@ fn parse_select(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserEr
         }
     }
 
-    query_data.fields = get_id_list(iter, false)?;
+    parse_select_list(iter, &mut query_data)?;
 
     assert_token(iter.next(), Token::From)?;
 
-    query_data.tables = get_id_list(iter, false)?;
+    loop {
+        let table = parse_table(iter, &mut query_data)?;
+        query_data.tables.push(table);
+        if !check_token(iter.peek(), Token::Comma) {
+            break;
+        }
+        iter.next();
+    }
 
     loop {
         match check_token(iter.peek(), Token::InnerJoin)
@@ -357,22 +956,14 @@ fn parse_select(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserEr
         {
             true => {
                 let mut join = Join::new(&iter.next().unwrap().name);
-                match iter.next() {
-                    Some(s) if s.token == Token::Identifier => join.table = s.name.clone(),
-                    Some(_) | None => return Err(ParserError::SyntaxError(String::from("invalid select join syntax"))),
-                }
+                join.table = parse_table(iter, &mut query_data)?;
 
                 assert_token(iter.next(), Token::On)?;
 
-                let mut symbols: Vec<&Symbol> = vec![];
-                loop {
-                    match iter.peek() {
-                        Some(s) if s.group == Group::Keyword || s.token == Token::Semicolon => break,
-                        Some(_) => symbols.push(iter.next().unwrap()),
-                        None => break,
-                    }
-                }
-                join.condition = Some(parse_predicate(symbols)?);
+                join.condition = Some(
+                    parse_condition(iter, None)?
+                        .ok_or(ParserError::SyntaxError(String::from("miss join condition")))?,
+                );
 
                 query_data.joins.push(join);
             }
@@ -380,32 +971,469 @@ fn parse_select(iter: &mut Peekable<Iter<Symbol>>) -> Result<QueryData, ParserEr
         }
     }
 
+    query_data.predicate = parse_where(iter)?;
+
+    if check_token(iter.peek(), Token::GroupBy) {
+        iter.next(); // group by
+        query_data.group_fields = get_id_list(iter, false)?;
+    }
+
+    if check_token(iter.peek(), Token::Having) {
+        iter.next(); // having
+        query_data.having = Some(parse_having(iter, &mut query_data)?);
+    }
+
+    Ok(query_data)
+}
+
+/// Parse `ORDER BY` and `LIMIT` if there are
+///
+/// An aggregation could be sorted by, except in set operations which sort the combined result.
+fn parse_order_by_limit(iter: &mut Peekable<Iter<Symbol>>, query_data: &mut QueryData) -> Result<(), ParserError> {
+    if check_token(iter.peek(), Token::OrderBy) {
+        iter.next(); // order by
+        loop {
+            match iter.peek() {
+                Some(s) if s.group == Group::Function && query_data.set_operations.is_empty() => {
+                    let aggregation = parse_aggregation(iter)?;
+                    let name = add_aggregation(query_data, aggregation);
+                    query_data.sort_fields.push(name);
+                }
+                Some(_) | None => {
+                    let field_sym = iter
+                        .next()
+                        .ok_or(ParserError::SyntaxError(String::from("miss order by field")))?;
+                    check_id(field_sym)?;
+                    query_data.sort_fields.push(field_sym.name.clone());
+                }
+            }
+
+            let direction = match iter.peek() {
+                Some(s) if s.token == Token::Desc => {
+                    iter.next();
+                    SortDirection::Desc
+                }
+                Some(s) if s.token == Token::Asc => {
+                    iter.next();
+                    SortDirection::Asc
+                }
+                Some(_) | None => SortDirection::Asc,
+            };
+            query_data.sort_dir.push(direction);
+
+            if !check_token(iter.peek(), Token::Comma) {
+                break;
+            }
+            iter.next();
+        }
+    }
+
+    if check_token(iter.peek(), Token::Limit) {
+        iter.next(); // limit
+        if query_data.top != TopType::None {
+            return Err(ParserError::SyntaxError(String::from(
+                "top and limit cannot be together",
+            )));
+        }
+        query_data.top = TopType::Number(parse_row_count(iter)?);
+
+        if check_token(iter.peek(), Token::Offset) {
+            iter.next(); // offset
+            query_data.offset = parse_row_count(iter)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a table in `FROM` or `JOIN`, and return the name it is referred by
+///
+/// A table could be referred by its alias. A derived table is a subquery with an alias, which is the
+/// name of the table.
+fn parse_table(iter: &mut Peekable<Iter<Symbol>>, query_data: &mut QueryData) -> Result<String, ParserError> {
+    if !check_token(iter.peek(), Token::ParentLeft) {
+        let table_sym = iter
+            .next()
+            .ok_or(ParserError::SyntaxError(String::from("miss table name")))?;
+        check_id(table_sym)?;
+        return match parse_alias(iter)? {
+            Some(alias) => {
+                debug!("   - table {} as {}", table_sym.name, alias);
+                query_data.table_aliases.push((alias.clone(), table_sym.name.clone()));
+                Ok(alias)
+            }
+            None => Ok(table_sym.name.clone()),
+        };
+    }
+
+    let (_, subquery) = parse_subquery(iter)?;
+    let alias = parse_alias(iter)?.ok_or(ParserError::SyntaxError(String::from("miss alias of derived table")))?;
+    debug!("   - derived table: {}", alias);
+    query_data.derived_tables.push((alias.clone(), subquery));
+    Ok(alias)
+}
+
+/// Parse an alias if there is one, which is after `AS` or right after what it names
+fn parse_alias(iter: &mut Peekable<Iter<Symbol>>) -> Result<Option<String>, ParserError> {
+    let is_as = check_token(iter.peek(), Token::As);
+    if is_as {
+        iter.next(); // as
+    }
     match iter.peek() {
-        Some(s) if s.token == Token::Where => {
-            let _ = iter.next();
-            let mut symbols: Vec<&Symbol> = vec![];
-            loop {
-                match iter.peek() {
-                    Some(s) if s.group == Group::Keyword || s.token == Token::Semicolon => break,
-                    Some(_) => symbols.push(iter.next().unwrap()),
-                    None => break,
+        Some(s) if s.group == Group::Identifier && !s.name.contains('.') && !s.name.contains('*') => {
+            let alias = s.name.clone();
+            iter.next();
+            Ok(Some(alias))
+        }
+        Some(_) | None if is_as => Err(ParserError::SyntaxError(String::from("invalid alias"))),
+        Some(_) | None => Ok(None),
+    }
+}
+
+/// Parse a subquery in parentheses, and return its text with the query
+///
+/// The text is the name of the subquery, such as the field name of a scalar subquery.
+fn parse_subquery(iter: &mut Peekable<Iter<Symbol>>) -> Result<(String, QueryData), ParserError> {
+    let begin = iter.clone();
+    assert_token(iter.next(), Token::ParentLeft)?;
+    let query_data = parse_query(iter)?;
+    assert_token(iter.next(), Token::ParentRight)?;
+
+    let num_symbols = begin.len() - iter.len();
+    let text = symbols_to_text(begin.take(num_symbols).collect());
+    Ok((text, query_data))
+}
+
+/// Join the symbols back to a text, such as `(select max(a1) from t1)`
+fn symbols_to_text(symbols: Vec<&Symbol>) -> String {
+    let mut text = String::new();
+    let mut last: Option<&Symbol> = None;
+    for s in symbols {
+        if let Some(last) = last {
+            let is_attached = last.token == Token::ParentLeft
+                || s.token == Token::ParentRight
+                || s.token == Token::Comma
+                || (s.token == Token::ParentLeft && last.group == Group::Function);
+            if !is_attached {
+                text.push(' ');
+            }
+        }
+        match s.group {
+            Group::Literal => text.push_str(&format!("'{}'", s.name)),
+            _ => text.push_str(&s.name),
+        }
+        last = Some(s);
+    }
+    text
+}
+
+#[inline]
+fn is_subquery_begin(iter: &Peekable<Iter<Symbol>>) -> bool {
+    let mut ahead = iter.clone();
+    check_token(ahead.next().as_ref(), Token::ParentLeft) && check_token(ahead.peek(), Token::Select)
+}
+
+/// Parse a number of rows for `LIMIT` and `OFFSET`
+fn parse_row_count(iter: &mut Peekable<Iter<Symbol>>) -> Result<u32, ParserError> {
+    iter.next()
+        .ok_or(ParserError::SyntaxError(String::from("miss number of rows")))?
+        .name
+        .parse::<u32>()
+        .map_err(|_| ParserError::SyntaxError(String::from("invalid number of rows")))
+}
+
+/// Parse the select list, in which are fields, aggregations, scalar subqueries or expressions
+///
+/// Each of them except `*` and `t1.*` could have an alias.
+fn parse_select_list(iter: &mut Peekable<Iter<Symbol>>, query_data: &mut QueryData) -> Result<(), ParserError> {
+    loop {
+        let is_star = match iter.peek() {
+            Some(s) if s.token == Token::Identifier && (s.name == "*" || s.name.ends_with(".*")) => {
+                query_data.fields.push(s.name.clone());
+                iter.next();
+                true
+            }
+            _ => {
+                let begin = iter.clone();
+                let expression = parse_condition(iter, Some(query_data))?
+                    .ok_or(ParserError::SyntaxError(String::from("invalid select list")))?;
+                let num_symbols = begin.len() - iter.len();
+                let text = symbols_to_text(begin.take(num_symbols).collect());
+                add_select_field(query_data, text, *expression);
+                false
+            }
+        };
+
+        let alias = parse_alias(iter)?;
+        if is_star && alias.is_some() {
+            return Err(ParserError::SyntaxError(String::from("* could not have an alias")));
+        }
+        query_data.aliases.push(alias);
+
+        if !check_token(iter.peek(), Token::Comma) {
+            break;
+        }
+        iter.next();
+    }
+    Ok(())
+}
+
+/// Add an item of the select list, which is a field or an aggregation if it is a leaf other than a
+/// literal, and is named by its text if it is computed
+fn add_select_field(query_data: &mut QueryData, text: String, mut expression: Node) {
+    let is_leaf = expression.left.is_none() && expression.right.is_none() && expression.args.is_empty();
+    if is_leaf && expression.subquery.is_some() {
+        let subquery = *expression.subquery.take().unwrap();
+        query_data.fields.push(expression.root.clone());
+        query_data.subquery_fields.push((expression.root, subquery));
+    } else if is_leaf && !expression::is_literal(&expression.root) {
+        query_data.fields.push(expression.root);
+    } else {
+        debug!("   - computed field: {}", text);
+        query_data.fields.push(text.clone());
+        query_data.computed_fields.push((text, expression));
+    }
+}
+
+/// Parse an aggregate function call
+///
+/// Syntax:
+///
+/// ```sql
+/// <function>(*)
+/// <function>(<column>)
+/// <function>(DISTINCT <column>)
+/// ```
+fn parse_aggregation(iter: &mut Peekable<Iter<Symbol>>) -> Result<Aggregation, ParserError> {
+    let function_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss function")))?;
+    let function = AggregateFunction::get(&function_sym.name).ok_or(ParserError::SyntaxError(format!(
+        "unknown function {}",
+        function_sym.name
+    )))?;
+
+    assert_token(iter.next(), Token::ParentLeft)?;
+
+    let is_distinct = check_token(iter.peek(), Token::Distinct);
+    if is_distinct {
+        iter.next();
+    }
+
+    let field_sym = iter
+        .next()
+        .ok_or(ParserError::SyntaxError(String::from("miss aggregation field")))?;
+    check_id(field_sym)?;
+    if field_sym.name == "*" && (function != AggregateFunction::Count || is_distinct) {
+        return Err(ParserError::SyntaxError(String::from("only count(*) is allowed")));
+    }
+
+    assert_token(iter.next(), Token::ParentRight)?;
+
+    let aggregation = Aggregation::new(function, &field_sym.name, is_distinct);
+    debug!("   - aggregation: {}", aggregation.name());
+    Ok(aggregation)
+}
+
+/// Add the aggregation to be computed if it is not added yet, and return its name
+fn add_aggregation(query_data: &mut QueryData, aggregation: Aggregation) -> String {
+    let name = aggregation.name();
+    if !query_data.aggregation_fn.contains(&aggregation) {
+        query_data.aggregation_fn.push(aggregation);
+    }
+    name
+}
+
+/// Parse the predicate of `HAVING`
+///
+/// The aggregations in the predicate are regarded as the fields named by them, such as `count(*)`.
+fn parse_having(iter: &mut Peekable<Iter<Symbol>>, query_data: &mut QueryData) -> Result<Box<Node>, ParserError> {
+    parse_condition(iter, Some(query_data))?.ok_or(ParserError::SyntaxError(String::from("miss having predicate")))
+}
+
+/// Parse the `WHERE` clause if there is one
+fn parse_where(iter: &mut Peekable<Iter<Symbol>>) -> Result<NodePtr, ParserError> {
+    if !check_token(iter.peek(), Token::Where) {
+        return Ok(None);
+    }
+    let _ = iter.next(); // where
+
+    let predicate =
+        parse_condition(iter, None)?.ok_or(ParserError::SyntaxError(String::from("miss where predicate")))?;
+    Ok(Some(predicate))
+}
+
+/// Parse a predicate or an expression till a keyword, `;`, `,`, an alias, or a `)` closing the outer
+/// subquery, `None` if it is empty
+///
+/// A subquery in the predicate is a leaf named by its text, which holds the query, and so is a `case`
+/// before it is grafted. If `query_data` is given, the aggregations are added to it and regarded as
+/// the fields named by them.
+fn parse_condition(
+    iter: &mut Peekable<Iter<Symbol>>,
+    mut query_data: Option<&mut QueryData>,
+) -> Result<NodePtr, ParserError> {
+    let mut symbols: Vec<Symbol> = vec![];
+    let mut subqueries: Vec<(String, QueryData)> = vec![];
+    let mut cases: Vec<(String, Node)> = vec![];
+    let mut depth = 0; // the depth of parentheses
+    loop {
+        if check_token(iter.peek(), Token::Case) {
+            let (name, case) = parse_case(iter, query_data.as_deref_mut())?;
+            symbols.push(sym(&name, Token::Identifier, Group::Identifier));
+            cases.push((name, case));
+            continue;
+        }
+        // an identifier right after an operand is an alias, except `*` for a multiplication
+        let is_alias = depth == 0
+            && symbols.last().is_some_and(is_operand)
+            && iter
+                .peek()
+                .is_some_and(|s| s.token == Token::Identifier && s.name != "*");
+        if is_alias {
+            break;
+        }
+        if is_subquery_begin(iter) {
+            let (name, subquery) = parse_subquery(iter)?;
+            // the values of `in` are in parentheses
+            let is_in_values = symbols
+                .last()
+                .is_some_and(|s| s.token == Token::In || s.token == Token::NotIn);
+            if is_in_values {
+                symbols.push(sym("(", Token::ParentLeft, Group::Delimiter));
+            }
+            symbols.push(sym(&name, Token::Identifier, Group::Identifier));
+            if is_in_values {
+                symbols.push(sym(")", Token::ParentRight, Group::Delimiter));
+            }
+            subqueries.push((name, subquery));
+            continue;
+        }
+        match iter.peek() {
+            Some(s) if s.group == Group::Function && AggregateFunction::get(&s.name).is_some() => match query_data {
+                Some(ref mut query_data) => {
+                    let aggregation = parse_aggregation(iter)?;
+                    let name = add_aggregation(query_data, aggregation);
+                    symbols.push(sym(&name, Token::Identifier, Group::Identifier));
+                }
+                None => symbols.push(iter.next().unwrap().clone()),
+            },
+            Some(s) if s.group == Group::Keyword || s.token == Token::Semicolon => break,
+            Some(s) if (s.token == Token::ParentRight || s.token == Token::Comma) && depth == 0 => break,
+            Some(s) => {
+                match s.token {
+                    Token::ParentLeft => depth += 1,
+                    Token::ParentRight => depth -= 1,
+                    _ => {}
                 }
+                symbols.push(iter.next().unwrap().clone());
             }
-            query_data.predicate = Some(parse_predicate(symbols)?);
+            None => break,
         }
-        Some(_) | None => {}
+    }
+    if symbols.is_empty() {
+        return Ok(None);
     }
 
-    if check_token(iter.peek(), Token::GroupBy) {
-        // TODO:
+    let mut tree = parse_predicate(symbols.iter().collect())?;
+    attach_subqueries(&mut tree, &subqueries)?;
+    graft_cases(&mut tree, &cases);
+    Ok(Some(tree))
+}
+
+/// If the symbol could end an operand, after which there is an operator or the end
+#[inline]
+fn is_operand(s: &Symbol) -> bool {
+    (s.group == Group::Identifier && s.name != "*") || s.group == Group::Literal || s.token == Token::ParentRight
+}
+
+/// Parse a `case` expression, and return its text with the expression
+///
+/// The arguments of the `case` are the conditions and the results in pairs, then the else result if
+/// there is. For a simple `case`, each condition is that the operand equals to the value.
+///
+/// Syntax:
+///
+/// ```sql
+/// CASE WHEN <condition> THEN <result> [WHEN ...] [ELSE <result>] END
+/// CASE <operand> WHEN <value> THEN <result> [WHEN ...] [ELSE <result>] END
+/// ```
+fn parse_case(
+    iter: &mut Peekable<Iter<Symbol>>,
+    mut query_data: Option<&mut QueryData>,
+) -> Result<(String, Node), ParserError> {
+    let begin = iter.clone();
+    assert_token(iter.next(), Token::Case)?;
+    let operand = parse_condition(iter, query_data.as_deref_mut())?;
+
+    let miss = |part: &str| ParserError::SyntaxError(format!("miss {} of case", part));
+    let mut args: Vec<Node> = vec![];
+    while check_token(iter.peek(), Token::When) {
+        iter.next(); // when
+        let when = parse_condition(iter, query_data.as_deref_mut())?.ok_or_else(|| miss("when"))?;
+        let condition = match operand {
+            Some(ref operand) => Node::new(String::from("=")).left(*operand.clone()).right(*when),
+            None => *when,
+        };
+        assert_token(iter.next(), Token::Then)?;
+        let result = parse_condition(iter, query_data.as_deref_mut())?.ok_or_else(|| miss("then"))?;
+        args.push(condition);
+        args.push(*result);
+    }
+    if args.is_empty() {
+        return Err(miss("when"));
+    }
+    if check_token(iter.peek(), Token::Else) {
+        iter.next(); // else
+        let otherwise = parse_condition(iter, query_data)?.ok_or_else(|| miss("else"))?;
+        args.push(*otherwise);
     }
+    assert_token(iter.next(), Token::End)?;
 
-    if check_token(iter.peek(), Token::OrderBy) {
-        // TODO:
+    let num_symbols = begin.len() - iter.len();
+    let text = symbols_to_text(begin.take(num_symbols).collect());
+    Ok((text, Node::new(String::from("case")).args(args)))
+}
+
+/// replace the leaves of the `case` expressions, which are named by their text
+fn graft_cases(node: &mut Node, cases: &[(String, Node)]) {
+    if node.left.is_none() && node.right.is_none() && node.args.is_empty() {
+        if let Some((_, case)) = cases.iter().find(|(name, _)| name == &node.root) {
+            *node = case.clone();
+            return;
+        }
+    }
+    if let Some(ref mut left) = node.left {
+        graft_cases(left, cases);
+    }
+    if let Some(ref mut right) = node.right {
+        graft_cases(right, cases);
+    }
+    for arg in node.args.iter_mut() {
+        graft_cases(arg, cases);
     }
+}
 
-    assert_token(iter.next(), Token::Semicolon)?;
-    Ok(query_data)
+/// put the queries into the leaves of the subqueries, which are named by the text of the queries
+fn attach_subqueries(node: &mut Node, subqueries: &[(String, QueryData)]) -> Result<(), ParserError> {
+    if node.left.is_none() && node.right.is_none() && node.args.is_empty() {
+        if let Some((_, subquery)) = subqueries.iter().find(|(name, _)| name == &node.root) {
+            node.subquery = Some(Box::new(subquery.clone()));
+        }
+    }
+    if let Some(ref mut left) = node.left {
+        attach_subqueries(left, subqueries)?;
+    }
+    if let Some(ref mut right) = node.right {
+        attach_subqueries(right, subqueries)?;
+    }
+    for arg in node.args.iter_mut() {
+        attach_subqueries(arg, subqueries)?;
+    }
+    if node.root == "exists" && node.args.iter().any(|arg| arg.subquery.is_none()) {
+        return Err(ParserError::SyntaxError(String::from("exists should be on a subquery")));
+    }
+    Ok(())
 }
 
 /// Parse a predicate as a tree
@@ -415,50 +1443,168 @@ fn parse_predicate(symbols: Vec<&Symbol>) -> Result<Box<Node>, ParserError> {
 }
 
 /// Parse a postfix to a binary tree, and do semantic check
+///
+/// The arguments of a function begin at a `(` and end at the function, so do the values of `in`. A
+/// `between` takes the expression and its lower bound as a binary operator, and the upper bound is
+/// given by the next `and`.
 fn parse_postfix_tree(symbols: Vec<&Symbol>) -> Result<Box<Node>, ParserError> {
     let mut iter = symbols.iter();
     let mut nodes_stack: Vec<Node> = vec![];
+    let mut args_begins: Vec<usize> = vec![]; // where the arguments of each function begin in the stack
     loop {
         match iter.next() {
             Some(s) if s.group == Group::Identifier => nodes_stack.push(Node::new(s.name.clone())),
+            // keep the quotes, so a literal is distinguished from a field
+            Some(s) if s.group == Group::Literal => nodes_stack.push(Node::new(format!("'{}'", s.name))),
+            Some(s) if s.token == Token::ParentLeft => args_begins.push(nodes_stack.len()),
+            Some(s) if s.group == Group::Function => {
+                let begin = args_begins
+                    .pop()
+                    .ok_or(ParserError::SyntaxError(String::from("invalid function syntax")))?;
+                let args = nodes_stack.split_off(begin);
+                if args.is_empty() {
+                    return Err(ParserError::SyntaxError(format!("miss arguments of {}", s.name)));
+                }
+                nodes_stack.push(Node::new(s.name.clone()).args(args));
+            }
             Some(s) if s.group == Group::Operator => match s.token {
                 Token::AND | Token::OR => {
-                    let tree = Node::new(s.name.clone())
-                        .right(nodes_stack.pop().unwrap())
-                        .left(nodes_stack.pop().unwrap());
-                    nodes_stack.push(tree);
+                    let right = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    let mut left = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    let right = match s.token {
+                        Token::AND => complete_between(&mut left, right),
+                        _ => Some(right),
+                    };
+                    match right {
+                        Some(right) => nodes_stack.push(Node::new(s.name.clone()).right(right).left(left)),
+                        None => nodes_stack.push(left),
+                    }
                 }
                 Token::NOT => {
                     let tree = Node::new(s.name.clone()).right(nodes_stack.pop().unwrap());
                     nodes_stack.push(tree);
                 }
-                Token::LT | Token::LE | Token::EQ | Token::NE | Token::GT | Token::GE => {
-                    let right = nodes_stack.pop().unwrap();
-                    let left = nodes_stack.pop().unwrap();
-
-                    // TODO: check right value
-                    // TODO: check left value
+                // postfix, whose operand is on the left
+                Token::IsNull | Token::IsNotNull => {
+                    let left = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    nodes_stack.push(Node::new(s.name.clone()).left(left));
+                }
+                Token::In | Token::NotIn => {
+                    let begin = args_begins
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(format!("miss the values of {}", s.name)))?;
+                    let values = nodes_stack.split_off(begin);
+                    if values.is_empty() {
+                        return Err(ParserError::SyntaxError(format!("miss the values of {}", s.name)));
+                    }
+                    let left = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    nodes_stack.push(Node::new(s.name.clone()).left(left).args(values));
+                }
+                Token::Exists => {
+                    let subquery = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("miss the subquery of exists")))?;
+                    nodes_stack.push(Node::new(s.name.clone()).args(vec![subquery]));
+                }
+                // the upper bound is added by `and`
+                Token::Between | Token::NotBetween => {
+                    let lower = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    let left = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    nodes_stack.push(Node::new(s.name.clone()).left(left).args(vec![lower]));
+                }
+                Token::LT
+                | Token::LE
+                | Token::EQ
+                | Token::NE
+                | Token::GT
+                | Token::GE
+                | Token::Plus
+                | Token::Minus
+                | Token::Multiply
+                | Token::Divide
+                | Token::Concat
+                | Token::Like
+                | Token::NotLike
+                | Token::Escape => {
+                    let right = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
+                    let left = nodes_stack
+                        .pop()
+                        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
 
                     let tree = Node::new(s.name.clone()).right(right).left(left);
                     nodes_stack.push(tree);
                 }
                 _ => {}
             },
-            Some(_) => return Err(ParserError::SyntaxError(String::from("invalid predicate syntax"))),
+            Some(s) => return Err(ParserError::SyntaxError(format!("unexpected {} in predicate", s.name))),
             None => break,
         }
     }
 
-    let tree = nodes_stack.pop().unwrap();
+    let tree = nodes_stack
+        .pop()
+        .ok_or(ParserError::SyntaxError(String::from("invalid predicate syntax")))?;
 
-    if nodes_stack.len() != 0 {
+    if !nodes_stack.is_empty() || !args_begins.is_empty() {
         return Err(ParserError::SyntaxError(String::from("invalid predicate syntax")));
     }
+    if has_incomplete_between(&tree) {
+        return Err(ParserError::SyntaxError(String::from(
+            "miss the upper bound of between",
+        )));
+    }
 
     Ok(Box::new(tree))
 }
 
+#[inline]
+fn is_between(node: &Node) -> bool {
+    node.root == "between" || node.root == "not between"
+}
+
+/// add the upper bound to the last `between` without it, which is at the right most of the tree
+///
+/// Return the upper bound back if there is no such `between`.
+fn complete_between(node: &mut Node, upper: Node) -> Option<Node> {
+    if is_between(node) && node.args.len() == 1 {
+        node.args.push(upper);
+        return None;
+    }
+    match node.root.as_str() {
+        "and" | "or" | "not" => match node.right {
+            Some(ref mut right) => complete_between(right, upper),
+            None => Some(upper),
+        },
+        _ => Some(upper),
+    }
+}
+
+fn has_incomplete_between(node: &Node) -> bool {
+    if is_between(node) && node.args.len() != 2 {
+        return true;
+    }
+    let children = node.left.iter().chain(node.right.iter()).map(|child| child.as_ref());
+    children.chain(node.args.iter()).any(has_incomplete_between)
+}
+
 /// parse predicate tokens from infix to postfix
+///
+/// A `*` identifier between operands is a multiplication. For a function call, the `(` after the
+/// function is also put into the output, to mark where the arguments begin. So is the `(` after `in`.
 #[allow(unused_assignments)]
 fn parse_infix_postfix(symbols: Vec<&Symbol>) -> Result<Vec<&Symbol>, ParserError> {
     let mut iter = symbols.iter();
@@ -469,19 +1615,44 @@ fn parse_infix_postfix(symbols: Vec<&Symbol>) -> Result<Vec<&Symbol>, ParserErro
         match iter.next() {
             Some(s) if s.token == Token::ParentLeft => {
                 parent_counter += 1;
+                if let Some(last) = stack.last() {
+                    if last.group == Group::Function || last.token == Token::In || last.token == Token::NotIn {
+                        output.push(*s);
+                    }
+                }
                 stack.push(*s);
             }
-            Some(s) if s.token == Token::ParentRight => loop {
-                match stack.pop() {
-                    Some(s_) if s_.token == Token::ParentLeft => {
-                        parent_counter -= 1;
-                        break;
+            Some(s) if s.token == Token::ParentRight => {
+                loop {
+                    match stack.pop() {
+                        Some(s_) if s_.token == Token::ParentLeft => {
+                            parent_counter -= 1;
+                            break;
+                        }
+                        Some(s_) => output.push(s_),
+                        None => break,
+                    }
+                }
+                // the function is after its arguments
+                if let Some(last) = stack.last() {
+                    if last.group == Group::Function {
+                        output.push(stack.pop().unwrap());
                     }
-                    Some(s_) => output.push(s_),
-                    None => break,
+                }
+            }
+            // the end of an argument
+            Some(s) if s.token == Token::Comma => loop {
+                match stack.last() {
+                    Some(last) if last.token != Token::ParentLeft => output.push(stack.pop().unwrap()),
+                    Some(_) | None => break,
                 }
             },
-            Some(s) if s.group == Group::Operator => {
+            Some(s) if s.group == Group::Function => stack.push(*s),
+            Some(s) if s.group == Group::Operator || (s.group == Group::Identifier && s.name == "*") => {
+                let s: &Symbol = match s.group {
+                    Group::Operator => s,
+                    _ => &SYMBOLS["*"],
+                };
                 loop {
                     match stack.last() {
                         Some(last) if last.group == Group::Operator => {
@@ -497,7 +1668,7 @@ fn parse_infix_postfix(symbols: Vec<&Symbol>) -> Result<Vec<&Symbol>, ParserErro
                         Some(_) | None => break,
                     }
                 }
-                stack.push(*s);
+                stack.push(s);
             }
             Some(s) => output.push(*s),
             None => {
@@ -521,9 +1692,12 @@ fn parse_infix_postfix(symbols: Vec<&Symbol>) -> Result<Vec<&Symbol>, ParserErro
 #[inline]
 fn operator_priority(t: &Token) -> u32 {
     match t {
+        &Token::Multiply | &Token::Divide => 6,
+        &Token::Plus | &Token::Minus | &Token::Concat => 5,
+        &Token::Escape => 4,
         &Token::NOT => 2,
         &Token::AND | &Token::OR => 1,
-        _ => 3, // >=, >, =, <, <=
+        _ => 3, // >=, >, =, <, <=, is null, is not null, like, in, between
     }
 }
 
@@ -558,6 +1732,35 @@ fn get_id_list(iter: &mut Peekable<Iter<Symbol>>, is_parent: bool) -> Result<Vec
     Ok(v)
 }
 
+/// Get the values in parentheses, `None` for a null
+fn get_value_list(iter: &mut Peekable<Iter<Symbol>>) -> Result<InsertValues, ParserError> {
+    let mut v = vec![];
+    assert_token(iter.next(), Token::ParentLeft)?;
+    loop {
+        v.push(parse_value(iter)?);
+        match iter.peek() {
+            Some(s) if s.token == Token::Comma => {
+                iter.next();
+            }
+            Some(_) | None => break,
+        }
+    }
+    assert_token(iter.next(), Token::ParentRight)?;
+    Ok(v)
+}
+
+/// Parse a value, which is a literal or a number, or `None` for the keyword `null`
+///
+/// A quoted `'null'` is a string rather than a null.
+fn parse_value(iter: &mut Peekable<Iter<Symbol>>) -> Result<Option<String>, ParserError> {
+    match iter.next() {
+        Some(s) if s.group == Group::Identifier && s.name == "null" => Ok(None),
+        Some(s) if s.group == Group::Identifier || s.group == Group::Literal => Ok(Some(s.name.clone())),
+        Some(s) => Err(ParserError::SyntaxError(format!("{} is not a value", s.name))),
+        None => Err(ParserError::SyntaxError(String::from("miss value"))),
+    }
+}
+
 /// Check if the symbol is an identifier
 #[inline]
 fn check_id(sym: &Symbol) -> Result<(), ParserError> {
@@ -567,6 +1770,15 @@ fn check_id(sym: &Symbol) -> Result<(), ParserError> {
     Ok(())
 }
 
+/// Consume the next symbol if it is the expected token, and return if it is consumed
+fn parse_if_token(iter: &mut Peekable<Iter<Symbol>>, token: Token) -> bool {
+    if check_token(iter.peek(), token) {
+        iter.next();
+        return true;
+    }
+    false
+}
+
 /// Check if the next symbol is the expected token.
 #[inline]
 fn check_token(sym: Option<&&Symbol>, token: Token) -> bool {
@@ -598,7 +1810,7 @@ mod tests {
 
     fn fake_sql() -> SQL {
         let mut sql = SQL::new("Jenny").unwrap();
-        sql.create_database("db1").unwrap();
+        sql.create_database("db1", false).unwrap();
         sql
     }
 
@@ -621,6 +1833,10 @@ mod tests {
         let parser = Parser::new(query).unwrap();
         parser.parse(&mut sql).unwrap();
         assert_eq!(sql.database.name, "db2");
+
+        let query = "create database if not exists db2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.database.name, "db2");
     }
 
     #[test]
@@ -637,6 +1853,10 @@ mod tests {
         assert!(table.fields.contains_key("b1"));
         assert!(table.fields.contains_key("c1"));
 
+        let query = "create table t1 (a1 int);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        Parser::new("drop table t1;").unwrap().parse(&mut sql).unwrap();
+
         let query = "create table t1 (a1 int not null default 5 encrypt, b1 char(7) not null, c1 double default 1.2);";
         let parser = Parser::new(query).unwrap();
         parser.parse(&mut sql).unwrap();
@@ -653,6 +1873,96 @@ mod tests {
         assert_eq!(c1.default.clone().unwrap(), "1.2");
     }
 
+    #[test]
+    fn test_parser_create_table_constraints() {
+        let mut sql = fake_sql();
+
+        let query = "create table t1 (a1 int primary key, a2 char(7) unique, a3 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t1").unwrap();
+        assert_eq!(table.primary_key, vec!["a1"]);
+        assert_eq!(table.unique, vec![vec!["a2"]]);
+        assert!(table.fields.get("a1").unwrap().not_null);
+
+        let query = "create table t2 (
+                        b1 int,
+                        b2 int,
+                        b3 int,
+                        constraint pkt2 primary key (b1, b2),
+                        unique (b2, b3),
+                        foreign key (b3) references t1(a1)
+                    );";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t2").unwrap();
+        assert_eq!(table.primary_key, vec!["b1", "b2"]);
+        assert_eq!(table.unique, vec![vec!["b2", "b3"]]);
+        assert_eq!(table.foreign_key, vec!["b3"]);
+        assert_eq!(table.reference_table.clone().unwrap(), "t1");
+        assert_eq!(table.reference_attr.clone().unwrap(), "a1");
+        match table.named_constraints.get("pkt2") {
+            Some(Constraint::PrimaryKey) => {}
+            _ => panic!("the constraint name should be kept"),
+        }
+        assert_eq!(table.named_constraints.len(), 1);
+
+        let query = "create table t3 (c1 int references t1(a2));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t3").unwrap();
+        assert_eq!(table.foreign_key, vec!["c1"]);
+        assert_eq!(table.reference_attr.clone().unwrap(), "a2");
+
+        println!("multiple primary keys");
+        let query = "create table t4 (d1 int primary key, d2 int, primary key (d2));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("duplicated constraint name");
+        let query = "create table t4 (d1 int, d2 int, constraint u1 unique (d1), constraint u1 unique (d2));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("constraint attribute not exists");
+        let query = "create table t4 (d1 int, unique (d2));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("foreign key with multiple columns");
+        let query = "create table t4 (d1 int, d2 int, foreign key (d1, d2) references t1(a1));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_parser_create_table_check() {
+        let mut sql = fake_sql();
+
+        let query = "create table t1 (
+                        a1 int check (a1 > 0),
+                        a2 int check (a2 >= 0 and a2 < 100),
+                        a3 char(7),
+                        check (a1 < a2 or a3 = 'vip'),
+                        constraint ca3 check (a3 <> 'none')
+                    );";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t1").unwrap();
+        match table.fields.get("a1").unwrap().check {
+            Checker::Some(Operator::GT, ref value) => assert_eq!(value, "0"),
+            _ => panic!("the simple check should be kept in the field"),
+        }
+        match table.fields.get("a3").unwrap().check {
+            Checker::Some(Operator::NE, ref value) => assert_eq!(value, "none"),
+            _ => panic!("the simple check should be kept in the field"),
+        }
+        assert_eq!(table.checks.len(), 2);
+        let mut output = vec![];
+        in_order(Box::new(table.checks[1].clone()), &mut output);
+        assert_eq!(output, vec!["a1", "<", "a2", "or", "a3", "=", "'vip'"]);
+
+        println!("check without any attribute");
+        let query = "create table t2 (b1 int, check (1 > 0));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("check without predicate");
+        let query = "create table t2 (b1 int check ());";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
     #[test]
     fn test_insert_into_table1() {
         let query = "insert into t1(a1, a2, a3) values (1, 2, 3), (4, 5, 6);";
@@ -664,8 +1974,16 @@ mod tests {
         assert_eq!(
             rows,
             vec![
-                vec![String::from("1"), String::from("2"), String::from("3")],
-                vec![String::from("4"), String::from("5"), String::from("6")]
+                vec![
+                    Some(String::from("1")),
+                    Some(String::from("2")),
+                    Some(String::from("3"))
+                ],
+                vec![
+                    Some(String::from("4")),
+                    Some(String::from("5")),
+                    Some(String::from("6"))
+                ]
             ]
         );
 
@@ -677,7 +1995,11 @@ mod tests {
         assert_eq!(attrs, vec![String::from("a1"), String::from("a2"), String::from("a3")]);
         assert_eq!(
             rows,
-            vec![vec![String::from("1"), String::from("2"), String::from("3")],]
+            vec![vec![
+                Some(String::from("1")),
+                Some(String::from("2")),
+                Some(String::from("3"))
+            ],]
         );
 
         let query = "insert into t1(a1) values (1);";
@@ -686,7 +2008,14 @@ mod tests {
         let (table_name, attrs, rows) = parser_insert_into_table(&mut iter).unwrap();
         assert_eq!(table_name, "t1");
         assert_eq!(attrs, vec![String::from("a1")]);
-        assert_eq!(rows, vec![vec![String::from("1")]]);
+        assert_eq!(rows, vec![vec![Some(String::from("1"))]]);
+
+        println!("the keyword null is a null, while a quoted one is a string");
+        let query = "insert into t1(a1, a2) values (null, 'null');";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (_, _, rows) = parser_insert_into_table(&mut iter).unwrap();
+        assert_eq!(rows, vec![vec![None, Some(String::from("null"))]]);
     }
 
     #[test]
@@ -730,6 +2059,199 @@ mod tests {
         assert!(parser.parse(&mut sql).is_err());
     }
 
+    #[test]
+    fn test_update_table() {
+        let query = "update t1 set a1 = 5, a2 = 'abc' where a3 > 1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (table_name, assignments, predicate) = parser_update_table(&mut iter).unwrap();
+        assert_eq!(table_name, "t1");
+        let assignments: Vec<(String, Vec<String>)> = assignments
+            .into_iter()
+            .map(|(column, value)| {
+                let mut output = vec![];
+                in_order(value, &mut output);
+                (column, output)
+            })
+            .collect();
+        assert_eq!(
+            assignments,
+            vec![
+                (String::from("a1"), vec![String::from("5")]),
+                (String::from("a2"), vec![String::from("'abc'")])
+            ]
+        );
+        let mut output = vec![];
+        in_order(predicate.unwrap(), &mut output);
+        assert_eq!(output, vec!["a3", ">", "1"]);
+
+        let query = "update t1 set a1 = 5;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (_, _, predicate) = parser_update_table(&mut iter).unwrap();
+        assert!(predicate.is_none());
+
+        let query = "update t1 set a1 = a1 * 2 + 1, a2 = upper(a2) where a3 > 1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (_, assignments, predicate) = parser_update_table(&mut iter).unwrap();
+        let mut output = vec![];
+        in_order(assignments[0].1.clone(), &mut output);
+        assert_eq!(output, vec!["a1", "*", "2", "+", "1"]);
+        assert_eq!(assignments[1].1.root, "upper");
+        assert!(predicate.is_some());
+
+        let query = "update t1 set a1 = ;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parser_update_table(&mut iter).is_err());
+
+        let query = "update t1 a1 = 5;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parser_update_table(&mut iter).is_err());
+    }
+
+    #[test]
+    fn test_parser_alter_table() {
+        let parse = |query: &str| {
+            let parser = Parser::new(query).unwrap();
+            let mut iter = parser.tokens.iter().peekable();
+            parser_alter_table(&mut iter)
+        };
+
+        let (table_name, action) =
+            parse("alter table t1 add column a4 varchar(10) not null default 'x' unique;").unwrap();
+        assert_eq!(table_name, "t1");
+        match action {
+            AlterAction::AddField(field, constraints) => {
+                assert_eq!(field.name, "a4");
+                assert_eq!(field.datatype, DataType::Varchar(10));
+                assert!(field.not_null);
+                assert_eq!(field.default, Some("x".to_string()));
+                assert_eq!(constraints.unique, vec![vec!["a4".to_string()]]);
+            }
+            _ => panic!("should add a field"),
+        }
+        match parse("alter table t1 add a4 int;").unwrap().1 {
+            AlterAction::AddField(field, _) => assert_eq!(field.datatype, DataType::Int),
+            _ => panic!("should add a field"),
+        }
+        match parse("alter table t1 add constraint u1 unique (a1, a2);").unwrap().1 {
+            AlterAction::AddConstraint(constraints) => {
+                assert_eq!(constraints.unique, vec![vec!["a1".to_string(), "a2".to_string()]]);
+                assert!(constraints.named_constraints.contains_key("u1"));
+            }
+            _ => panic!("should add a constraint"),
+        }
+        match parse("alter table t1 add check (a1 > 0);").unwrap().1 {
+            AlterAction::AddConstraint(constraints) => assert_eq!(constraints.checks.len(), 1),
+            _ => panic!("should add a constraint"),
+        }
+        match parse("alter table t1 drop column a2;").unwrap().1 {
+            AlterAction::DropField(name) => assert_eq!(name, "a2"),
+            _ => panic!("should drop a field"),
+        }
+        match parse("alter table t1 drop constraint u1;").unwrap().1 {
+            AlterAction::DropConstraint(name) => assert_eq!(name, "u1"),
+            _ => panic!("should drop a constraint"),
+        }
+        match parse("alter table t1 alter column a1 double not null;").unwrap().1 {
+            AlterAction::AlterField(field) => {
+                assert_eq!(field.datatype, DataType::Double);
+                assert!(field.not_null);
+            }
+            _ => panic!("should alter a field"),
+        }
+        match parse("alter table t1 alter column a1 set default 3;").unwrap().1 {
+            AlterAction::SetDefault(name, default) => {
+                assert_eq!(name, "a1");
+                assert_eq!(default, Some("3".to_string()));
+            }
+            _ => panic!("should set the default"),
+        }
+        match parse("alter table t1 alter column a1 drop default;").unwrap().1 {
+            AlterAction::SetDefault(_, default) => assert_eq!(default, None),
+            _ => panic!("should drop the default"),
+        }
+
+        println!("errors");
+        for query in [
+            "alter table t1;",
+            "alter table t1 add column;",
+            "alter table t1 add column a4;",
+            "alter table t1 drop column;",
+            "alter table t1 alter column a1 set 3;",
+            "alter table t1 drop constraint;",
+            "alter table t1 drop constraint u1 u2;",
+            "alter table t1 drop column a1 a2;",
+        ] {
+            assert!(parse(query).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parser_create_drop_index() {
+        let tokens = |query: &str| Parser::new(query).unwrap().tokens;
+
+        let symbols = tokens("create index i1 on t1 (a2);");
+        let (table_name, index) = parser_create_index(&mut symbols.iter().peekable()).unwrap();
+        assert_eq!(table_name, "t1");
+        assert_eq!(index, IndexMeta::new("i1", "a2", false));
+
+        let symbols = tokens("create unique index i2 on t1 (a1)");
+        let (_, index) = parser_create_index(&mut symbols.iter().peekable()).unwrap();
+        assert_eq!(index, IndexMeta::new("i2", "a1", true));
+
+        for query in [
+            "create index i1 on t1 (a1, a2);",
+            "create index i1 t1 (a1);",
+            "create index on t1 (a1);",
+            "create index i1 on t1 a1;",
+            "create index i1 on t1 (a1) garbage;",
+        ]
+        .iter()
+        {
+            let symbols = tokens(query);
+            assert!(
+                parser_create_index(&mut symbols.iter().peekable()).is_err(),
+                "{}",
+                query
+            );
+        }
+
+        let symbols = tokens("drop index i1 on t1;");
+        let (table_name, index_name) = parser_drop_index(&mut symbols.iter().peekable()).unwrap();
+        assert_eq!((table_name.as_str(), index_name.as_str()), ("t1", "i1"));
+        let symbols = tokens("drop index i1;");
+        assert!(parser_drop_index(&mut symbols.iter().peekable()).is_err());
+        let symbols = tokens("drop index i1 on t1 garbage;");
+        assert!(parser_drop_index(&mut symbols.iter().peekable()).is_err());
+    }
+
+    #[test]
+    fn test_delete_from_table() {
+        let query = "delete from t1 where a1 = 3 or a2 < 5;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (table_name, predicate) = parser_delete_from_table(&mut iter).unwrap();
+        assert_eq!(table_name, "t1");
+        let mut output = vec![];
+        in_order(predicate.unwrap(), &mut output);
+        assert_eq!(output, vec!["a1", "=", "3", "or", "a2", "<", "5"]);
+
+        let query = "delete from t1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let (_, predicate) = parser_delete_from_table(&mut iter).unwrap();
+        assert!(predicate.is_none());
+
+        let query = "delete t1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parser_delete_from_table(&mut iter).is_err());
+    }
+
     #[test]
     fn test_parser_new_error() {
         let query = "create table $1234;";
@@ -769,6 +2291,10 @@ mod tests {
             "a1", "3", "=", "not", "b2", "5", ">=", "c1", "7", "<", "or", "not", "and", "not",
         ];
         assert_parse_infix_postfix(query, answer.to_vec());
+
+        let query = "a1 + 2 * abs(b1 - 1) > 3;";
+        let answer = ["a1", "2", "(", "b1", "1", "-", "abs", "*", "+", "3", ">"];
+        assert_parse_infix_postfix(query, answer.to_vec());
     }
 
     fn assert_parse_postfix_tree(query: &str, answer: Vec<&str>) {
@@ -838,6 +2364,105 @@ mod tests {
         assert_parse_predicate(query, answer);
     }
 
+    #[test]
+    fn test_parse_predicate_expression() {
+        let query = "a1 * 2 + 1 > abs(b1 - 3) and a2 = 'aaa';";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        let tree = parse_predicate(parser.tokens.iter().collect()).unwrap();
+        assert_eq!(tree.root, "and");
+
+        let comparison = tree.left.unwrap();
+        assert_eq!(comparison.root, ">");
+        let mut output = vec![];
+        in_order(comparison.left.unwrap(), &mut output);
+        assert_eq!(output, vec!["a1", "*", "2", "+", "1"]);
+        let function = comparison.right.unwrap();
+        assert_eq!(function.root, "abs");
+        let mut output = vec![];
+        in_order(Box::new(function.args[0].clone()), &mut output);
+        assert_eq!(output, vec!["b1", "-", "3"]);
+
+        // a literal keeps its quotes
+        assert_eq!(tree.right.unwrap().right.unwrap().root, "'aaa'");
+
+        let query = "a1 + > 1;";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        assert!(parse_predicate(parser.tokens.iter().collect()).is_err());
+    }
+
+    #[test]
+    fn test_parse_predicate_is_null() {
+        let query = "not a1 + 1 is null or a2 is not null;";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        let tree = parse_predicate(parser.tokens.iter().collect()).unwrap();
+        assert_eq!(tree.root, "or");
+
+        let negation = tree.left.unwrap();
+        assert_eq!(negation.root, "not");
+        let is_null = negation.right.unwrap();
+        assert_eq!(is_null.root, "is null");
+        assert!(is_null.right.is_none());
+        let mut output = vec![];
+        in_order(is_null.left.unwrap(), &mut output);
+        assert_eq!(output, vec!["a1", "+", "1"]);
+
+        let is_not_null = tree.right.unwrap();
+        assert_eq!(is_not_null.root, "is not null");
+        assert_eq!(is_not_null.left.unwrap().root, "a2");
+
+        let query = "is null;";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        assert!(parse_predicate(parser.tokens.iter().collect()).is_err());
+    }
+
+    #[test]
+    fn test_parse_predicate_like_in_between() {
+        let query = "a1 not between 1 and b1 + 1 and a2 in ('x', 'y') or a2 like 'a!%' escape '!';";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        let tree = parse_predicate(parser.tokens.iter().collect()).unwrap();
+        assert_eq!(tree.root, "or");
+
+        let conjunction = tree.left.unwrap();
+        assert_eq!(conjunction.root, "and");
+        let between = conjunction.left.unwrap();
+        assert_eq!(between.root, "not between");
+        assert_eq!(between.left.unwrap().root, "a1");
+        assert_eq!(between.args[0].root, "1");
+        assert_eq!(between.args[1].root, "+");
+        let in_list = conjunction.right.unwrap();
+        assert_eq!(in_list.root, "in");
+        assert_eq!(in_list.left.unwrap().root, "a2");
+        let values: Vec<&str> = in_list.args.iter().map(|arg| arg.root.as_str()).collect();
+        assert_eq!(values, vec!["'x'", "'y'"]);
+
+        let like = tree.right.unwrap();
+        assert_eq!(like.root, "like");
+        let escape = like.right.unwrap();
+        assert_eq!(escape.root, "escape");
+        assert_eq!(escape.left.unwrap().root, "'a!%'");
+        assert_eq!(escape.right.unwrap().root, "'!'");
+
+        println!("not on a between");
+        let query = "not a1 between 1 and 2;";
+        let mut parser = Parser::new(query).unwrap();
+        parser.tokens.pop(); // `;`
+        let tree = parse_predicate(parser.tokens.iter().collect()).unwrap();
+        assert_eq!(tree.root, "not");
+        assert_eq!(tree.right.unwrap().args.len(), 2);
+
+        println!("errors");
+        for query in ["a1 between 1;", "a1 in ();", "a1 in 1;", "a1 between 1 or 2;"] {
+            let mut parser = Parser::new(query).unwrap();
+            parser.tokens.pop(); // `;`
+            assert!(parse_predicate(parser.tokens.iter().collect()).is_err());
+        }
+    }
+
     #[test]
     fn test_parse_select_field_table() {
         let query = "select t1.a1, t1.a2, t1.a3 from t1, t2;";
@@ -873,9 +2498,92 @@ mod tests {
 
         let querydata = parse_select(&mut iter).unwrap();
         assert_eq!(querydata.top, TopType::Percent(50.0));
+
+        let query = "select a1 from t1 order by a1 limit 10 offset 20;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(querydata.top, TopType::Number(10));
+        assert_eq!(querydata.offset, 20);
+
+        let query = "select top 5 a1 from t1 limit 10;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parse_select(&mut iter).is_err());
+
+        let query = "select top 150 percent a1 from t1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parse_select(&mut iter).is_err());
         assert_eq!(querydata.is_distinct, false);
     }
 
+    #[test]
+    fn test_parse_select_order_by() {
+        let query = "select a1, a2 from t1 where a1 > 1 order by a2 desc, a1, a3 asc;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert!(querydata.predicate.is_some());
+        assert_eq!(
+            querydata.sort_fields,
+            vec![String::from("a2"), String::from("a1"), String::from("a3")]
+        );
+        assert_eq!(
+            querydata.sort_dir,
+            vec![SortDirection::Desc, SortDirection::Asc, SortDirection::Asc]
+        );
+
+        let query = "select a1 from t1 order by;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parse_select(&mut iter).is_err());
+    }
+
+    #[test]
+    fn test_parse_select_group_by() {
+        let query = "select a2, count(*), sum(a1), count(distinct a3) from t1 group by a2 having count(*) > 1 and avg(a1) < 3 order by sum(a1) desc;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(
+            querydata.fields,
+            vec![
+                String::from("a2"),
+                String::from("count(*)"),
+                String::from("sum(a1)"),
+                String::from("count(distinct a3)")
+            ]
+        );
+        assert_eq!(querydata.group_fields, vec![String::from("a2")]);
+        // the aggregation only in having is also computed, and each one is only computed once
+        assert_eq!(
+            querydata.aggregation_fn,
+            vec![
+                Aggregation::new(AggregateFunction::Count, "*", false),
+                Aggregation::new(AggregateFunction::Sum, "a1", false),
+                Aggregation::new(AggregateFunction::Count, "a3", true),
+                Aggregation::new(AggregateFunction::Avg, "a1", false),
+            ]
+        );
+        let having = querydata.having.unwrap();
+        assert_eq!(having.root, "and".to_string());
+        assert_eq!(having.left.unwrap().left.unwrap().root, "count(*)".to_string());
+        assert_eq!(querydata.sort_fields, vec![String::from("sum(a1)")]);
+
+        let query = "select sum(*) from t1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parse_select(&mut iter).is_err());
+
+        let query = "select count(a1 from t1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        assert!(parse_select(&mut iter).is_err());
+    }
+
     #[test]
     fn test_parse_select_join() {
         let query = "select t1.a1, t1.a2, t1.a3 from t1 inner join t2 on t1.a1 = t2.a1 left join t3 on t1.a1 = t3.a1;";
@@ -894,4 +2602,182 @@ mod tests {
         assert_eq!(querydata.joins[1].table, "t3".to_string());
     }
 
+    #[test]
+    fn test_parse_select_alias() {
+        let query = "select t.*, a1 as x, count(*) n, (select max(b1) from t2) as m from t1 as t inner join t2 u on t.a1 = u.b1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(querydata.fields[0], "t.*".to_string());
+        assert_eq!(querydata.fields[1], "a1".to_string());
+        assert_eq!(querydata.fields[2], "count(*)".to_string());
+        assert_eq!(
+            querydata.aliases,
+            vec![
+                None,
+                Some("x".to_string()),
+                Some("n".to_string()),
+                Some("m".to_string())
+            ]
+        );
+        assert_eq!(querydata.tables, vec!["t".to_string()]);
+        assert_eq!(querydata.joins[0].table, "u".to_string());
+        assert_eq!(
+            querydata.table_aliases,
+            vec![("t".to_string(), "t1".to_string()), ("u".to_string(), "t2".to_string())]
+        );
+
+        println!("errors");
+        for query in [
+            "select * as x from t1;",
+            "select a1 as from t1;",
+            "select a1 as t1.a1 from t1;",
+            "select a1 from t1 as;",
+        ] {
+            let parser = Parser::new(query).unwrap();
+            let mut iter = parser.tokens.iter().peekable();
+            assert!(parse_select(&mut iter).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_select_expression() {
+        let query = "select a1 * 2 + 1 as x, upper(a2), a2 || 'z' y, case when a1 > 1 then 'b' else 'a' end, count(*) + 1 from t1 group by a1, a2;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(
+            querydata.fields,
+            vec![
+                "a1 * 2 + 1".to_string(),
+                "upper(a2)".to_string(),
+                "a2 || 'z'".to_string(),
+                "case when a1 > 1 then 'b' else 'a' end".to_string(),
+                "count(*) + 1".to_string(),
+            ]
+        );
+        assert_eq!(
+            querydata.aliases,
+            vec![Some("x".to_string()), None, Some("y".to_string()), None, None]
+        );
+        assert_eq!(querydata.computed_fields.len(), 5);
+        assert_eq!(querydata.aggregation_fn.len(), 1);
+
+        let mut vec = vec![];
+        in_order(Box::new(querydata.computed_fields[0].1.clone()), &mut vec);
+        assert_eq!(vec, vec!["a1", "*", "2", "+", "1"]);
+        let case = &querydata.computed_fields[3].1;
+        assert_eq!(case.root, "case".to_string());
+        assert_eq!(case.args.len(), 3);
+        assert_eq!(case.args[0].root, ">".to_string());
+
+        println!("simple case, nested in a function");
+        let query = "select abs(case a1 when 1 then -1 when 2 then a3 end), 1 from t1 where a1 * 2 > 1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(querydata.fields[1], "1".to_string());
+        let case = &querydata.computed_fields[0].1.args[0];
+        assert_eq!(case.root, "case".to_string());
+        assert_eq!(case.args.len(), 4);
+        assert_eq!(case.args[2].root, "=".to_string());
+        assert_eq!(case.args[2].left.as_ref().unwrap().root, "a1".to_string());
+
+        println!("errors");
+        for query in [
+            "select a1 + from t1;",
+            "select case end from t1;",
+            "select case when a1 > 1 'b' end from t1;",
+            "select case when a1 > 1 then 'b' from t1;",
+            "select upper() from t1;",
+            "select a1 as x + 1 from t1;",
+        ] {
+            let parser = Parser::new(query).unwrap();
+            let mut iter = parser.tokens.iter().peekable();
+            assert!(parse_select(&mut iter).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_select_set_operations() {
+        let query = "select a1 from t1 where a1 > 1 union all select b1 from t2 except select c1 from t3 order by a1 desc limit 2;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(querydata.fields, vec!["a1".to_string()]);
+        assert!(querydata.predicate.is_some());
+        assert_eq!(querydata.set_operations.len(), 2);
+        assert_eq!(querydata.set_operations[0].0, SetOperator::UnionAll);
+        assert_eq!(querydata.set_operations[0].1.tables, vec!["t2".to_string()]);
+        assert_eq!(querydata.set_operations[1].0, SetOperator::Except);
+        assert_eq!(querydata.set_operations[1].1.fields, vec!["c1".to_string()]);
+        // sort and limit the combined result
+        assert_eq!(querydata.sort_fields, vec!["a1".to_string()]);
+        assert_eq!(querydata.sort_dir, vec![SortDirection::Desc]);
+        assert_eq!(querydata.top, TopType::Number(2));
+        assert!(querydata.set_operations[1].1.sort_fields.is_empty());
+
+        println!("errors");
+        for query in [
+            "select a1 from t1 order by a1 union select b1 from t2;",
+            "select a1 from t1 limit 1 intersect select b1 from t2;",
+            "select top 1 a1 from t1 union select b1 from t2;",
+            "select a1 from t1 union select b1 from t2 order by count(*);",
+            "select a1 from t1 union;",
+        ] {
+            let parser = Parser::new(query).unwrap();
+            let mut iter = parser.tokens.iter().peekable();
+            assert!(parse_select(&mut iter).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_select_subquery() {
+        let query = "select a1, (select max(b1) from t2 where b2 = 'x') from t1
+                     where a1 in (select b1 from t2) and exists (select b1 from t2 where b1 = a1);";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+
+        let querydata = parse_select(&mut iter).unwrap();
+        let scalar = "(select max(b1) from t2 where b2 = 'x')".to_string();
+        assert_eq!(querydata.fields, vec!["a1".to_string(), scalar.clone()]);
+        assert_eq!(querydata.subquery_fields[0].0, scalar);
+        assert_eq!(querydata.subquery_fields[0].1.tables, vec!["t2".to_string()]);
+
+        let tree = querydata.predicate.unwrap();
+        assert_eq!(tree.root, "and");
+        let in_subquery = tree.left.unwrap();
+        assert_eq!(in_subquery.root, "in");
+        assert_eq!(in_subquery.args[0].root, "(select b1 from t2)");
+        assert!(in_subquery.args[0].subquery.is_some());
+        let exists = tree.right.unwrap();
+        assert_eq!(exists.root, "exists");
+        let correlated = exists.args[0].subquery.as_ref().unwrap();
+        assert_eq!(correlated.predicate.as_ref().unwrap().root, "=");
+
+        println!("derived table");
+        let query = "select x.a1 from (select a1 from t1 where a1 > 1) as x inner join t2 on x.a1 = b1;";
+        let parser = Parser::new(query).unwrap();
+        let mut iter = parser.tokens.iter().peekable();
+        let querydata = parse_select(&mut iter).unwrap();
+        assert_eq!(querydata.tables, vec!["x".to_string()]);
+        assert_eq!(querydata.derived_tables[0].0, "x".to_string());
+        assert!(querydata.derived_tables[0].1.predicate.is_some());
+        assert_eq!(querydata.joins[0].table, "t2".to_string());
+
+        println!("errors");
+        for query in [
+            "select a1 from (select a1 from t1);",
+            "select a1 from t1 where a1 in (select a1 from t1;",
+            "select a1 from t1 where exists a1;",
+        ] {
+            let parser = Parser::new(query).unwrap();
+            let mut iter = parser.tokens.iter().peekable();
+            assert!(parse_select(&mut iter).is_err());
+        }
+    }
 }
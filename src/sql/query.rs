@@ -1,38 +1,117 @@
+use crate::component::field::Field;
+use crate::component::table::Table;
 use std::collections::HashSet;
 
 /// Data for `select`
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct QueryData {
     pub fields: Vec<String>,
+    pub aliases: Vec<Option<String>>, // the alias of each field, which is the name in the result
+    pub subquery_fields: Vec<(String, QueryData)>, // scalar subqueries in the select list, by name
+    pub computed_fields: Vec<(String, Node)>, // expressions in the select list, by their text
     pub tables: Vec<String>,
+    pub table_aliases: Vec<(String, String)>, // tables referred by aliases, as (alias, table)
+    pub derived_tables: Vec<(String, QueryData)>, // subqueries in `FROM` or `JOIN`, by alias
     pub joins: Vec<Join>,
     pub predicate: NodePtr,
     pub group_fields: Vec<String>,
-    pub aggregation_fn: Vec<String>,
+    pub aggregation_fn: Vec<Aggregation>,
+    pub having: NodePtr,
     pub sort_fields: Vec<String>,
-    pub sort_dir: SortDirection,
+    pub sort_dir: Vec<SortDirection>, // direction of each sort field
     pub is_distinct: bool,
     pub top: TopType,
+    pub offset: u32, // number of rows skipped before top
+
+    // queries combined with this one in order, whose result is sorted and limited by this one
+    pub set_operations: Vec<(SetOperator, QueryData)>,
 }
 
 impl QueryData {
     pub fn new() -> QueryData {
         QueryData {
             fields: vec![],
+            aliases: vec![],
+            subquery_fields: vec![],
+            computed_fields: vec![],
             tables: vec![],
+            table_aliases: vec![],
+            derived_tables: vec![],
             joins: vec![],
             predicate: None,
             group_fields: vec![],
             aggregation_fn: vec![],
+            having: None,
             sort_fields: vec![],
-            sort_dir: SortDirection::None,
+            sort_dir: vec![],
             is_distinct: false,
             top: TopType::None,
+            offset: 0,
+            set_operations: vec![],
+        }
+    }
+}
+
+/// An aggregate function call, such as `count(*)`, `sum(a1)` and `count(distinct a1)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Aggregation {
+    pub function: AggregateFunction,
+    pub field: String, // `*` for `count(*)`
+    pub is_distinct: bool,
+}
+
+impl Aggregation {
+    pub fn new(function: AggregateFunction, field: &str, is_distinct: bool) -> Aggregation {
+        Aggregation {
+            function,
+            field: field.to_string(),
+            is_distinct,
+        }
+    }
+
+    /// The name of the aggregation, which is the name of its column in the result
+    pub fn name(&self) -> String {
+        if self.is_distinct {
+            return format!("{}(distinct {})", self.function.as_str(), self.field);
+        }
+        format!("{}({})", self.function.as_str(), self.field)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AggregateFunction {
+    Avg,
+    Count,
+    Max,
+    Min,
+    Sum,
+}
+
+impl AggregateFunction {
+    pub fn get(name: &str) -> Option<AggregateFunction> {
+        let f = match name {
+            "avg" => AggregateFunction::Avg,
+            "count" => AggregateFunction::Count,
+            "max" => AggregateFunction::Max,
+            "min" => AggregateFunction::Min,
+            "sum" => AggregateFunction::Sum,
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    pub fn as_str(&self) -> &str {
+        match *self {
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Count => "count",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Sum => "sum",
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 #[allow(dead_code)]
 pub enum SortDirection {
     Asc,
@@ -40,14 +119,36 @@ pub enum SortDirection {
     None,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TopType {
     Percent(f32),
     Number(u32),
     None,
 }
 
-#[derive(Debug)]
+/// A set operation combining the result with the one of another query
+#[derive(Debug, PartialEq, Clone)]
+pub enum SetOperator {
+    Union,
+    UnionAll,
+    Intersect,
+    Except,
+}
+
+impl SetOperator {
+    pub fn get(name: &str) -> Option<SetOperator> {
+        let operator = match name {
+            "union" => SetOperator::Union,
+            "union all" => SetOperator::UnionAll,
+            "intersect" => SetOperator::Intersect,
+            "except" => SetOperator::Except,
+            _ => return None,
+        };
+        Some(operator)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Join {
     pub join_type: JoinType,
     pub table: String,
@@ -64,7 +165,7 @@ impl Join {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum JoinType {
     InnerJoin,
     FullOuterJoin,
@@ -85,14 +186,38 @@ impl JoinType {
     }
 }
 
+/// An action of `alter table`
+///
+/// The constraints added are kept in a table without fields, such as its primary key or checks.
+#[derive(Debug, Clone)]
+pub enum AlterAction {
+    AddField(Field, Table), // the new field, and the constraints on it
+    AddConstraint(Table),
+    DropConstraint(String), // the name of the constraint
+    DropField(String),
+    AlterField(Field),                  // the field with its new datatype, not null and default
+    SetDefault(String, Option<String>), // the field and its new default, `None` to drop the default
+}
+
 pub type NodePtr = Option<Box<Node>>;
 
-#[derive(Default, Debug, Clone)]
+/// a column of `update ... set` with the expression of its new value
+pub type Assignment = (String, Box<Node>);
+
+/// the values of a row of `insert`, `None` for a null
+pub type InsertValues = Vec<Option<String>>;
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub root: String,
+    #[serde(skip)]
     pub set: HashSet<usize>,
     pub left: NodePtr,
     pub right: NodePtr,
+    #[serde(default)]
+    pub args: Vec<Node>, // arguments of a function call
+    #[serde(skip)]
+    pub subquery: Option<Box<QueryData>>,
 }
 
 impl Node {
@@ -112,4 +237,29 @@ impl Node {
         self.right = Some(Box::new(leaf));
         self
     }
+
+    pub fn args(mut self, args: Vec<Node>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// if the trees have the same roots in the same places, regardless of the rows and the subqueries
+    pub fn is_same_tree(&self, other: &Node) -> bool {
+        let is_same_child = |a: &NodePtr, b: &NodePtr| match (a, b) {
+            (Some(a), Some(b)) => a.is_same_tree(b),
+            (None, None) => true,
+            _ => false,
+        };
+        self.root == other.root
+            && is_same_child(&self.left, &other.left)
+            && is_same_child(&self.right, &other.right)
+            && self.args.len() == other.args.len()
+            && self.args.iter().zip(other.args.iter()).all(|(a, b)| a.is_same_tree(b))
+    }
+
+    /// if there is any subquery in the tree
+    pub fn has_subquery(&self) -> bool {
+        let children = self.left.iter().chain(self.right.iter()).map(|child| child.as_ref());
+        self.subquery.is_some() || children.chain(self.args.iter()).any(Node::has_subquery)
+    }
 }
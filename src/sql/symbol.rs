@@ -13,8 +13,9 @@ pub enum Group {
     DataType,
     Function,
     Keyword,
-    Operator,   // >, >=, =, !=, <>, <, <=
+    Operator,   // >, >=, =, !=, <>, <, <=, like, in, between, exists
     Identifier, // t1, a, b
+    Literal,    // 'abc', "abc"
     Delimiter,  // `,`, (, )
 }
 
@@ -47,6 +48,7 @@ pub enum Token {
     Default,
     Delete,
     Desc,
+    Describe,
     Distinct,
     DropColumn,
     DropConstraint,
@@ -55,6 +57,10 @@ pub enum Token {
     DropIndex,
     DropTable,
     DropView,
+    Else,
+    End,
+    Escape,
+    Except,
     Exec,
     Exists,
     ForeignKey,
@@ -62,26 +68,38 @@ pub enum Token {
     FullOuterJoin,
     GroupBy,
     Having,
+    IfExists,
+    IfNotExists,
     In,
     Index,
     InnerJoin,
     InsertInto,
+    Intersect,
     IsNull,
     IsNotNull,
     LeftJoin,
     Like,
     Limit,
+    NotBetween,
+    NotIn,
+    NotLike,
     NotNull,
+    Offset,
     On,
     OrderBy,
     Percent,
     PrimaryKey,
     Procedure,
+    References,
     RightJoin,
     Rownum,
     Select,
     Set,
+    ShowColumns,
+    ShowDatabases,
+    ShowTables,
     Table,
+    Then,
     Top,
     TruncateTable,
     Union,
@@ -90,6 +108,7 @@ pub enum Token {
     Update,
     Values,
     View,
+    When,
     Where,
 
     /* SQL Function */
@@ -98,6 +117,15 @@ pub enum Token {
     Max,
     Min,
     Sum,
+    Abs,
+    Coalesce,
+    Length,
+    Lower,
+    Nullif,
+    Round,
+    Substr,
+    Trim,
+    Upper,
 
     /* SQL Data Type */
     Char,
@@ -108,12 +136,17 @@ pub enum Token {
     Url,
 
     /* Operator */
-    LT, // <
-    LE, // <=
-    EQ, // =
-    NE, // !=, <>
-    GT, // >
-    GE, // >=
+    LT,       // <
+    LE,       // <=
+    EQ,       // =
+    NE,       // !=, <>
+    GT,       // >
+    GE,       // >=
+    Plus,     // +
+    Minus,    // -
+    Multiply, // *
+    Divide,   // /
+    Concat,   // ||
     AND,
     NOT,
     OR,
@@ -156,7 +189,6 @@ lazy_static! {
         m.insert("any", sym("any", Token::Any, Group::Keyword));
         m.insert("as", sym("as", Token::As, Group::Keyword));
         m.insert("asc", sym("asc", Token::Asc, Group::Keyword));
-        m.insert("between", sym("between", Token::Between, Group::Keyword));
         m.insert("case", sym("case", Token::Case, Group::Keyword));
         m.insert("check", sym("check", Token::Check, Group::Keyword));
         m.insert("column", sym("column", Token::Column, Group::Keyword));
@@ -173,6 +205,7 @@ lazy_static! {
         m.insert("default", sym("default", Token::Default, Group::Keyword));
         m.insert("delete", sym("delete", Token::Delete, Group::Keyword));
         m.insert("desc", sym("desc", Token::Desc, Group::Keyword));
+        m.insert("describe", sym("describe", Token::Describe, Group::Keyword));
         m.insert("distinct", sym("distinct", Token::Distinct, Group::Keyword));
         m.insert("drop column", sym("drop column", Token::DropColumn, Group::Keyword));
         m.insert("drop constraint", sym("drop constraint", Token::DropConstraint, Group::Keyword));
@@ -181,33 +214,40 @@ lazy_static! {
         m.insert("drop index", sym("drop index", Token::DropIndex, Group::Keyword));
         m.insert("drop table", sym("drop table", Token::DropTable, Group::Keyword));
         m.insert("drop view", sym("drop view", Token::DropView, Group::Keyword));
+        m.insert("else", sym("else", Token::Else, Group::Keyword));
+        m.insert("end", sym("end", Token::End, Group::Keyword));
+        m.insert("except", sym("except", Token::Except, Group::Keyword));
         m.insert("exec", sym("exec", Token::Exec, Group::Keyword));
-        m.insert("exists", sym("exists", Token::Exists, Group::Keyword));
         m.insert("foreign key", sym("foreign key", Token::ForeignKey, Group::Keyword));
         m.insert("from", sym("from", Token::From, Group::Keyword));
         m.insert("full outer join", sym("full outer join", Token::FullOuterJoin, Group::Keyword));
         m.insert("group by", sym("group by", Token::GroupBy, Group::Keyword));
         m.insert("having", sym("having", Token::Having, Group::Keyword));
-        m.insert("in", sym("in", Token::In, Group::Keyword));
+        m.insert("if exists", sym("if exists", Token::IfExists, Group::Keyword));
+        m.insert("if not exists", sym("if not exists", Token::IfNotExists, Group::Keyword));
         m.insert("index", sym("index", Token::Index, Group::Keyword));
         m.insert("inner join", sym("inner join", Token::InnerJoin, Group::Keyword));
         m.insert("insert into", sym("insert into", Token::InsertInto, Group::Keyword));
-        m.insert("is null", sym("is null", Token::IsNull, Group::Keyword));
-        m.insert("is not null", sym("is not null", Token::IsNotNull, Group::Keyword));
+        m.insert("intersect", sym("intersect", Token::Intersect, Group::Keyword));
         m.insert("left join", sym("left join", Token::LeftJoin, Group::Keyword));
-        m.insert("like", sym("like", Token::Like, Group::Keyword));
         m.insert("limit", sym("limit", Token::Limit, Group::Keyword));
         m.insert("not null", sym("not null", Token::NotNull, Group::Keyword));
+        m.insert("offset", sym("offset", Token::Offset, Group::Keyword));
         m.insert("on", sym("on", Token::On, Group::Keyword));
         m.insert("order by", sym("order by", Token::OrderBy, Group::Keyword));
         m.insert("percent", sym("percent", Token::Percent, Group::Keyword));
         m.insert("primary key", sym("primary key", Token::PrimaryKey, Group::Keyword));
         m.insert("procedure", sym("procedure", Token::Procedure, Group::Keyword));
+        m.insert("references", sym("references", Token::References, Group::Keyword));
         m.insert("right join", sym("right join", Token::RightJoin, Group::Keyword));
         m.insert("rownum", sym("rownum", Token::Rownum, Group::Keyword));
         m.insert("select", sym("select", Token::Select, Group::Keyword));
         m.insert("set", sym("set", Token::Set, Group::Keyword));
+        m.insert("show columns", sym("show columns", Token::ShowColumns, Group::Keyword));
+        m.insert("show databases", sym("show databases", Token::ShowDatabases, Group::Keyword));
+        m.insert("show tables", sym("show tables", Token::ShowTables, Group::Keyword));
         m.insert("table", sym("table", Token::Table, Group::Keyword));
+        m.insert("then", sym("then", Token::Then, Group::Keyword));
         m.insert("top", sym("top", Token::Top, Group::Keyword));
         m.insert("truncate table", sym("truncate table", Token::TruncateTable, Group::Keyword));
         m.insert("union", sym("union", Token::Union, Group::Keyword));
@@ -216,6 +256,7 @@ lazy_static! {
         m.insert("update", sym("update", Token::Update, Group::Keyword));
         m.insert("values", sym("values", Token::Values, Group::Keyword));
         m.insert("view", sym("view", Token::View, Group::Keyword));
+        m.insert("when", sym("when", Token::When, Group::Keyword));
         m.insert("where", sym("where", Token::Where, Group::Keyword));
 
         /* SQL Function */
@@ -224,6 +265,15 @@ lazy_static! {
         m.insert("max", sym("max", Token::Max, Group::Function));
         m.insert("min", sym("min", Token::Min, Group::Function));
         m.insert("sum", sym("sum", Token::Sum, Group::Function));
+        m.insert("abs", sym("abs", Token::Abs, Group::Function));
+        m.insert("coalesce", sym("coalesce", Token::Coalesce, Group::Function));
+        m.insert("length", sym("length", Token::Length, Group::Function));
+        m.insert("lower", sym("lower", Token::Lower, Group::Function));
+        m.insert("nullif", sym("nullif", Token::Nullif, Group::Function));
+        m.insert("round", sym("round", Token::Round, Group::Function));
+        m.insert("substr", sym("substr", Token::Substr, Group::Function));
+        m.insert("trim", sym("trim", Token::Trim, Group::Function));
+        m.insert("upper", sym("upper", Token::Upper, Group::Function));
 
         /* SQL Data Type */
         m.insert("char", sym("char", Token::Char, Group::DataType));
@@ -241,9 +291,24 @@ lazy_static! {
         m.insert("<>", sym("<>", Token::NE, Group::Operator));
         m.insert("<", sym("<", Token::LT, Group::Operator));
         m.insert("<=", sym("<=", Token::LE, Group::Operator));
+        m.insert("+", sym("+", Token::Plus, Group::Operator));
+        m.insert("-", sym("-", Token::Minus, Group::Operator));
+        m.insert("*", sym("*", Token::Multiply, Group::Operator));
+        m.insert("/", sym("/", Token::Divide, Group::Operator));
+        m.insert("||", sym("||", Token::Concat, Group::Operator));
         m.insert("and", sym("and", Token::AND, Group::Operator));
         m.insert("not", sym("not", Token::NOT, Group::Operator));
         m.insert("or", sym("or", Token::OR, Group::Operator));
+        m.insert("is null", sym("is null", Token::IsNull, Group::Operator));
+        m.insert("is not null", sym("is not null", Token::IsNotNull, Group::Operator));
+        m.insert("like", sym("like", Token::Like, Group::Operator));
+        m.insert("not like", sym("not like", Token::NotLike, Group::Operator));
+        m.insert("escape", sym("escape", Token::Escape, Group::Operator));
+        m.insert("in", sym("in", Token::In, Group::Operator));
+        m.insert("not in", sym("not in", Token::NotIn, Group::Operator));
+        m.insert("between", sym("between", Token::Between, Group::Operator));
+        m.insert("not between", sym("not between", Token::NotBetween, Group::Operator));
+        m.insert("exists", sym("exists", Token::Exists, Group::Operator));
 
         /* StellarSQL */
         m.insert("encrypt", sym("encrypt", Token::Encrypt, Group::Keyword));
@@ -275,8 +340,9 @@ pub fn check_multi_keywords_front(s: &str) -> Option<Vec<u32>> {
         "create" => Some(vec![2, 3, 4]),
         "drop" => Some(vec![2]),
         "foreign" => Some(vec![2]),
-        "full" => Some(vec![2]),
+        "full" => Some(vec![3]),
         "group" => Some(vec![2]),
+        "if" => Some(vec![2, 3]),
         "inner" => Some(vec![2]),
         "insert" => Some(vec![2]),
         "is" => Some(vec![2, 3]),
@@ -287,6 +353,7 @@ pub fn check_multi_keywords_front(s: &str) -> Option<Vec<u32>> {
         "primary" => Some(vec![2]),
         "right" => Some(vec![2]),
         "select" => Some(vec![2]),
+        "show" => Some(vec![2]),
         "truncate" => Some(vec![2]),
         "union" => Some(vec![2]),
         _ => return None,
@@ -1,10 +1,27 @@
 use crate::component::database::Database;
 use crate::component::database::DatabaseError;
+use crate::component::datatype::DataType;
+use crate::component::expression;
+use crate::component::field::Field;
+use crate::component::table::RowUpdate;
+use crate::component::table::SelectData;
 use crate::component::table::Table;
+use crate::component::table::TableError;
+use crate::sql::query::AlterAction;
+use crate::sql::query::Assignment;
+use crate::sql::query::InsertValues;
+use crate::sql::query::Node;
 use crate::sql::query::NodePtr;
 use crate::sql::query::QueryData;
+use crate::sql::query::SetOperator;
+use crate::sql::query::TopType;
+use crate::storage::diskinterface::DiskError;
+use crate::storage::diskinterface::DiskInterface;
+use crate::storage::diskinterface::IndexMeta;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
+use std::mem;
 
 #[derive(Debug)]
 pub struct SQL {
@@ -31,6 +48,7 @@ impl User {
 #[derive(Debug)]
 pub enum SQLError {
     CauserByDatabase(DatabaseError),
+    CausedByTable(TableError),
     SemanticError(String),
 }
 
@@ -38,6 +56,7 @@ impl fmt::Display for SQLError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             SQLError::CauserByDatabase(ref e) => write!(f, "{}", e),
+            SQLError::CausedByTable(ref e) => write!(f, "{}", e),
             SQLError::SemanticError(ref s) => write!(f, "semantic error: {}", s),
         }
     }
@@ -54,44 +73,402 @@ impl SQL {
     }
 
     // Create a new database
-    pub fn create_database(&mut self, db_name: &str) -> Result<(), SQLError> {
+    //
+    // With `if_not_exists`, an existing database is loaded instead.
+    pub fn create_database(&mut self, db_name: &str, if_not_exists: bool) -> Result<(), SQLError> {
+        if if_not_exists && self.is_database_existed(db_name) {
+            if self.database.name != db_name {
+                self.load_database(db_name)?;
+            }
+            return Ok(());
+        }
         self.database = Database::new(db_name);
         Ok(())
     }
 
     /// Load a database
     pub fn load_database(&mut self, db_name: &str) -> Result<(), SQLError> {
-        self.database = Database::load_db(&self.user.name, db_name).map_err(|e| SQLError::CauserByDatabase(e))?;
+        self.database = Database::load_db(&self.user.name, db_name, None).map_err(SQLError::CauserByDatabase)?;
         Ok(())
     }
 
-    // TODO
     /// Drop the database
-    pub fn drop_database(&mut self, db_name: &str) -> Result<(), SQLError> {
+    ///
+    /// The current database is only marked deleted, and the pool will remove it from storage later.
+    /// Any other database is removed from storage at once.
+    pub fn drop_database(&mut self, db_name: &str, if_exists: bool) -> Result<(), SQLError> {
+        if !self.is_database_existed(db_name) {
+            if if_exists {
+                return Ok(());
+            }
+            return Err(SQLError::SemanticError("database not exists".to_string()));
+        }
+
+        if self.database.name == db_name {
+            self.database.is_delete = true;
+            return Ok(());
+        }
+        DiskInterface::remove_db(&self.user.name, db_name, None)
+            .map_err(|e| SQLError::CauserByDatabase(DatabaseError::CausedByFile(e)))
+    }
+
+    /// Check if the database exists, either as the current database or in storage
+    fn is_database_existed(&self, db_name: &str) -> bool {
+        if self.database.name == db_name {
+            return !self.database.is_delete;
+        }
+        DiskInterface::get_dbs(&self.user.name, None).is_ok_and(|dbs| dbs.iter().any(|db| db == db_name))
+    }
+
+    /// Check if the current database could be used, which should not be dropped
+    fn check_database(&self) -> Result<(), SQLError> {
+        if self.database.is_delete {
+            return Err(SQLError::SemanticError("database not exists".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Check if the table exists, which is not dropped
+    fn is_table_existed(&self, table_name: &str) -> bool {
+        self.database.tables.get(table_name).is_some_and(|t| !t.is_delete)
+    }
+
+    /// Load the database and create a new table, whose constraints should be valid
+    ///
+    /// A table of the same name should not exist, unless `if_not_exists` is given. A table dropped in
+    /// this session is still in storage, so the pool will remove it before creating the new one.
+    pub fn create_table(&mut self, table: &Table, if_not_exists: bool) -> Result<(), SQLError> {
+        self.check_database()?;
+        if self.is_table_existed(&table.name) {
+            if if_not_exists {
+                return Ok(());
+            }
+            return Err(SQLError::SemanticError("table exists".to_string()));
+        }
+        let mut table = table.clone();
+        table.is_stored = self.database.tables.get(&table.name).is_some_and(|t| t.is_stored);
+
+        self.check_constraints(&table)?;
+
+        // primary key could not be null
+        for attr in table.primary_key.clone() {
+            table.fields.get_mut(&attr).unwrap().not_null = true;
+        }
+
+        // a new table has nothing in storage to load
+        table.is_data_loaded = true;
+        self.database.insert_new_table(table);
+        Ok(())
+    }
+
+    /// Check if the constraints of the table are valid
+    ///
+    /// The attributes of the constraints should exist. A foreign key should refer to the primary key
+    /// or a unique attribute of the reference table.
+    fn check_constraints(&self, table: &Table) -> Result<(), SQLError> {
+        let mut constraint_attrs = table.primary_key.clone();
+        constraint_attrs.extend(table.unique.concat());
+        constraint_attrs.extend(table.foreign_key.clone());
+        for attr in constraint_attrs.iter() {
+            if !table.fields.contains_key(attr) {
+                return Err(SQLError::SemanticError(format!(
+                    "constraint attribute {} not exists",
+                    attr
+                )));
+            }
+        }
+
+        for check in table.checks.iter() {
+            if table.get_check_attrs(check).is_empty() {
+                return Err(SQLError::SemanticError(
+                    "check should use at least an attribute".to_string(),
+                ));
+            }
+        }
+
+        if let (Some(reference_table), Some(reference_attr)) = (&table.reference_table, &table.reference_attr) {
+            let reference = if reference_table == &table.name {
+                table
+            } else {
+                self.database
+                    .tables
+                    .get(reference_table)
+                    .filter(|t| !t.is_delete)
+                    .ok_or(SQLError::SemanticError("reference table not exists".to_string()))?
+            };
+            let key = vec![reference_attr.to_string()];
+            if reference.primary_key != key && !reference.unique.contains(&key) {
+                return Err(SQLError::SemanticError(format!(
+                    "reference attribute {} is not a primary key or unique",
+                    reference_attr
+                )));
+            }
+        }
         Ok(())
     }
 
-    // TODO: check db delete bit
-    /// Load the database and create a new table
-    pub fn create_table(&mut self, table: &Table) -> Result<(), SQLError> {
-        self.database.insert_new_table(table.clone());
+    /// Alter the schema of the table
+    ///
+    /// The table is altered on a copy, and the rows are checked with the new schema, so nothing is
+    /// changed if it fails. The rows in storage are migrated to the new schema when the table is
+    /// written back.
+    pub fn alter_table(&mut self, table_name: &str, action: AlterAction) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+        let mut table = self
+            .database
+            .tables
+            .get(table_name)
+            .ok_or(SQLError::SemanticError("table not exists".to_string()))?
+            .clone();
+
+        let mut constraints = Table::new(table_name);
+        match action {
+            AlterAction::AddField(field, field_constraints) => {
+                table.add_field(field).map_err(SQLError::CausedByTable)?;
+                constraints = field_constraints;
+            }
+            AlterAction::AddConstraint(table_constraints) => constraints = table_constraints,
+            AlterAction::DropConstraint(name) => {
+                table.drop_constraint(&name).map_err(SQLError::CausedByTable)?;
+                // the attribute referred by a foreign key should be still a primary key or unique
+                let referring = self.database.tables.values().find(|t| {
+                    let key = t.reference_attr.iter().cloned().collect::<Vec<String>>();
+                    !t.is_delete
+                        && t.name != table_name
+                        && t.reference_table.as_deref() == Some(table_name)
+                        && table.primary_key != key
+                        && !table.unique.contains(&key)
+                });
+                if let Some(t) = referring {
+                    return Err(SQLError::SemanticError(format!(
+                        "{} is referred by the foreign key of {}",
+                        name, t.name
+                    )));
+                }
+            }
+            AlterAction::DropField(name) => {
+                // a field referred by a foreign key could not be dropped
+                let is_referred = self.database.tables.values().any(|t| {
+                    !t.is_delete
+                        && t.reference_table.as_deref() == Some(table_name)
+                        && t.reference_attr.as_deref() == Some(name.as_str())
+                });
+                if is_referred {
+                    return Err(SQLError::SemanticError(format!(
+                        "{} is referred by a foreign key",
+                        name
+                    )));
+                }
+                if table.fields.len() == 1 && table.fields.contains_key(&name) {
+                    return Err(SQLError::SemanticError(String::from(
+                        "the only field of the table could not be dropped",
+                    )));
+                }
+                table.drop_field(&name).map_err(SQLError::CausedByTable)?;
+            }
+            AlterAction::AlterField(mut field) => {
+                // the default is kept, unless a new one is given
+                if field.default.is_none() {
+                    field.default = table.fields.get(&field.name).and_then(|old| old.default.clone());
+                }
+                table.alter_field(field).map_err(SQLError::CausedByTable)?;
+            }
+            AlterAction::SetDefault(name, default) => {
+                let mut field = table
+                    .fields
+                    .get(&name)
+                    .ok_or(SQLError::CausedByTable(TableError::AlterFieldNotExisted(name.clone())))?
+                    .clone();
+                field.default = default;
+                table.alter_field(field).map_err(SQLError::CausedByTable)?;
+            }
+        }
+
+        if !constraints.primary_key.is_empty() && !table.primary_key.is_empty() {
+            return Err(SQLError::SemanticError(String::from("multiple primary keys")));
+        }
+        if !constraints.foreign_key.is_empty() && !table.foreign_key.is_empty() {
+            return Err(SQLError::SemanticError(String::from(
+                "only one foreign key is supported",
+            )));
+        }
+        table.add_constraints(&constraints).map_err(SQLError::CausedByTable)?;
+        self.check_constraints(&table)?;
+
+        // the existing rows should refer to the reference table
+        if let (Some(reference_table), Some(reference_attr)) = (&table.reference_table, &table.reference_attr) {
+            let values = if reference_table == table_name {
+                table.get_values_set(reference_attr)
+            } else {
+                self.load_table_rows(reference_table)?;
+                self.database.tables[reference_table].get_values_set(reference_attr)
+            };
+            for row in table.rows.iter().filter(|row| !row.is_delete) {
+                table.check_foreign_key(row, &values).map_err(SQLError::CausedByTable)?;
+            }
+        }
+
+        // a new table isn't in storage yet, so it will be created with the new schema
+        if !table.is_dirty {
+            table.is_altered = true;
+        }
+        self.database.insert_new_table(table);
         Ok(())
     }
 
-    // TODO
     /// Drop the table
-    pub fn drop_table(&mut self, table_name: &str) -> Result<(), SQLError> {
+    ///
+    /// A table in storage is only marked deleted, and the pool will remove it later. A table referred
+    /// by the foreign key of another table could not be dropped.
+    pub fn drop_table(&mut self, table_name: &str, if_exists: bool) -> Result<(), SQLError> {
+        self.check_database()?;
+        if !self.is_table_existed(table_name) {
+            if if_exists {
+                return Ok(());
+            }
+            return Err(SQLError::SemanticError("table not exists".to_string()));
+        }
+        self.check_referring_tables(table_name)?;
+
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        if !table.is_stored {
+            // a new table has nothing in storage to remove
+            self.database.tables.remove(table_name);
+        } else {
+            table.is_delete = true;
+            table.rows.clear();
+        }
+        Ok(())
+    }
+
+    /// Remove all rows of the table
+    ///
+    /// The table is replaced by an empty one of the same schema, which the pool will create again in
+    /// storage, so the rows are not deleted one by one.
+    pub fn truncate_table(&mut self, table_name: &str) -> Result<(), SQLError> {
+        self.check_database()?;
+        if !self.is_table_existed(table_name) {
+            return Err(SQLError::SemanticError("table not exists".to_string()));
+        }
+        self.check_referring_tables(table_name)?;
+
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        table.truncate_rows();
+        table.is_dirty = true;
+        table.is_altered = false;
+        table.is_index_altered = false;
+        Ok(())
+    }
+
+    /// Create an index on a field of the table, whose values should be unique if the index is unique
+    ///
+    /// The index is recorded in the schema, and the pool will build it in storage later, while the rows
+    /// in storage are kept as they are.
+    pub fn create_index(&mut self, table_name: &str, index: IndexMeta) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        table.create_index(index).map_err(SQLError::CausedByTable)?;
+        // a new table isn't in storage yet, so it will be created with the index
+        if !table.is_dirty {
+            table.is_index_altered = true;
+        }
+        Ok(())
+    }
+
+    /// Drop the index of the table, which the pool will remove from storage later
+    pub fn drop_index(&mut self, table_name: &str, index_name: &str) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        table.drop_index(index_name).map_err(SQLError::CausedByTable)?;
+        if !table.is_dirty {
+            table.is_index_altered = true;
+        }
+        Ok(())
+    }
+
+    /// Check if no other table refers to the table with a foreign key, so it could be dropped or
+    /// truncated
+    fn check_referring_tables(&self, table_name: &str) -> Result<(), SQLError> {
+        let referring = self
+            .database
+            .tables
+            .values()
+            .find(|t| !t.is_delete && t.name != table_name && t.reference_table.as_deref() == Some(table_name));
+        match referring {
+            Some(t) => Err(SQLError::SemanticError(format!(
+                "{} is referred by the foreign key of {}",
+                table_name, t.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// List the databases of the user, for `show databases`
+    ///
+    /// The current database is listed even if it isn't in storage yet.
+    pub fn show_databases(&mut self) -> Result<(), SQLError> {
+        let mut dbs = match DiskInterface::get_dbs(&self.user.name, None) {
+            Ok(dbs) => dbs,
+            // a new user has nothing in storage
+            Err(DiskError::BaseDirNotExists)
+            | Err(DiskError::UsernamesJsonNotExists)
+            | Err(DiskError::UsernameNotExists)
+            | Err(DiskError::UsernameDirNotExists) => vec![],
+            Err(e) => return Err(SQLError::CauserByDatabase(DatabaseError::CausedByFile(e))),
+        };
+        dbs.retain(|db| db != &self.database.name);
+        if !self.database.name.is_empty() && !self.database.is_delete {
+            dbs.push(self.database.name.clone());
+        }
+        dbs.sort();
+
+        let mut data = SelectData::new();
+        data.fields = vec!["database".to_string()];
+        data.rows = dbs.into_iter().map(|db| vec![Some(db)]).collect();
+        self.result_json = serde_json::to_string(&data).unwrap();
+        Ok(())
+    }
+
+    /// List the tables of the current database, for `show tables`
+    pub fn show_tables(&mut self) -> Result<(), SQLError> {
+        self.check_database()?;
+        let mut tables: Vec<String> = self
+            .database
+            .tables
+            .values()
+            .filter(|t| !t.is_delete)
+            .map(|t| t.name.clone())
+            .collect();
+        tables.sort();
+
+        let mut data = SelectData::new();
+        data.fields = vec!["table".to_string()];
+        data.rows = tables.into_iter().map(|table| vec![Some(table)]).collect();
+        self.result_json = serde_json::to_string(&data).unwrap();
+        Ok(())
+    }
+
+    /// Show the fields of the table, for `describe` and `show columns from`
+    pub fn describe_table(&mut self, table_name: &str) -> Result<(), SQLError> {
+        self.check_database()?;
+        if !self.is_table_existed(table_name) {
+            return Err(SQLError::SemanticError("table not exists".to_string()));
+        }
+        let data = self.database.tables[table_name].describe();
+        self.result_json = serde_json::to_string(&data).unwrap();
         Ok(())
     }
 
-    // TODO: check db, table delete bit
     /// Insert new rows into the table
     pub fn insert_into_table(
         &mut self,
         table_name: &str,
         attrs: Vec<String>,
-        rows: Vec<Vec<String>>,
+        rows: Vec<InsertValues>,
     ) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+        let referenced = self.get_referenced_values(table_name)?;
+
         let table = self
             .database
             .tables
@@ -101,16 +478,224 @@ impl SQL {
             table.public_key = self.user.key;
         }
 
+        // if any row fails, remove the rows inserted by this query
+        let num_rows = table.rows.len();
         for row in rows {
-            let mut row_in_pair: Vec<(&str, &str)> = Vec::new();
+            let mut row_in_pair: Vec<(&str, Option<&str>)> = Vec::new();
             for i in 0..attrs.len() {
-                row_in_pair.push((&attrs[i], &row[i]));
+                row_in_pair.push((&attrs[i], row[i].as_deref()));
+            }
+            if let Err(e) = table.insert_row(row_in_pair) {
+                table.rollback_rows(num_rows);
+                return Err(SQLError::CausedByTable(e));
+            }
+        }
+        if let Some(values) = referenced {
+            for i in num_rows..table.rows.len() {
+                if let Err(e) = table.check_foreign_key(&table.rows[i], &values) {
+                    table.rollback_rows(num_rows);
+                    return Err(SQLError::CausedByTable(e));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Update the rows matching the predicate with the new values
+    ///
+    /// The new values are evaluated on each row before any row is modified. The rows are only modified
+    /// in memory and marked dirty, the pool will write them back later.
+    pub fn update_table(
+        &mut self,
+        table_name: &str,
+        mut assignments: Vec<Assignment>,
+        mut predicate: NodePtr,
+    ) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+        let referenced = self.get_referenced_values(table_name)?;
+
+        let set = self.get_matched_rows(table_name, &mut predicate)?;
+        for (_, expression) in assignments.iter_mut() {
+            if expression.has_subquery() {
+                self.resolve_subqueries(expression, None)?;
+            }
+        }
+        let table = self.database.tables.get(table_name).unwrap();
+        for (attr, _) in assignments.iter() {
+            if !table.fields.contains_key(attr) {
+                return Err(SQLError::CausedByTable(TableError::UpdateFieldNotExisted(attr.clone())));
             }
-            table
-                .insert_row(row_in_pair)
-                .map_err(|e| SQLError::SemanticError(format!("{}", e)))?;
         }
 
+        let mut updates: HashMap<usize, RowUpdate> = HashMap::new();
+        for i in set.iter() {
+            let mut values = vec![];
+            for (attr, expression) in assignments.iter() {
+                let value = match expression::eval_expression(expression, &table.fields, &table.rows[*i])
+                    .map_err(|e| SQLError::CausedByTable(TableError::CausedByExpression(e)))?
+                {
+                    expression::Value::Null => None,
+                    value => Some(value.to_string()),
+                };
+                values.push((attr.clone(), value));
+            }
+            updates.insert(*i, values);
+        }
+
+        // the updated rows should still refer to the reference table
+        if let Some(values) = referenced {
+            for (i, new_values) in updates.iter() {
+                let mut row = table.rows[*i].clone();
+                for (key, value) in new_values.iter() {
+                    row.set_value(key, value.as_deref());
+                }
+                table
+                    .check_foreign_key(&row, &values)
+                    .map_err(SQLError::CausedByTable)?;
+            }
+        }
+        self.check_referenced_rows(table_name, &set, Some(&updates))?;
+
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        table.update_rows(&updates).map_err(SQLError::CausedByTable)?;
+
+        Ok(())
+    }
+
+    /// Delete the rows matching the predicate
+    ///
+    /// The rows are only marked deleted in memory, the pool will write the deletion back later.
+    pub fn delete_from_table(&mut self, table_name: &str, mut predicate: NodePtr) -> Result<(), SQLError> {
+        self.load_table_rows(table_name)?;
+
+        let set = self.get_matched_rows(table_name, &mut predicate)?;
+        self.check_referenced_rows(table_name, &set, None)?;
+
+        let table = self.database.tables.get_mut(table_name).unwrap();
+        table.delete_rows(&set);
+
+        Ok(())
+    }
+
+    /// Find the rows of the table where the predicate is true, or all the rows without a predicate
+    ///
+    /// The predicate is evaluated on the table itself, which is taken out of the database meanwhile. A
+    /// predicate with subqueries is evaluated on a copy instead, since they could query the table.
+    fn get_matched_rows(&mut self, table_name: &str, predicate: &mut NodePtr) -> Result<HashSet<usize>, SQLError> {
+        let has_subquery = match predicate {
+            Some(p) => p.has_subquery(),
+            None => return Ok(self.database.tables[table_name].get_all_rows_set()),
+        };
+        let result = if has_subquery {
+            let vt = self.database.tables[table_name].clone();
+            self.table_predicate(&vt, predicate)
+        } else {
+            let table = self.database.tables.remove(table_name).unwrap();
+            let result = self.table_predicate(&table, predicate);
+            self.database.tables.insert(table_name.to_string(), table);
+            result
+        };
+        result?;
+        Ok(predicate.as_ref().map(|p| p.set.clone()).unwrap_or_default())
+    }
+
+    /// Load the rows of the table from storage, if they haven't been loaded
+    ///
+    /// A dropped table, or a table of a dropped database, doesn't exist.
+    fn load_table_rows(&mut self, table_name: &str) -> Result<(), SQLError> {
+        self.check_database()?;
+        let table = self
+            .database
+            .tables
+            .get_mut(table_name)
+            .filter(|t| !t.is_delete)
+            .ok_or(SQLError::SemanticError("table not exists".to_string()))?;
+        table
+            .load_all_rows_data(&self.user.name, &self.database.name, None)
+            .map_err(SQLError::CausedByTable)
+    }
+
+    /// Load the rows of the table, and copy them to a virtual table with field names qualified by the
+    /// qualifier, which is the table name or its alias
+    fn get_qualified_table(&mut self, table_name: &str, qualifier: &str) -> Result<Table, SQLError> {
+        self.load_table_rows(table_name)?;
+        Ok(self.database.tables.get(table_name).unwrap().to_qualified(qualifier))
+    }
+
+    /// Get the values which the foreign key of the table could refer to
+    ///
+    /// Return `None` if the table has no foreign key.
+    fn get_referenced_values(&mut self, table_name: &str) -> Result<Option<HashSet<String>>, SQLError> {
+        let table = self
+            .database
+            .tables
+            .get(table_name)
+            .ok_or(SQLError::SemanticError("table not exists".to_string()))?;
+        let (reference_table, reference_attr) = match (&table.reference_table, &table.reference_attr) {
+            (Some(t), Some(a)) => (t.clone(), a.clone()),
+            _ => return Ok(None),
+        };
+
+        self.load_table_rows(&reference_table)?;
+        let reference = self.database.tables.get(&reference_table).unwrap();
+        Ok(Some(reference.get_values_set(&reference_attr)))
+    }
+
+    /// Check if the rows could be deleted, or be modified by `updates`, while they might be referred by
+    /// the foreign keys of other tables
+    fn check_referenced_rows(
+        &mut self,
+        table_name: &str,
+        row_set: &HashSet<usize>,
+        updates: Option<&HashMap<usize, RowUpdate>>,
+    ) -> Result<(), SQLError> {
+        let referring_tables: Vec<(String, String, String)> = self
+            .database
+            .tables
+            .values()
+            .filter(|t| !t.is_delete && t.reference_table == Some(table_name.to_string()))
+            .map(|t| {
+                (
+                    t.name.clone(),
+                    t.foreign_key[0].clone(),
+                    t.reference_attr.clone().unwrap(),
+                )
+            })
+            .collect();
+
+        for (referring_table, foreign_key, reference_attr) in referring_tables {
+            // on update, only the modification of the reference attribute matters
+            let is_assigned = |values: &RowUpdate| values.iter().any(|(attr, _)| attr == &reference_attr);
+            if updates.is_some_and(|updates| !updates.values().any(is_assigned)) {
+                continue;
+            }
+
+            self.load_table_rows(&referring_table)?;
+            let referring = self
+                .database
+                .tables
+                .get(&referring_table)
+                .unwrap()
+                .get_values_set(&foreign_key);
+
+            let table = self.database.tables.get(table_name).unwrap();
+            for i in row_set.iter() {
+                let value = match table.rows[*i].data.get(&reference_attr) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let new_value = updates
+                    .and_then(|updates| updates[i].iter().find(|(attr, _)| attr == &reference_attr))
+                    .and_then(|(_, value)| value.as_ref());
+                if Some(value) != new_value && referring.contains(value) {
+                    return Err(SQLError::CausedByTable(TableError::ForeignKeyViolation(format!(
+                        "{}({}) refers to ({}) = ({})",
+                        referring_table, foreign_key, reference_attr, value
+                    ))));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -169,6 +754,28 @@ impl SQL {
     ///
     /// reference: [stack overflow #1018822](https://stackoverflow.com/a/1944492/6798649)
     pub fn select(&mut self) -> Result<(), SQLError> {
+        let mut querydata = mem::replace(&mut self.querydata, QueryData::new());
+        let result = self.query(&mut querydata, None);
+        self.querydata = querydata;
+
+        let (_, data) = result?;
+        self.result_json = serde_json::to_string(&data).unwrap();
+        Ok(())
+    }
+
+    /// Run a select query in the steps described in `select`, and return the fields and data of the result
+    ///
+    /// `outer` is the table and the row id of the outer query for a correlated subquery, whose fields
+    /// could be referred to if the query doesn't have them.
+    fn query(
+        &mut self,
+        querydata: &mut QueryData,
+        outer: Option<(&Table, usize)>,
+    ) -> Result<(Vec<Field>, SelectData), SQLError> {
+        if !querydata.set_operations.is_empty() {
+            return self.set_query(querydata, outer);
+        }
+
         let mut is_where_clause = false;
         let mut is_join_on_clause = false;
 
@@ -176,30 +783,55 @@ impl SQL {
 
         // step 1
 
-        // copy the first table
-        let mut vt1 = self
-            .database
-            .tables
-            .get(&self.querydata.tables[0])
-            .ok_or(SQLError::SemanticError("table not exists".to_string()))?
-            .clone();
+        // load the first table, then copy it
+        let first_table = querydata.tables[0].clone();
+        let mut vt1 = self.get_from_table(&first_table, querydata)?;
 
         // dealing cross joins
-        if self.querydata.tables.len() > 1 {
+        if querydata.tables.len() > 1 {
             is_where_clause = true;
         }
-        if self.querydata.joins.len() > 0 {
+        if !querydata.joins.is_empty() {
             is_join_on_clause = true;
         }
 
-        let mut vt3 = Table::new(""); // stand by
+        let mut vt3;
 
         match (is_where_clause, is_join_on_clause) {
             (true, false) => {
-                // TODO: step 1.1
+                // step 1.1
+                // cross join the tables, then the rows are filtered by the where clause in step 4
+                for table_name in querydata.tables[1..].iter() {
+                    let vt2 = self.get_from_table(table_name, querydata)?;
+                    vt1 = vt1.cross_join(&vt2);
+                }
+                vt3 = vt1;
             }
             (false, true) => {
-                // TODO: step 1.2
+                // step 1.2
+                for i in 0..querydata.joins.len() {
+                    let vt2 = self.get_from_table(&querydata.joins[i].table, querydata)?;
+                    let join = &mut querydata.joins[i];
+
+                    // filter the cross join by the condition, in which the fields could be unqualified
+                    let mut product = vt1.cross_join(&vt2);
+                    product.add_unqualified_fields();
+                    if let Some(condition) = join.condition.as_deref() {
+                        let mut names = vec![];
+                        collect_node_references(condition, &mut names, &mut vec![]);
+                        check_ambiguous_fields(&names, &product)?;
+                    }
+                    if let Some((outer_table, i)) = outer {
+                        product.add_outer_fields(outer_table, &outer_table.rows[i]);
+                    }
+                    self.table_predicate(&product, &mut join.condition)?;
+                    let matched = match join.condition.as_ref() {
+                        Some(s) => s.set.clone(),
+                        None => HashSet::new(),
+                    };
+                    vt1 = vt1.join(&vt2, &join.join_type, &matched);
+                }
+                vt3 = vt1;
             }
             (true, true) => {
                 return Err(SQLError::SemanticError(String::from(
@@ -208,17 +840,26 @@ impl SQL {
             }
             (false, false) => {
                 // No join. The virtual table is the table.
-                vt1.load_all_rows_data(&self.user.name, &self.database.name)
-                    .map_err(|e| SQLError::SemanticError(format!("{}", e)))?;
                 vt3 = vt1;
             }
         }
 
+        expand_star_fields(querydata, &vt3)?;
+
+        // the fields could be referred without the table name if there is no ambiguity
+        vt3.add_unqualified_fields();
+        check_ambiguous_fields(&get_references(querydata).0, &vt3)?;
+
+        // a correlated subquery could refer to the fields of the outer row
+        if let Some((outer_table, i)) = outer {
+            vt3.add_outer_fields(outer_table, &outer_table.rows[i]);
+        }
+
         // step 4
         let mut vt4;
-        if self.querydata.predicate.is_some() {
-            table_predicate(&mut vt3, &mut self.querydata.predicate)?;
-            let set = match self.querydata.predicate.as_ref() {
+        if querydata.predicate.is_some() {
+            self.table_predicate(&vt3, &mut querydata.predicate)?;
+            let set = match querydata.predicate.as_ref() {
                 Some(s) => s.set.clone(),
                 None => HashSet::new(), // should not happen, but still set empty if ever happen
             };
@@ -226,106 +867,608 @@ impl SQL {
         }
         vt4 = vt3;
 
+        // step 5, 6, 7
+        let is_grouping = !querydata.group_fields.is_empty() || !querydata.aggregation_fn.is_empty();
+        if is_grouping {
+            // a field not aggregated has only a value in the group if it is grouped by
+            for field in &querydata.fields {
+                let is_aggregated = querydata.aggregation_fn.iter().any(|agg| &agg.name() == field);
+                let is_subquery = querydata.subquery_fields.iter().any(|(name, _)| name == field);
+                // the fields in an expression are checked when it is evaluated on the groups
+                let is_computed = querydata.computed_fields.iter().any(|(name, _)| name == field);
+                if !is_aggregated && !is_subquery && !is_computed && !querydata.group_fields.contains(field) {
+                    return Err(SQLError::SemanticError(format!(
+                        "{} should be in group by or an aggregate function",
+                        field
+                    )));
+                }
+            }
+            let mut vt5 = vt4
+                .group_by(&querydata.group_fields, &querydata.aggregation_fn)
+                .map_err(SQLError::CausedByTable)?;
+
+            if querydata.having.is_some() {
+                self.table_predicate(&vt5, &mut querydata.having)?;
+                let set = match querydata.having.as_ref() {
+                    Some(s) => s.set.clone(),
+                    None => HashSet::new(),
+                };
+                vt5.set_row_set(set);
+            }
+            vt4 = vt5;
+        } else if querydata.having.is_some() {
+            return Err(SQLError::SemanticError(String::from(
+                "having should be with group by or an aggregate function",
+            )));
+        }
+
         // step 8
-        let data = vt4
-            .select(self.querydata.fields.clone())
+        // the subqueries and the expressions are computed before sorting, so they could be sorted by
+        for (name, subquery) in querydata.subquery_fields.iter() {
+            self.add_subquery_field(&mut vt4, name, subquery)?;
+        }
+        for (name, expression) in querydata.computed_fields.iter() {
+            self.add_computed_field(&mut vt4, name, expression)?;
+        }
+
+        // step 10
+        // sort before selecting, so the fields not selected could be sorted by, or by the aliases
+        if !querydata.sort_fields.is_empty() {
+            let sort_fields: Vec<String> = querydata
+                .sort_fields
+                .iter()
+                .map(
+                    |name| match querydata.aliases.iter().position(|alias| alias.as_ref() == Some(name)) {
+                        Some(i) => querydata.fields[i].clone(),
+                        None => name.clone(),
+                    },
+                )
+                .collect();
+            vt4.sort_rows(&sort_fields, &querydata.sort_dir)
+                .map_err(SQLError::CausedByTable)?;
+        }
+
+        let mut data = vt4
+            .select(querydata.fields.clone())
             .map_err(|e| SQLError::SemanticError(format!("{}", e)))?;
+        // the fields of the result are named by their aliases
+        let mut fields: Vec<Field> = vec![];
+        for (i, name) in querydata.fields.iter().enumerate() {
+            let mut field = vt4.fields[name].clone();
+            field.name = match querydata.aliases.get(i) {
+                Some(Some(alias)) => alias.to_string(),
+                Some(None) | None => name.to_string(),
+            };
+            data.fields[i] = field.name.clone();
+            fields.push(field);
+        }
 
-        self.result_json = serde_json::to_string(&data).unwrap();
-        Ok(())
+        // step 9
+        if querydata.is_distinct {
+            data.distinct();
+        }
+
+        // step 11
+        data.top(&querydata.top, querydata.offset as usize);
+
+        Ok((fields, data))
     }
-}
 
-fn table_predicate(tb: &mut Table, node: &mut NodePtr) -> Result<(), SQLError> {
-    match node.as_mut() {
-        Some(p) => {
-            // due to the mechanism of borrowing, set the mutable variable first before call them.
-            let left;
-            let right;
-            let mut left_node_root = "".to_string();
-            let mut left_node_set = HashSet::new();
-            let mut right_node_root = "".to_string();
-            let mut right_node_set = HashSet::new();
-            let mut ll = false;
-            let mut lr = false;
-            let mut rl = false;
-            let mut rr = false;
-            let this_node_root: &str = &p.root;
-
-            // post-order traversal
-            table_predicate(tb, &mut p.left)?;
-            table_predicate(tb, &mut p.right)?;
-
-            match p.left.as_mut() {
-                Some(s) => {
-                    left = true;
-                    left_node_root = s.root.to_string();
-                    left_node_set = s.set.clone();
-                    ll = s.left.is_some();
-                    lr = s.right.is_some();
+    /// Run the queries combined by set operations from left to right, then sort and limit the result
+    ///
+    /// The queries should select the same number of fields, and the fields at the same position should
+    /// be both numeric or both not. The fields of the result are named by the first query.
+    fn set_query(
+        &mut self,
+        querydata: &QueryData,
+        outer: Option<(&Table, usize)>,
+    ) -> Result<(Vec<Field>, SelectData), SQLError> {
+        let mut first = querydata.clone();
+        first.set_operations = vec![];
+        first.sort_fields = vec![];
+        first.sort_dir = vec![];
+        first.top = TopType::None;
+        first.offset = 0;
+        let (mut fields, mut data) = self.query(&mut first, outer)?;
+
+        for (operator, query) in querydata.set_operations.iter() {
+            let (other_fields, other_data) = self.query(&mut query.clone(), outer)?;
+            if other_fields.len() != fields.len() {
+                return Err(SQLError::SemanticError(String::from(
+                    "queries in set operations should select the same number of fields",
+                )));
+            }
+            for (field, other) in fields.iter_mut().zip(other_fields.iter()) {
+                if field.datatype.is_numeric() != other.datatype.is_numeric() {
+                    return Err(SQLError::SemanticError(format!(
+                        "{} and {} are not compatible in set operations",
+                        field.name, other.name
+                    )));
+                }
+                // an integer and a decimal are combined as decimals
+                if field.datatype.is_numeric() && field.datatype != other.datatype {
+                    field.datatype = DataType::Double;
                 }
-                None => left = false,
             }
 
-            match p.right.as_mut() {
-                Some(s) => {
-                    right = true;
-                    right_node_root = s.root.to_string();
-                    right_node_set = s.set.clone();
-                    rl = s.left.is_some();
-                    rr = s.right.is_some();
-                }
-                None => right = false,
+            // the rows are compared by their values, so nulls are regarded as the same
+            let other_rows: HashSet<Vec<Option<String>>> = other_data.rows.iter().cloned().collect();
+            match operator {
+                SetOperator::Union | SetOperator::UnionAll => data.rows.extend(other_data.rows),
+                SetOperator::Intersect => data.rows.retain(|row| other_rows.contains(row)),
+                SetOperator::Except => data.rows.retain(|row| !other_rows.contains(row)),
             }
+            if *operator != SetOperator::UnionAll {
+                data.distinct();
+            }
+        }
 
-            debug!("current node: {}", this_node_root);
-            debug!("left node: {}", left_node_root);
-            debug!("right node: {}", right_node_root);
-            debug!("grandchildren nodes: {:?}", (ll, lr, rl, rr));
+        if !querydata.sort_fields.is_empty() {
+            let names: Vec<String> = fields.iter().map(|field| field.name.clone()).collect();
+            let mut vt = Table::from_select_data("", &fields, &data);
+            vt.add_unqualified_fields();
+            vt.sort_rows(&querydata.sort_fields, &querydata.sort_dir)
+                .map_err(SQLError::CausedByTable)?;
+            data = vt
+                .select(names)
+                .map_err(|e| SQLError::SemanticError(format!("{}", e)))?;
+        }
+        data.top(&querydata.top, querydata.offset as usize);
 
-            if left && right {
-                match (ll, lr, rl, rr) {
-                    (false, false, false, false) => match this_node_root {
-                        "and" => {
-                            let set: HashSet<usize> = left_node_set.intersection(&right_node_set).cloned().collect();
-                            (*p).set = set;
-                        }
-                        "or" => {
-                            let set: HashSet<usize> = left_node_set.union(&right_node_set).cloned().collect();
-                            (*p).set = set;
-                        }
-                        _ => {
-                            (*p).set = tb
-                                .operator_filter_rows(&left_node_root, this_node_root, &right_node_root)
-                                .map_err(|e| SQLError::SemanticError(format!("{}", e)))?;
+        Ok((fields, data))
+    }
 
-                            (*p).left = None;
-                            (*p).right = None;
-                        }
-                    },
-                    (_, _, _, _) => {}
-                };
+    /// Get the table in `FROM` or `JOIN` as a virtual table with qualified field names
+    ///
+    /// A derived table is the result of its subquery, whose fields are named without the tables they
+    /// come from, such as `x.a1` for `t1.a1` selected in the subquery aliased `x`.
+    fn get_from_table(&mut self, table_name: &str, querydata: &QueryData) -> Result<Table, SQLError> {
+        if let Some((alias, table)) = querydata.table_aliases.iter().find(|(alias, _)| alias == table_name) {
+            return self.get_qualified_table(table, alias);
+        }
+        let subquery = match querydata.derived_tables.iter().find(|(alias, _)| alias == table_name) {
+            Some((_, subquery)) => subquery,
+            None => return self.get_qualified_table(table_name, table_name),
+        };
+        let (mut fields, data) = self.query(&mut subquery.clone(), None)?;
+        for field in fields.iter_mut() {
+            match field.name.rfind('.') {
+                Some(pos) if !field.name.contains('(') => field.name = field.name[pos + 1..].to_string(),
+                _ => {}
             }
+        }
+        Ok(Table::from_select_data(table_name, &fields, &data).to_qualified(table_name))
+    }
 
-            if right {
-                match this_node_root {
-                    "not" => {
-                        let all = tb.get_all_rows_set();
-                        let set: HashSet<usize> = all.difference(&right_node_set).cloned().collect();
-                        (*p).set = set;
-                        // cut the tree
-                        (*p).left = None;
-                        (*p).right = None;
-                    }
-                    _ => {}
+    /// Add the field of a scalar subquery in the select list, with its value on each row of the table
+    fn add_subquery_field(&mut self, tb: &mut Table, name: &str, subquery: &QueryData) -> Result<(), SQLError> {
+        // an uncorrelated subquery has the same value on every row
+        let uncorrelated = match self.is_correlated(subquery, tb) {
+            true => None,
+            false => Some(self.query(&mut subquery.clone(), None)?),
+        };
+
+        let mut field: Option<Field> = None;
+        let mut values: Vec<(usize, Option<String>)> = vec![];
+        for i in tb.get_row_ids() {
+            let (fields, data) = match uncorrelated {
+                Some((ref fields, ref data)) => (fields.clone(), scalar_value(data)?),
+                None => {
+                    let (fields, data) = self.query(&mut subquery.clone(), Some((tb, i)))?;
+                    let value = scalar_value(&data)?;
+                    (fields, value)
                 }
-            }
+            };
+            field = fields.into_iter().next();
+            values.push((i, data));
+        }
 
-            debug!("this node set: {:?}", (*p).set);
+        let mut field = match (field, uncorrelated) {
+            (Some(field), _) => field,
+            (None, Some((fields, _))) => fields[0].clone(),
+            // no row to evaluate a correlated subquery on
+            (None, None) => Field::new(name, DataType::Varchar(0)),
+        };
+        field.name = name.to_string();
+        field.not_null = false;
+        tb.insert_new_field(field);
+        for (i, value) in values {
+            if let Some(value) = value {
+                tb.rows[i].data.insert(name.to_string(), value);
+            }
         }
-        None => {}
+        Ok(())
+    }
+
+    /// Add the field of an expression in the select list, with its value on each row of the table
+    ///
+    /// The datatype of the field is inferred from the expression, or a varchar if it is unknown.
+    fn add_computed_field(&mut self, tb: &mut Table, name: &str, expression: &Node) -> Result<(), SQLError> {
+        let to_error = |e| SQLError::CausedByTable(TableError::CausedByExpression(e));
+
+        // a correlated subquery depends on the outer row, so it is resolved on each row
+        let mut resolved = expression.clone();
+        let is_correlated = self.has_correlated_subquery(expression, tb);
+        if !is_correlated {
+            self.resolve_subqueries(&mut resolved, None)?;
+        }
+        let typed = if is_correlated { expression } else { &resolved };
+        let datatype = expression::infer_datatype(typed, &tb.fields)
+            .map_err(to_error)?
+            .unwrap_or(DataType::Varchar(255));
+
+        let mut values: Vec<(usize, expression::Value)> = vec![];
+        for i in tb.get_row_ids() {
+            let value = match is_correlated {
+                true => {
+                    let mut resolved = expression.clone();
+                    self.resolve_subqueries(&mut resolved, Some((tb, i)))?;
+                    expression::eval_expression(&resolved, &tb.fields, &tb.rows[i])
+                }
+                false => expression::eval_expression(&resolved, &tb.fields, &tb.rows[i]),
+            };
+            values.push((i, value.map_err(to_error)?));
+        }
+
+        tb.insert_new_field(Field::new(name, datatype));
+        for (i, value) in values {
+            if value != expression::Value::Null {
+                tb.rows[i].data.insert(name.to_string(), value.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the subqueries in the predicate by their results
+    ///
+    /// A scalar subquery is replaced by a literal, a subquery of `in` by the literals of its values, and
+    /// a subquery of `exists` by a literal for each of its rows.
+    fn resolve_subqueries(&mut self, node: &mut Node, outer: Option<(&Table, usize)>) -> Result<(), SQLError> {
+        if let Some(subquery) = node.subquery.take() {
+            let (fields, data) = self.query(&mut subquery.clone(), outer)?;
+            node.root = to_literal(&fields[0].datatype, scalar_value(&data)?.as_deref());
+            return Ok(());
+        }
+
+        if node.root == "in" || node.root == "not in" || node.root == "exists" {
+            let mut args: Vec<Node> = vec![];
+            for arg in node.args.iter() {
+                let subquery = match arg.subquery {
+                    Some(ref subquery) => subquery,
+                    None => {
+                        args.push(arg.clone());
+                        continue;
+                    }
+                };
+                let (fields, data) = self.query(&mut (**subquery).clone(), outer)?;
+                if node.root == "exists" {
+                    args.extend(data.rows.iter().map(|_| Node::new("1".to_string())));
+                    continue;
+                }
+                if fields.len() != 1 {
+                    return Err(SQLError::SemanticError(String::from(
+                        "subquery of in should return a column",
+                    )));
+                }
+                for row in data.rows.iter() {
+                    args.push(Node::new(to_literal(&fields[0].datatype, row[0].as_deref())));
+                }
+            }
+            node.args = args;
+        }
+
+        if let Some(ref mut left) = node.left {
+            self.resolve_subqueries(left, outer)?;
+        }
+        if let Some(ref mut right) = node.right {
+            self.resolve_subqueries(right, outer)?;
+        }
+        for arg in node.args.iter_mut() {
+            self.resolve_subqueries(arg, outer)?;
+        }
+        Ok(())
+    }
+
+    /// Check if any subquery in the node is correlated to the outer table
+    fn has_correlated_subquery(&self, node: &Node, outer: &Table) -> bool {
+        if let Some(ref subquery) = node.subquery {
+            if self.is_correlated(subquery, outer) {
+                return true;
+            }
+        }
+        let children = node.left.iter().chain(node.right.iter()).map(|child| child.as_ref());
+        children
+            .chain(node.args.iter())
+            .any(|child| self.has_correlated_subquery(child, outer))
+    }
+
+    /// Check if the subquery refers to the fields of the outer table, which is correlated to it
+    ///
+    /// A field is of the outer table if the tables of the subquery don't have it, and neither do the
+    /// tables of the queries between them for a nested subquery.
+    fn is_correlated(&self, subquery: &QueryData, outer: &Table) -> bool {
+        self.refers_to_outer(subquery, outer, &HashSet::new())
+    }
+
+    /// Check if the query refers to the fields of the outer table, where `enclosing` are the fields of
+    /// the queries enclosing it inside the outer one
+    fn refers_to_outer(&self, querydata: &QueryData, outer: &Table, enclosing: &HashSet<String>) -> bool {
+        let mut scope = self.get_scope_fields(querydata);
+        scope.extend(enclosing.iter().cloned());
+
+        let (mut names, mut subqueries) = get_references(querydata);
+        for join in querydata.joins.iter() {
+            if let Some(condition) = join.condition.as_deref() {
+                collect_node_references(condition, &mut names, &mut subqueries);
+            }
+        }
+        names
+            .iter()
+            .any(|name| outer.fields.contains_key(*name) && !scope.contains(*name))
+            || subqueries
+                .iter()
+                .any(|subquery| self.refers_to_outer(subquery, outer, &scope))
+            || querydata
+                .set_operations
+                .iter()
+                .any(|(_, query)| self.refers_to_outer(query, outer, enclosing))
+    }
+
+    /// Get the names of the fields of the tables in `FROM` and `JOIN` of the query, both qualified and
+    /// unqualified, such as `t1.a1` and `a1`
+    ///
+    /// An unknown table has no field, so the query referring to it fails on its own.
+    fn get_scope_fields(&self, querydata: &QueryData) -> HashSet<String> {
+        let mut scope = HashSet::new();
+        let table_names = querydata
+            .tables
+            .iter()
+            .chain(querydata.joins.iter().map(|join| &join.table));
+        for name in table_names {
+            let mut fields: Vec<String> = vec![];
+            match querydata.derived_tables.iter().find(|(alias, _)| alias == name) {
+                // the fields of a derived table are named by their aliases or without the tables
+                Some((_, derived)) => {
+                    for (i, field) in derived.fields.iter().enumerate() {
+                        if field == "*" || field.ends_with(".*") {
+                            let expanded = self.get_scope_fields(derived);
+                            fields.extend(expanded.into_iter().filter(|field| !field.contains('.')));
+                            continue;
+                        }
+                        match derived.aliases.get(i) {
+                            Some(Some(alias)) => fields.push(alias.clone()),
+                            _ => fields.push(field[field.rfind('.').map_or(0, |pos| pos + 1)..].to_string()),
+                        }
+                    }
+                }
+                None => {
+                    let table_name = match querydata.table_aliases.iter().find(|(alias, _)| alias == name) {
+                        Some((_, table_name)) => table_name,
+                        None => name,
+                    };
+                    if let Some(table) = self.database.tables.get(table_name) {
+                        fields.extend(table.field_order.iter().cloned());
+                    }
+                }
+            }
+            for field in fields {
+                scope.insert(format!("{}.{}", name, field));
+                scope.insert(field);
+            }
+        }
+        scope
+    }
+
+    /// Filter the rows of the table by the predicate, the set of each node is the rows where it is true
+    ///
+    /// A range on an indexed field is filtered by scanning its index rather than every row.
+    fn table_predicate(&mut self, tb: &Table, node: &mut NodePtr) -> Result<(), SQLError> {
+        if let Some(p) = node.as_mut() {
+            self.node_predicate(tb, p)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate the predicate node in three-valued logic, and return the rows where it is false
+    ///
+    /// The rows neither in the set of the node nor in the returned set are unknown, which is caused by
+    /// null. Such as `not (a1 > 1)` doesn't pick the rows whose `a1` is null.
+    fn node_predicate(&mut self, tb: &Table, p: &mut Node) -> Result<HashSet<usize>, SQLError> {
+        debug!("current node: {}", p.root);
+        let false_set: HashSet<usize> = match p.root.as_str() {
+            "and" | "or" | "not" => {
+                // post-order traversal
+                let left_false_set = match p.left.as_mut() {
+                    Some(left) => self.node_predicate(tb, left)?,
+                    None => HashSet::new(),
+                };
+                let right_false_set = match p.right.as_mut() {
+                    Some(right) => self.node_predicate(tb, right)?,
+                    None => HashSet::new(),
+                };
+
+                let left_node_set = p.left.as_ref().map(|s| s.set.clone()).unwrap_or_default();
+                let right_node_set = p.right.as_ref().map(|s| s.set.clone()).unwrap_or_default();
+                let (set, false_set) = match p.root.as_str() {
+                    "and" => (
+                        left_node_set.intersection(&right_node_set).cloned().collect(),
+                        left_false_set.union(&right_false_set).cloned().collect(),
+                    ),
+                    "or" => (
+                        left_node_set.union(&right_node_set).cloned().collect(),
+                        left_false_set.intersection(&right_false_set).cloned().collect(),
+                    ),
+                    _ => (right_false_set, right_node_set),
+                };
+                p.set = set;
+                false_set
+            }
+            // a comparison, whose both sides could be any expression
+            _ => {
+                // a correlated subquery depends on the outer row, so it is evaluated on each row
+                let is_correlated = self.has_correlated_subquery(p, tb);
+                let mut resolved = p.clone();
+                if !is_correlated {
+                    self.resolve_subqueries(&mut resolved, None)?;
+                }
+                if is_correlated {
+                    let (set, false_set) = self.filter_correlated_rows(tb, p)?;
+                    p.set = set;
+                    false_set
+                } else if let Some((set, false_set)) =
+                    tb.index_filter_rows(&resolved).map_err(SQLError::CausedByTable)?
+                {
+                    p.set = set;
+                    false_set
+                } else {
+                    let set = tb.filter_rows(&resolved).map_err(SQLError::CausedByTable)?;
+                    let negation = Node::new("not".to_string()).right(resolved);
+                    let false_set = tb.filter_rows(&negation).map_err(SQLError::CausedByTable)?;
+                    p.set = set;
+                    false_set
+                }
+            }
+        };
+        debug!("this node set: {:?}", p.set);
+        Ok(false_set)
+    }
+
+    /// Evaluate the comparison with correlated subqueries on each row, and return the rows where it is
+    /// true and the rows where it is false
+    fn filter_correlated_rows(&mut self, tb: &Table, p: &Node) -> Result<(HashSet<usize>, HashSet<usize>), SQLError> {
+        let mut set = HashSet::new();
+        let mut false_set = HashSet::new();
+        for i in tb.get_row_ids() {
+            let mut resolved = p.clone();
+            self.resolve_subqueries(&mut resolved, Some((tb, i)))?;
+            let result = expression::eval_predicate(&resolved, &tb.fields, &tb.rows[i])
+                .map_err(|e| SQLError::CausedByTable(TableError::CausedByExpression(e)))?;
+            match result {
+                Some(true) => set.insert(i),
+                Some(false) => false_set.insert(i),
+                None => false,
+            };
+        }
+        Ok((set, false_set))
+    }
+}
+
+/// Get the names which the query refers to, and the subqueries in its select list and expressions
+///
+/// The names are the fields and the roots of the expressions, some of which are operators or literals
+/// rather than fields. The names in the join conditions, which only refer to the tables joined before,
+/// and the ones in the subqueries, the derived tables and the queries combined by set operations are not
+/// included.
+fn get_references(querydata: &QueryData) -> (Vec<&String>, Vec<&QueryData>) {
+    let mut names: Vec<&String> = vec![];
+    names.extend(querydata.fields.iter());
+    names.extend(querydata.group_fields.iter());
+    names.extend(querydata.sort_fields.iter());
+    names.extend(querydata.aggregation_fn.iter().map(|aggregation| &aggregation.field));
+    let mut subqueries: Vec<&QueryData> = querydata.subquery_fields.iter().map(|(_, subquery)| subquery).collect();
+    let nodes = querydata
+        .computed_fields
+        .iter()
+        .map(|(_, node)| node)
+        .chain(querydata.predicate.as_deref())
+        .chain(querydata.having.as_deref());
+    for node in nodes {
+        collect_node_references(node, &mut names, &mut subqueries);
+    }
+    (names, subqueries)
+}
+
+/// Collect the roots of the expression and its subqueries
+fn collect_node_references<'a>(node: &'a Node, names: &mut Vec<&'a String>, subqueries: &mut Vec<&'a QueryData>) {
+    names.push(&node.root);
+    if let Some(ref subquery) = node.subquery {
+        subqueries.push(subquery);
+    }
+    let children = node.left.iter().chain(node.right.iter()).map(|child| child.as_ref());
+    for child in children.chain(node.args.iter()) {
+        collect_node_references(child, names, subqueries);
+    }
+}
+
+/// Check if none of the names refers to a field without the table name, which more than one of the
+/// tables have
+fn check_ambiguous_fields(names: &[&String], tb: &Table) -> Result<(), SQLError> {
+    match names.iter().find(|name| tb.is_ambiguous(name)) {
+        Some(name) => Err(SQLError::SemanticError(format!("ambiguous column {}", name))),
+        None => Ok(()),
+    }
+}
+
+/// Expand `*` and `t1.*` in the select list to the fields of the tables in the order of the schema
+///
+/// An expanded field is named without the table name if there is no ambiguity, such as `a1` for `t1.a1`.
+fn expand_star_fields(querydata: &mut QueryData, tb: &Table) -> Result<(), SQLError> {
+    let unqualified = |name: &str| match name.rfind('.') {
+        Some(pos) => name[pos + 1..].to_string(),
+        None => name.to_string(),
+    };
+
+    let mut fields: Vec<String> = vec![];
+    let mut aliases: Vec<Option<String>> = vec![];
+    for (i, name) in querydata.fields.iter().enumerate() {
+        let expanded: Vec<&String> = if name == "*" {
+            tb.field_order.iter().collect()
+        } else if name.ends_with(".*") {
+            let prefix = &name[..name.len() - 1]; // `t1.`
+            let expanded: Vec<&String> = tb
+                .field_order
+                .iter()
+                .filter(|field| field.starts_with(prefix))
+                .collect();
+            if expanded.is_empty() {
+                return Err(SQLError::SemanticError(format!("table of {} not exists", name)));
+            }
+            expanded
+        } else {
+            fields.push(name.clone());
+            aliases.push(querydata.aliases.get(i).cloned().unwrap_or(None));
+            continue;
+        };
+
+        for field in expanded {
+            let short = unqualified(field);
+            let is_ambiguous = tb
+                .field_order
+                .iter()
+                .filter(|other| unqualified(other) == short)
+                .count()
+                > 1;
+            fields.push(field.clone());
+            aliases.push(if is_ambiguous { None } else { Some(short) });
+        }
+    }
+    querydata.fields = fields;
+    querydata.aliases = aliases;
+    Ok(())
+}
+
+/// the value of a scalar subquery, which returns a column and at most a row, `None` if it is null
+fn scalar_value(data: &SelectData) -> Result<Option<String>, SQLError> {
+    if data.fields.len() != 1 {
+        return Err(SQLError::SemanticError(String::from(
+            "scalar subquery should return a column",
+        )));
+    }
+    if data.rows.len() > 1 {
+        return Err(SQLError::SemanticError(String::from(
+            "scalar subquery should return at most a row",
+        )));
+    }
+    Ok(data.rows.first().and_then(|row| row[0].clone()))
+}
+
+/// the value of the datatype as a literal in a predicate, a string is quoted, and a null is `null`
+fn to_literal(datatype: &DataType, value: Option<&str>) -> String {
+    match value {
+        None => "null".to_string(),
+        Some(value) if datatype.is_numeric() => value.to_string(),
+        Some(value) => format!("'{}'", value),
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -336,7 +1479,7 @@ mod tests {
 
     fn fake_sql() -> SQL {
         let mut sql = SQL::new("Tiger").unwrap();
-        sql.create_database("db11").unwrap();
+        sql.create_database("db11", false).unwrap();
 
         let query = "create table t1 (a1 int, a2 char(7), a3 double);";
         Parser::new(query).unwrap().parse(&mut sql).unwrap();
@@ -366,6 +1509,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_where_index() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int primary key, b2 varchar(10));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (7, 'abc'), (-3, 'ab'), (12, null), (0, 'b'), (100, 'abd');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("ranges on the primary key are filtered by its index");
+        let query = "select b1 from t2 where b1 > -3 and b1 <= 12;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b1\"],\"rows\":[[\"7\"],[\"12\"],[\"0\"]]}".to_string()
+        );
+
+        let query = "select b1 from t2 where not (b1 between 0 and 12) or 7 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b1\"],\"rows\":[[\"7\"],[\"-3\"],[\"100\"]]}".to_string()
+        );
+
+        let query = "delete from t2 where b1 >= 100;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "update t2 set b2 = 'neg' where b1 < 0;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select b1, b2 from t2 where b1 <> 7 and b2 like 'ne%';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b1\",\"b2\"],\"rows\":[[\"-3\",\"neg\"]]}".to_string()
+        );
+
+        println!("a prefix pattern on the primary key is filtered by its index");
+        let query = "create table t3 (c1 varchar(10) primary key);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t3(c1) values ('abc'), ('b'), ('ab'), ('a'), ('abd');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select c1 from t3 where c1 like 'ab%' and c1 not like 'abc';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"c1\"],\"rows\":[[\"ab\"],[\"abd\"]]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_create_drop_index() {
+        let mut sql = fake_sql();
+
+        println!("a secondary index could have duplicated values");
+        Parser::new("create index i1 on t1 (a2);")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+        let query = "select a1 from t1 where a2 = 'bbb' and a1 <> 4;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"3\"],[\"5\"]]}".to_string()
+        );
+
+        println!("a unique index rejects duplicated values");
+        assert!(Parser::new("create unique index i2 on t1 (a2);")
+            .unwrap()
+            .parse(&mut sql)
+            .is_err());
+        Parser::new("create unique index i2 on t1 (a1);")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+        assert!(Parser::new("insert into t1(a1, a2, a3) values (3, 'ccc', 2.6);")
+            .unwrap()
+            .parse(&mut sql)
+            .is_err());
+        assert!(Parser::new("update t1 set a1 = 1 where a1 = 2;")
+            .unwrap()
+            .parse(&mut sql)
+            .is_err());
+        Parser::new("insert into t1(a1, a2, a3) values (6, 'ccc', 2.6);")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+        let query = "select a1, a2 from t1 where a1 >= 5 or a2 like 'cc%';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\"],\"rows\":[[\"5\",\"bbb\"],[\"6\",\"ccc\"]]}".to_string()
+        );
+
+        Parser::new("describe t1;").unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"field\",\"type\",\"null\",\"key\",\"default\"],\"rows\":[\
+             [\"a1\",\"int\",\"yes\",\"uni\",\"null\"],\
+             [\"a2\",\"char(7)\",\"yes\",\"mul\",\"null\"],\
+             [\"a3\",\"double\",\"yes\",\"\",\"null\"]]}"
+                .to_string()
+        );
+
+        println!("an index should be on an existing field with a new name");
+        for query in [
+            "create index i1 on t1 (a3);",
+            "create index i3 on t1 (a4);",
+            "create index i3 on t4 (a3);",
+        ]
+        .iter()
+        {
+            assert!(Parser::new(query).unwrap().parse(&mut sql).is_err(), "{}", query);
+        }
+        assert!(Parser::new("alter table t1 drop column a2;")
+            .unwrap()
+            .parse(&mut sql)
+            .is_err());
+
+        println!("an index could be dropped once");
+        Parser::new("drop index i1 on t1;").unwrap().parse(&mut sql).unwrap();
+        assert!(Parser::new("drop index i1 on t1;").unwrap().parse(&mut sql).is_err());
+        let indexes = &sql.database.tables["t1"].indexes;
+        assert_eq!(indexes, &vec![IndexMeta::new("i2", "a1", true)]);
+        Parser::new("alter table t1 drop column a2;")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+
+        println!("the index of a stored table only changes its meta in storage");
+        let table = sql.database.tables.get_mut("t1").unwrap();
+        table.is_dirty = false;
+        table.is_stored = true;
+        Parser::new("create index i3 on t1 (a3);")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+        let table = &sql.database.tables["t1"];
+        assert!(table.is_index_altered && !table.is_altered);
+    }
+
     #[test]
     fn test_select_where_or() {
         let mut sql = fake_sql();
@@ -421,4 +1703,1070 @@ mod tests {
                 .to_string()
         );
     }
+    #[test]
+    fn test_alter_table() {
+        let mut sql = fake_sql();
+
+        println!("add a field with the default to the existing rows");
+        let query = "alter table t1 add column a4 int not null default 7;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1, a2, a3, a4) values (6, 'ccc', 2.6, 8);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select * from t1 where a1 > 4;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a3\",\"a4\"],\"rows\":[[\"5\",\"bbb\",\"2.5\",\"7\"],[\"6\",\"ccc\",\"2.6\",\"8\"]]}"
+                .to_string()
+        );
+        assert!(sql.database.tables["t1"].is_dirty && !sql.database.tables["t1"].is_altered);
+
+        println!("alter the datatype, drop the field");
+        let query = "alter table t1 alter column a4 double;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "alter table t1 drop column a3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1, a2, a4) values (7, 'ddd', null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select * from t1 where a1 = 1 or a1 = 7;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a4\"],\"rows\":[[\"1\",\"aaa\",\"7\"],[\"7\",\"ddd\",\"null\"]]}".to_string()
+        );
+
+        println!("constraints on the existing rows");
+        let query = "alter table t1 add constraint pk primary key (a1);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.database.tables["t1"].primary_key, vec!["a1".to_string()]);
+        let query = "insert into t1(a1, a2) values (7, 'eee');";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "alter table t1 alter column a2 set default 'zzz';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1) values (8);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "alter table t1 alter column a2 drop default;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1) values (9);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        let query = "create table t2 (b1 int references t1(a1), b2 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 1), (8, 2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("errors, which change nothing");
+        for query in vec![
+            "alter table t1 add column a2 int;",
+            "alter table t1 add column a5 int not null;",
+            "alter table t1 add column a5 int default 'x';",
+            "alter table t1 add constraint u unique (a2);",
+            "alter table t1 add check (a1 < 5);",
+            "alter table t1 add primary key (a2);",
+            "alter table t1 add unique (a9);",
+            "alter table t1 alter column a2 char(2);",
+            "alter table t1 alter column a2 int;",
+            "alter table t1 alter column a4 int not null;",
+            "alter table t1 alter column a9 int;",
+            "alter table t1 drop column a1;",
+            "alter table t1 drop column a9;",
+            "alter table t2 drop column b1;",
+            "alter table t2 add constraint fk foreign key (b2) references t1(a1);",
+            "alter table t2 add unique (b2) unique (b1);",
+            "alter table t3 drop column a1;",
+            "alter table t1 add constraint pk unique (a1, a2);",
+            "alter table t1 drop constraint pk;",
+            "alter table t1 drop constraint u9;",
+        ] {
+            assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        }
+        let query = "select * from t1 where a1 = 8;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a4\"],\"rows\":[[\"8\",\"zzz\",\"7\"]]}".to_string()
+        );
+
+        println!("drop the named constraints");
+        let query = "alter table t2 add constraint u2 unique (b2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 2);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "alter table t2 drop constraint u2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "alter table t1 add constraint c1 check (a1 < 100);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1, a2) values (100, 'fff');";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "alter table t1 drop constraint c1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t1(a1, a2) values (100, 'fff');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert!(!sql.database.tables["t1"].named_constraints.contains_key("c1"));
+    }
+
+    #[test]
+    fn test_drop_truncate_table() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int primary key, b2 char(7));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "create table t3 (c1 int, c2 int references t2(b1));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 'aaa'), (2, 'bbb');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("a table referred by a foreign key could not be dropped or truncated");
+        assert!(Parser::new("drop table t2;").unwrap().parse(&mut sql).is_err());
+        assert!(Parser::new("truncate table t2;").unwrap().parse(&mut sql).is_err());
+
+        println!("a new table is removed at once");
+        Parser::new("drop table t3;").unwrap().parse(&mut sql).unwrap();
+        assert!(!sql.database.tables.contains_key("t3"));
+        assert!(Parser::new("drop table t3;").unwrap().parse(&mut sql).is_err());
+        Parser::new("drop table if exists t3;")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+
+        println!("a table of the same name could not be created");
+        let query = "create table t2 (b1 int);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        assert_eq!(sql.database.tables.get("t2").unwrap().rows.len(), 2);
+
+        println!("truncate a stored table, which will be created again");
+        let table = sql.database.tables.get_mut("t2").unwrap();
+        table.is_dirty = false;
+        table.is_stored = true;
+        Parser::new("truncate table t2;").unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t2").unwrap();
+        assert!(table.rows.is_empty() && table.is_dirty && table.is_stored);
+        let query = "insert into t2(b1, b2) values (3, 'ccc');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.database.tables.get("t2").unwrap().rows.len(), 1);
+
+        println!("a truncated table in storage is still removed from storage");
+        Parser::new("drop table t2;").unwrap().parse(&mut sql).unwrap();
+        assert!(sql.database.tables.get("t2").unwrap().is_delete);
+
+        println!("a stored table is marked deleted, and doesn't exist anymore");
+        let table = sql.database.tables.get_mut("t1").unwrap();
+        table.is_dirty = false;
+        table.is_stored = true;
+        Parser::new("drop table t1;").unwrap().parse(&mut sql).unwrap();
+        assert!(sql.database.tables.get("t1").unwrap().is_delete);
+        assert!(Parser::new("select a1 from t1;").unwrap().parse(&mut sql).is_err());
+        assert!(Parser::new("insert into t1(a1, a2, a3) values (6, 'ccc', 2.6);")
+            .unwrap()
+            .parse(&mut sql)
+            .is_err());
+        assert!(Parser::new("truncate table t1;").unwrap().parse(&mut sql).is_err());
+        Parser::new("drop table if exists t1;")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+
+        println!("create the table again");
+        let query = "create table if not exists t1 (a1 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "create table if not exists t1 (a1 int, a2 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let table = sql.database.tables.get("t1").unwrap();
+        assert!(!table.is_delete && table.is_dirty && table.is_stored);
+        assert_eq!(table.field_order, vec!["a1".to_string()]);
+
+        println!("drop the current database");
+        Parser::new("drop database db11;").unwrap().parse(&mut sql).unwrap();
+        assert!(sql.database.is_delete);
+        assert!(Parser::new("select a1 from t1;").unwrap().parse(&mut sql).is_err());
+        assert!(Parser::new("drop database db11;").unwrap().parse(&mut sql).is_err());
+        Parser::new("drop database if exists db11;")
+            .unwrap()
+            .parse(&mut sql)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_show_describe() {
+        let mut sql = fake_sql();
+
+        let query =
+            "create table t2 (b1 int primary key, b2 char(7) not null unique, b3 int default 3 references t2(b1));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        Parser::new("show tables;").unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"table\"],\"rows\":[[\"t1\"],[\"t2\"]]}".to_string()
+        );
+
+        Parser::new("describe t2;").unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"field\",\"type\",\"null\",\"key\",\"default\"],\"rows\":[\
+             [\"b1\",\"int\",\"no\",\"pri\",\"null\"],\
+             [\"b2\",\"char(7)\",\"no\",\"uni\",\"null\"],\
+             [\"b3\",\"int\",\"yes\",\"fk\",\"3\"]]}"
+                .to_string()
+        );
+        let describe = sql.result_json.clone();
+        Parser::new("show columns from t2;").unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.result_json, describe);
+        assert!(Parser::new("describe t3;").unwrap().parse(&mut sql).is_err());
+
+        println!("a dropped table is not shown");
+        Parser::new("drop table t2;").unwrap().parse(&mut sql).unwrap();
+        Parser::new("show tables;").unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"table\"],\"rows\":[[\"t1\"]]}".to_string()
+        );
+        assert!(Parser::new("desc t2;").unwrap().parse(&mut sql).is_err());
+
+        println!("the current database is shown before it is stored");
+        Parser::new("show databases;").unwrap().parse(&mut sql).unwrap();
+        assert!(sql.result_json.contains("[\"db11\"]"));
+    }
+
+    #[test]
+    fn test_update_where() {
+        let mut sql = fake_sql();
+
+        let query = "update t1 set a2 = 'ccc', a3 = 9.9 where a1 > 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1, a2, a3 from t1 where a2 = 'ccc';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a3\"],\"rows\":[[\"4\",\"ccc\",\"9.9\"],[\"5\",\"ccc\",\"9.9\"]]}"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_delete_where() {
+        let mut sql = fake_sql();
+
+        let query = "delete from t1 where a2 = 'aaa' or a3 > 2.4;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1, a2, a3 from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a3\"],\"rows\":[[\"3\",\"bbb\",\"2.3\"],[\"4\",\"bbb\",\"2.4\"]]}"
+                .to_string()
+        );
+
+        // deleted rows won't be updated
+        let query = "update t1 set a2 = 'ccc';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let table = sql.database.tables.get("t1").unwrap();
+        assert_eq!(table.rows[0].data.get("a2").unwrap(), "aaa");
+        assert_eq!(table.rows[2].data.get("a2").unwrap(), "ccc");
+
+        let query = "delete from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1, a2, a3 from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\",\"a3\"],\"rows\":[]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_update_all_rows() {
+        let mut sql = fake_sql();
+
+        let query = "update t1 set a2 = 'zzz';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1, a2 from t1 where a2 = 'zzz';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\"],\"rows\":[[\"1\",\"zzz\"],[\"2\",\"zzz\"],[\"3\",\"zzz\"],[\"4\",\"zzz\"],[\"5\",\"zzz\"]]}"
+                .to_string()
+        );
+
+        let query = "update t1 set a4 = 1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("the values are evaluated on each row");
+        let query = "update t1 set a1 = a1 * 10, a3 = a3 + a1 where a1 < 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select a1, a3 from t1 where a1 >= 10;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a3\"],\"rows\":[[\"10\",\"3.1\"],[\"20\",\"4.2\"]]}".to_string()
+        );
+
+        println!("an identifier which isn't a field is not a value");
+        let query = "update t1 set a2 = a9;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "update t1 set a1 = a3;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        assert_eq!(sql.database.tables["t1"].rows[0].data["a2"], "zzz");
+    }
+
+    #[test]
+    fn test_select_order_by() {
+        let mut sql = fake_sql();
+
+        let query = "insert into t1(a1, a2, a3) values (10, 'aaa', 1.5), (9, 'ccc', 1.5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        // without order by, rows are in the inserted order
+        let query = "select a1 from t1 where a3 < 2.0;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"10\"],[\"9\"]]}".to_string()
+        );
+
+        // numbers are sorted as numbers
+        let query = "select a1 from t1 order by a1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"2\"],[\"3\"],[\"4\"],[\"5\"],[\"9\"],[\"10\"]]}".to_string()
+        );
+
+        // sort by a field not selected
+        let query = "select a1 from t1 where a1 > 3 order by a3 asc, a2 desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"9\"],[\"10\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 order by a4;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_select_group_by() {
+        let mut sql = fake_sql();
+
+        let query = "insert into t1(a1, a2, a3) values (6, 'ccc', 2.5), (7, 'bbb', null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a2, count(*), count(a3), count(distinct a3), sum(a1), avg(a1), max(a3), min(a3) from t1 group by a2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\",\"count(*)\",\"count(a3)\",\"count(distinct a3)\",\"sum(a1)\",\"avg(a1)\",\"max(a3)\",\"min(a3)\"],\"rows\":[[\"aaa\",\"2\",\"2\",\"2\",\"3\",\"1.5\",\"2.2\",\"2.1\"],[\"bbb\",\"4\",\"3\",\"3\",\"19\",\"4.75\",\"2.5\",\"2.3\"],[\"ccc\",\"1\",\"1\",\"1\",\"6\",\"6\",\"2.5\",\"2.5\"]]}".to_string()
+        );
+
+        let query = "select count(distinct a3), sum(a3) from t1 where a1 > 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(distinct a3)\",\"sum(a3)\"],\"rows\":[[\"2\",\"7.4\"]]}".to_string()
+        );
+
+        println!("having on aggregations, which may be not selected");
+        let query = "select a2 from t1 group by a2 having count(*) > 1 and sum(a1) < 10 order by a2 desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\"],\"rows\":[[\"aaa\"]]}".to_string()
+        );
+
+        println!("order by an aggregation");
+        let query = "select a2, max(a1) from t1 group by a2 order by max(a1) desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\",\"max(a1)\"],\"rows\":[[\"bbb\",\"7\"],[\"ccc\",\"6\"],[\"aaa\",\"2\"]]}".to_string()
+        );
+
+        println!("aggregate on no row");
+        let query = "select count(*), sum(a1), max(a2) from t1 where a1 > 100;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(*)\",\"sum(a1)\",\"max(a2)\"],\"rows\":[[\"0\",\"null\",\"null\"]]}".to_string()
+        );
+        let query = "select a2, count(*) from t1 where a1 > 100 group by a2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\",\"count(*)\"],\"rows\":[]}".to_string()
+        );
+
+        println!("semantic errors");
+        let query = "select a1, count(*) from t1 group by a2;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select sum(a2) from t1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select a1 from t1 having a1 > 1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_select_expression_predicate() {
+        let mut sql = fake_sql();
+
+        let query = "select a1 from t1 where a1 * 2 > a3 + 5;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"4\"],[\"5\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where length(a2) = 3 and a1 / 2 = 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"3\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where not abs(a1 - 3) > 1 and a2 <> 'a1';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"3\"],[\"4\"]]}".to_string()
+        );
+
+        let query = "update t1 set a2 = 'big' where a1 + a3 > 7;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select a1 from t1 where a2 = 'big';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.result_json, "{\"fields\":[\"a1\"],\"rows\":[[\"5\"]]}".to_string());
+
+        println!("type mismatched");
+        let query = "select a1 from t1 where a1 > a2;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select a1 from t1 where a1 / 0 > 1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select a1 from t1 where a4 > 1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_select_null() {
+        let mut sql = fake_sql();
+
+        let query = "insert into t1(a1, a2, a3) values (6, null, null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "update t1 set a3 = null where a1 = 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1, a2 from t1 where a3 is null;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\"],\"rows\":[[\"1\",\"aaa\"],[\"6\",\"null\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where a2 is not null and a3 + 1 is not null;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"3\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+
+        println!("a comparison with null is unknown, so is its negation");
+        let query = "select a1 from t1 where not a3 > 2.2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.result_json, "{\"fields\":[\"a1\"],\"rows\":[[\"2\"]]}".to_string());
+        let query = "select a1 from t1 where not (a3 > 2.2 and a1 > 5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"2\"],[\"3\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+        let query = "select a1 from t1 where not (a3 > 2.2 or a1 < 2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.result_json, "{\"fields\":[\"a1\"],\"rows\":[[\"2\"]]}".to_string());
+
+        println!("aggregations ignore null");
+        let query = "select count(*), count(a2), count(a3), min(a3) from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(*)\",\"count(a2)\",\"count(a3)\",\"min(a3)\"],\"rows\":[[\"6\",\"5\",\"4\",\"2.2\"]]}"
+                .to_string()
+        );
+
+        println!("a quoted null is a string");
+        let query = "insert into t1(a1, a2, a3) values (7, 'null', 2.7);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "update t1 set a2 = 'null' where a1 = 2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select a1 from t1 where a2 is not null and a2 = 'null';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"7\"]]}".to_string()
+        );
+
+        println!("a quoted null stays a string in derived tables, subqueries and groups");
+        let query = "select x.a1 from (select a1, a2 from t1) as x where x.a2 is null;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"x.a1\"],\"rows\":[[\"6\"]]}".to_string()
+        );
+        let query = "select a1 from t1 where a2 = (select a2 from t1 where a1 = 7);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"7\"]]}".to_string()
+        );
+        let query = "select count(*) from (select a2 from t1 group by a2) as y where y.a2 is null;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(*)\"],\"rows\":[[\"1\"]]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_select_like_in_between() {
+        let mut sql = fake_sql();
+
+        let query = "insert into t1(a1, a2, a3) values (6, 'a%c', null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a1 from t1 where a2 like 'a%' and a2 not like '%!%%' escape '!';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"2\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where a2 like '_b_' and a1 in (1, 3, 5, 7);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"3\"],[\"5\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where a3 between 2.2 and 2.4 or a1 not in (1, 2, 3, 4, 5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"3\"],[\"4\"],[\"6\"]]}".to_string()
+        );
+
+        println!("null is neither between nor not between");
+        let query = "select a1 from t1 where a3 not between 2.2 and 2.4;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"5\"]]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_select_subquery() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int, b2 char(7));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 'aaa'), (3, 'bbb'), (3, 'ccc'), (9, 'aaa');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("scalar subquery");
+        let query = "select a1 from t1 where a3 > (select avg(a3) from t1);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"4\"],[\"5\"]]}".to_string()
+        );
+        let query = "select a1, (select max(b1) from t2) from t1 where a1 = 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"(select max(b1) from t2)\"],\"rows\":[[\"1\",\"9\"]]}".to_string()
+        );
+        let query = "select a1 from t1 where a1 = (select b1 from t2);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+
+        println!("in and exists");
+        let query = "select a1 from t1 where a1 in (select b1 from t2 where b2 <> 'ccc');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"3\"]]}".to_string()
+        );
+        let query =
+            "select a1 from t1 where a1 not in (select b1 from t2) and exists (select b1 from t2 where b1 > 5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+
+        println!("correlated subquery");
+        let query = "select a1 from t1 where exists (select b1 from t2 where b2 = a2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"2\"],[\"3\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+        let query = "select a1, (select count(*) from t2 where b1 = a1) from t1 where not exists (select b1 from t2 where b1 = a1);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"(select count(*) from t2 where b1 = a1)\"],\"rows\":[[\"2\",\"0\"],[\"4\",\"0\"],[\"5\",\"0\"]]}"
+                .to_string()
+        );
+
+        println!("the error of an uncorrelated subquery is raised, even if there is no row");
+        let query = "create table t4 (c1 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        for query in [
+            "select c1 from t4 where c1 in (select a9 from t1);",
+            "select c1, (select a9 from t1) from t4;",
+            "select c1 from t4 where c1 = (select a1 from t1);",
+        ]
+        .iter()
+        {
+            assert!(Parser::new(query).unwrap().parse(&mut sql).is_err(), "{}", query);
+        }
+
+        println!("derived table");
+        let query = "select x.a1, b2 from (select a1 from t1 where a1 > 1) as x inner join t2 on x.a1 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"x.a1\",\"b2\"],\"rows\":[[\"3\",\"bbb\"],[\"3\",\"ccc\"]]}".to_string()
+        );
+        let query = "select count(*) from (select a2 from t1 group by a2) x;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(*)\"],\"rows\":[[\"2\"]]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_select_star_alias() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b2 char(7), b1 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 'xxx'), (3, 'yyy');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("fields in the order of the schema");
+        let query = "select * from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b2\",\"b1\"],\"rows\":[[\"xxx\",\"1\"],[\"yyy\",\"3\"]]}".to_string()
+        );
+        let query = "select t2.*, a1 from t1 inner join t2 on a1 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b2\",\"b1\",\"a1\"],\"rows\":[[\"xxx\",\"1\",\"1\"],[\"yyy\",\"3\",\"3\"]]}".to_string()
+        );
+
+        println!("aliases of fields");
+        let query = "select a1 as x, a2 y, count(*) as n from t1 group by a1, a2 order by x desc limit 2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"x\",\"y\",\"n\"],\"rows\":[[\"5\",\"bbb\",\"1\"],[\"4\",\"bbb\",\"1\"]]}".to_string()
+        );
+
+        println!("aliases of tables");
+        let query = "select p.a1, q.a1 from t1 as p inner join t1 q on p.a1 + 1 = q.a1 where q.a2 = 'bbb';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"p.a1\",\"q.a1\"],\"rows\":[[\"2\",\"3\"],[\"3\",\"4\"],[\"4\",\"5\"]]}".to_string()
+        );
+        let query = "select * from t1 p, t1 q where p.a1 = 1 and q.a1 = 2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"p.a1\",\"p.a2\",\"p.a3\",\"q.a1\",\"q.a2\",\"q.a3\"],\"rows\":[[\"1\",\"aaa\",\"2.1\",\"2\",\"aaa\",\"2.2\"]]}"
+                .to_string()
+        );
+        let query = "select d.* from (select a1 as x, a3 from t1 where a1 > 3) as d;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"x\",\"a3\"],\"rows\":[[\"4\",\"2.4\"],[\"5\",\"2.5\"]]}".to_string()
+        );
+        let query = "select t1.a1 from t1 as p;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select t3.* from t1;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_select_expression() {
+        let mut sql = fake_sql();
+
+        let query = "select a1 * 2 + 1 as x, upper(a2), a2 || '-' || a1 as y from t1 where a1 < 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"x\",\"upper(a2)\",\"y\"],\"rows\":[[\"3\",\"AAA\",\"aaa-1\"],[\"5\",\"AAA\",\"aaa-2\"]]}"
+                .to_string()
+        );
+
+        println!("functions");
+        let query = "select substr(a2, 2), length(a2), round(a3 * a1, 1), abs(2 - a1) from t1 where a1 = 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"substr(a2, 2)\",\"length(a2)\",\"round(a3 * a1, 1)\",\"abs(2 - a1)\"],\"rows\":[[\"bb\",\"3\",\"6.9\",\"1\"]]}"
+                .to_string()
+        );
+        let query = "insert into t1(a1, a2, a3) values (6, null, null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select coalesce(a3, 0), nullif(a2, 'aaa'), trim('  x ') || a2 from t1 where a1 = 1 or a1 = 6;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"coalesce(a3, 0)\",\"nullif(a2, 'aaa')\",\"trim('  x ') || a2\"],\"rows\":[[\"2.1\",\"null\",\"xaaa\"],[\"0\",\"null\",\"null\"]]}"
+                .to_string()
+        );
+
+        println!("case, sorted by the alias");
+        let query = "select a1, case when a1 < 2 then 'low' when a1 < 4 then 'mid' else 'high' end as level from t1 where a1 < 6 order by level, a1 desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"level\"],\"rows\":[[\"5\",\"high\"],[\"4\",\"high\"],[\"1\",\"low\"],[\"3\",\"mid\"],[\"2\",\"mid\"]]}"
+                .to_string()
+        );
+        let query = "select case a2 when 'aaa' then 1 end as k, count(*) * 10 as n from t1 group by a2 order by n;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"k\",\"n\"],\"rows\":[[\"null\",\"10\"],[\"1\",\"20\"],[\"null\",\"30\"]]}".to_string()
+        );
+
+        println!("errors");
+        for query in [
+            "select a1 + a2 from t1;",
+            "select upper(a1) from t1;",
+            "select round(a3, 1, 1) from t1;",
+            "select a1 + a9 from t1;",
+        ] {
+            assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        }
+    }
+
+    #[test]
+    fn test_select_set_operations() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int, b2 char(7), b3 double);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2, b3) values (1, 'aaa', 1.5), (3, 'ccc', null), (9, 'ccc', 2.5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select a2 from t1 union select b2 from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\"],\"rows\":[[\"aaa\"],[\"bbb\"],[\"ccc\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 where a1 < 3 union all select b1 from t2 order by a1 desc limit 3 offset 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"3\"],[\"2\"],[\"1\"]]}".to_string()
+        );
+
+        let query = "select a1, a2 from t1 intersect select b1, b2 from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"a2\"],\"rows\":[[\"1\",\"aaa\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 except select b1 from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"4\"],[\"5\"]]}".to_string()
+        );
+
+        println!("an integer and a decimal are sorted as decimals");
+        let query = "select a1 from t1 where a1 < 3 union select b3 from t2 order by a1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"null\"],[\"1\"],[\"1.5\"],[\"2\"],[\"2.5\"]]}".to_string()
+        );
+
+        println!("incompatible queries");
+        let query = "select a1, a2 from t1 union select b1 from t2;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "select a1 from t1 union select b2 from t2;";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_select_distinct_top() {
+        let mut sql = fake_sql();
+
+        let query = "select distinct a2 from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\"],\"rows\":[[\"aaa\"],[\"bbb\"]]}".to_string()
+        );
+
+        let query = "select top 2 a1 from t1 order by a1 desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"5\"],[\"4\"]]}".to_string()
+        );
+
+        // 5 rows * 50% is rounded up to 3 rows
+        let query = "select top 50 percent a1 from t1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"1\"],[\"2\"],[\"3\"]]}".to_string()
+        );
+
+        // distinct is applied before top
+        let query = "select distinct top 1 a2 from t1 order by a1 desc;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a2\"],\"rows\":[[\"bbb\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 order by a1 limit 2 offset 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\"],\"rows\":[[\"2\"],[\"3\"]]}".to_string()
+        );
+
+        let query = "select a1 from t1 limit 2 offset 10;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(sql.result_json, "{\"fields\":[\"a1\"],\"rows\":[]}".to_string());
+    }
+
+    #[test]
+    fn test_select_join() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int, b2 char(7));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 'xxx'), (3, 'yyy'), (9, 'zzz');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select t1.a1, b2 from t1 inner join t2 on t1.a1 = t2.b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"t1.a1\",\"b2\"],\"rows\":[[\"1\",\"xxx\"],[\"3\",\"yyy\"]]}".to_string()
+        );
+
+        let query = "select a1, b2 from t1 left join t2 on a1 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"b2\"],\"rows\":[[\"1\",\"xxx\"],[\"2\",\"null\"],[\"3\",\"yyy\"],[\"4\",\"null\"],[\"5\",\"null\"]]}".to_string()
+        );
+
+        let query = "select a1, b1 from t1 right join t2 on a1 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"b1\"],\"rows\":[[\"1\",\"1\"],[\"3\",\"3\"],[\"null\",\"9\"]]}".to_string()
+        );
+
+        let query = "select a1, b1 from t1 full outer join t2 on a1 = b1 and a1 > 1 where a1 < 4 or b1 > 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"b1\"],\"rows\":[[\"1\",\"null\"],[\"2\",\"null\"],[\"3\",\"3\"],[\"null\",\"9\"]]}"
+                .to_string()
+        );
+
+        println!("comma joins with where");
+        let query = "select a1, b2 from t1, t2 where a1 = b1 and b2 = 'yyy';";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"a1\",\"b2\"],\"rows\":[[\"3\",\"yyy\"]]}".to_string()
+        );
+        let query = "select count(*) from t1, t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"count(*)\"],\"rows\":[[\"15\"]]}".to_string()
+        );
+
+        println!("ambiguous field");
+        let query = "create table t3 (a1 int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t3(a1) values (2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        for query in [
+            "select a1 from t1 inner join t3 on t1.a1 = t3.a1;",
+            "select a1 from t1, t3 where t1.a1 = t3.a1;",
+            "select t1.a1 from t1, t3 where a1 = 2;",
+            "select a2 from t1 inner join t3 on a1 = 2;",
+        ]
+        .iter()
+        {
+            match Parser::new(query).unwrap().parse(&mut sql) {
+                Err(ParserError::SQLError(SQLError::SemanticError(ref e))) if e == "ambiguous column a1" => {}
+                result => panic!("{}: {:?}", query, result),
+            }
+        }
+        // a join condition only refers to the tables joined before
+        let query = "select b2 from t1 inner join t2 on a1 = b1 inner join t3 on t3.a1 = b1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select t3.a1, a2 from t1 inner join t3 on t1.a1 = t3.a1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"t3.a1\",\"a2\"],\"rows\":[[\"2\",\"aaa\"]]}".to_string()
+        );
+    }
+
+    fn is_foreign_key_violation(result: Result<(), ParserError>) -> bool {
+        matches!(
+            result,
+            Err(ParserError::SQLError(SQLError::CausedByTable(
+                TableError::ForeignKeyViolation(_)
+            )))
+        )
+    }
+
+    #[test]
+    fn test_insert_rollback() {
+        let mut sql = fake_sql();
+
+        let query = "create table p1 (id int primary key, v int);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("a duplicated key drops the whole insert");
+        let query = "insert into p1(id, v) values (1, 1), (2, 2), (1, 3);";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        assert_eq!(sql.database.tables.get("p1").unwrap().rows.len(), 0);
+
+        println!("the keys of the dropped rows could be inserted again");
+        let query = "insert into p1(id, v) values (2, 2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "select id, v from p1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"id\",\"v\"],\"rows\":[[\"2\",\"2\"]]}".to_string()
+        );
+    }
+
+    #[test]
+    fn test_foreign_key() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int primary key, b2 char(7));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "create table t3 (c1 int, c2 int references t2(b1));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 'aaa'), (2, 'bbb'), (3, 'ccc');";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t3(c1, c2) values (10, 1), (20, 2), (30, null);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("referring to nothing, and the whole insert is dropped");
+        let query = "insert into t3(c1, c2) values (40, 3), (50, 9);";
+        assert!(is_foreign_key_violation(Parser::new(query).unwrap().parse(&mut sql)));
+        assert_eq!(sql.database.tables.get("t3").unwrap().rows.len(), 3);
+
+        println!("update the foreign key");
+        let query = "update t3 set c2 = 9 where c1 = 10;";
+        assert!(is_foreign_key_violation(Parser::new(query).unwrap().parse(&mut sql)));
+        let query = "update t3 set c2 = 3 where c1 = 10;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("the referred rows could not be deleted or modified");
+        let query = "delete from t2 where b1 = 2;";
+        assert!(is_foreign_key_violation(Parser::new(query).unwrap().parse(&mut sql)));
+        let query = "update t2 set b1 = 7 where b1 = 3;";
+        assert!(is_foreign_key_violation(Parser::new(query).unwrap().parse(&mut sql)));
+        let query = "update t2 set b2 = 'zzz' where b1 = 3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "delete from t2 where b1 = 1;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select b1, b2 from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b1\",\"b2\"],\"rows\":[[\"2\",\"bbb\"],[\"3\",\"zzz\"]]}".to_string()
+        );
+
+        println!("the reference should be a primary key or unique");
+        let query = "create table t4 (d1 int references t2(b2));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        let query = "create table t4 (d1 int references t9(b1));";
+        assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+    }
+
+    #[test]
+    fn test_keys_of_numbers() {
+        let mut sql = fake_sql();
+
+        let query = "create table t2 (b1 int primary key, b2 double unique);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "create table t3 (c1 int references t2(b1));";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "insert into t2(b1, b2) values (1, 1.5), (2, 2.5);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        println!("the same number in other forms is the same key");
+        for query in [
+            "insert into t2(b1, b2) values (01, 3.5);",
+            "insert into t2(b1, b2) values (+1, 3.5);",
+            "insert into t2(b1, b2) values (3, 1.50);",
+            "insert into t2(b1, b2) values (3, +2.5);",
+            "update t2 set b1 = 02 where b1 = 1;",
+            "update t2 set b2 = 02.50 where b1 = 1;",
+        ] {
+            assert!(Parser::new(query).unwrap().parse(&mut sql).is_err());
+        }
+
+        println!("the foreign key refers to the same number");
+        let query = "insert into t3(c1) values (01), (+2);";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        let query = "update t2 set b2 = 03.0 where b1 = 2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+
+        let query = "select * from t2;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"b1\",\"b2\"],\"rows\":[[\"1\",\"1.5\"],[\"2\",\"3\"]]}".to_string()
+        );
+        let query = "select * from t3;";
+        Parser::new(query).unwrap().parse(&mut sql).unwrap();
+        assert_eq!(
+            sql.result_json,
+            "{\"fields\":[\"c1\"],\"rows\":[[\"1\"],[\"2\"]]}".to_string()
+        );
+    }
 }
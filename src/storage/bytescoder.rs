@@ -138,34 +138,101 @@ impl BytesCoder {
         Ok(s)
     }
 
+    /// encode an attribute into an index key, whose bytes compare in the same order as the values
+    ///
+    /// The sign bit of an int is flipped. A float or double is in the total order of IEEE 754, where all
+    /// the bits of a negative number are flipped and only the sign bit of a positive one is, and `-0` is
+    /// stored as `0` so that they are the same key. A string is padded with zeros as in a row.
+    pub fn attr_to_key(datatype: &DataType, str_val: &str) -> Result<Vec<u8>, BytesCoderError> {
+        match datatype {
+            DataType::Double => Ok(BytesCoder::double_to_key(str_val.parse::<f64>()?)),
+            DataType::Float => Ok(BytesCoder::float_to_key(str_val.parse::<f32>()?)),
+            DataType::Int => Ok(BytesCoder::int_to_key(str_val.parse::<i32>()?)),
+            DataType::Char(_) | DataType::Varchar(_) | DataType::Url => BytesCoder::attr_to_bytes(datatype, str_val),
+        }
+    }
+
+    pub fn int_to_key(val: i32) -> Vec<u8> {
+        ((val as u32) ^ (1 << 31)).to_be_bytes().to_vec()
+    }
+
+    pub fn float_to_key(val: f32) -> Vec<u8> {
+        let bits = if val == 0.0 { 0 } else { val.to_bits() };
+        let bits = if bits >> 31 == 1 { !bits } else { bits ^ (1 << 31) };
+        bits.to_be_bytes().to_vec()
+    }
+
+    pub fn double_to_key(val: f64) -> Vec<u8> {
+        let bits = if val == 0.0 { 0 } else { val.to_bits() };
+        let bits = if bits >> 63 == 1 { !bits } else { bits ^ (1 << 63) };
+        bits.to_be_bytes().to_vec()
+    }
+
+    /// the number of bytes of the null bitmap for `num_attrs` attributes, a bit for each attribute
+    pub fn null_bitmap_size(num_attrs: usize) -> u32 {
+        num_attrs.div_ceil(8) as u32
+    }
+
+    /// the offset of the null bitmap, which is at the tail of a row
+    ///
+    /// A table created before the null bitmap has no room for it, so it can't store nulls.
+    fn null_bitmap_offset(tablemeta: &TableMeta) -> Option<usize> {
+        let attrs_end = tablemeta.attr_offset_ranges.last().map_or(0, |range| range[1]);
+        if tablemeta.row_length > attrs_end {
+            Some(attrs_end as usize)
+        } else {
+            None
+        }
+    }
+
     pub fn row_to_bytes(tablemeta: &TableMeta, row: &Row) -> Result<Vec<u8>, BytesCoderError> {
+        let bitmap_offset = BytesCoder::null_bitmap_offset(tablemeta);
+        let mut null_bitmap = vec![0; BytesCoder::null_bitmap_size(tablemeta.attrs_order.len() - 1) as usize];
+
         // set `__valid__` to 1
         let mut row_bytes = vec![1];
-        for attr in tablemeta.attrs_order[1..].iter() {
-            let attr_bytes = BytesCoder::attr_to_bytes(
-                &tablemeta.attrs[attr].datatype,
-                row.data.get(attr).ok_or_else(|| BytesCoderError::AttrNotExists)?,
-            )?;
-            row_bytes.extend_from_slice(&attr_bytes);
+        for (idx, attr) in tablemeta.attrs_order[1..].iter().enumerate() {
+            match (row.data.get(attr), bitmap_offset) {
+                (Some(value), _) => {
+                    let attr_bytes = BytesCoder::attr_to_bytes(&tablemeta.attrs[attr].datatype, value)?;
+                    row_bytes.extend_from_slice(&attr_bytes);
+                }
+                // a null is padded, and marked in the null bitmap
+                (None, Some(_)) => {
+                    let range = &tablemeta.attr_offset_ranges[idx + 1];
+                    row_bytes.extend_from_slice(&vec![0; (range[1] - range[0]) as usize]);
+                    null_bitmap[idx / 8] |= 1 << (idx % 8);
+                }
+                (None, None) => return Err(BytesCoderError::AttrNotExists),
+            }
+        }
+        if bitmap_offset.is_some() {
+            row_bytes.extend_from_slice(&null_bitmap);
         }
 
         Ok(row_bytes)
     }
 
     pub fn bytes_to_row(tablemeta: &TableMeta, bytes: &Vec<u8>) -> Result<Row, BytesCoderError> {
-        let mut attr_vals: Vec<String> = vec![];
+        let null_bitmap = match BytesCoder::null_bitmap_offset(tablemeta) {
+            Some(offset) => &bytes[offset..tablemeta.row_length as usize],
+            None => &[],
+        };
+
+        let mut new_row = Row::new();
         for (idx, attr) in tablemeta.attrs_order[1..].iter().enumerate() {
+            // a null is absent from the row
+            if null_bitmap
+                .get(idx / 8)
+                .is_some_and(|byte| byte & (1 << (idx % 8)) != 0)
+            {
+                continue;
+            }
             let attr_bytes = bytes
                 [tablemeta.attr_offset_ranges[idx + 1][0] as usize..tablemeta.attr_offset_ranges[idx + 1][1] as usize]
                 .to_vec();
             let attr_val = BytesCoder::bytes_to_attr(&tablemeta.attrs[attr].datatype, &attr_bytes)?;
-            attr_vals.push(attr_val);
-        }
-        let mut new_row = Row::new();
-        for i in 0..attr_vals.len() {
-            new_row
-                .data
-                .insert(tablemeta.attrs_order[i + 1].clone(), attr_vals[i].clone());
+            new_row.data.insert(attr.clone(), attr_val);
         }
 
         Ok(new_row)
@@ -230,6 +297,57 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_attr_key_order() {
+        let cases = [
+            (
+                DataType::Int,
+                vec!["-2147483648", "-300", "-1", "0", "1", "256", "2147483647"],
+            ),
+            (
+                DataType::Float,
+                vec![
+                    "-inf", "-1000.5", "-2.5", "-0.001", "0", "0.001", "2.5", "3", "1000.5", "inf",
+                ],
+            ),
+            (
+                DataType::Double,
+                vec![
+                    "-inf",
+                    "-123456.789",
+                    "-2.5",
+                    "-0.5",
+                    "0",
+                    "0.5",
+                    "2.5",
+                    "3",
+                    "123456.789",
+                    "inf",
+                ],
+            ),
+            (DataType::Varchar(5), vec!["", "a", "ab", "abc", "b", "ba", "zzzzz"]),
+        ];
+        for (datatype, values) in cases.iter() {
+            let keys: Vec<Vec<u8>> = values
+                .iter()
+                .map(|val| BytesCoder::attr_to_key(datatype, val).unwrap())
+                .collect();
+            for i in 1..keys.len() {
+                assert!(keys[i - 1] < keys[i], "{} < {}", values[i - 1], values[i]);
+            }
+        }
+
+        // -0 is the same key as 0
+        assert_eq!(
+            BytesCoder::attr_to_key(&DataType::Double, "-0").unwrap(),
+            BytesCoder::attr_to_key(&DataType::Double, "0").unwrap()
+        );
+        assert_eq!(
+            BytesCoder::attr_to_key(&DataType::Char(2), "abc").unwrap_err(),
+            BytesCoderError::StringLength
+        );
+    }
+
     #[test]
     pub fn test_row_encode_decode() {
         let mut aff_table_meta = TableMeta {
@@ -242,6 +360,9 @@ mod tests {
             foreign_key: vec![],
             reference_table: None,
             reference_attr: None,
+            unique: vec![],
+            checks: vec![],
+            named_constraints: HashMap::new(),
             attr_offset_ranges: vec![vec![0, 1], vec![1, 5], vec![5, 55], vec![55, 95], vec![95, 115]],
             row_length: 115,
             // ignore attrs checking
@@ -252,7 +373,9 @@ mod tests {
                 "AffName".to_string(),
                 "AffPhoneNum".to_string(),
             ],
+            fields_order: vec![],
             attrs: HashMap::new(),
+            indexes: vec![],
         };
 
         aff_table_meta.attrs.insert(
@@ -314,5 +437,20 @@ mod tests {
         for (attr, val) in row.data.iter() {
             assert_eq!(val.clone(), reconstructed_row.data[attr]);
         }
+
+        println!("null can't be stored without the null bitmap");
+        row.data.remove("AffPhoneNum");
+        assert_eq!(
+            BytesCoder::row_to_bytes(&aff_table_meta, &row).unwrap_err(),
+            BytesCoderError::AttrNotExists
+        );
+
+        println!("null is stored in the null bitmap");
+        aff_table_meta.row_length += BytesCoder::null_bitmap_size(4);
+        let row_bytes = BytesCoder::row_to_bytes(&aff_table_meta, &row).unwrap();
+        assert_eq!(row_bytes.len(), aff_table_meta.row_length as usize);
+        assert_eq!(row_bytes[115], 0b1000);
+        let reconstructed_row = BytesCoder::bytes_to_row(&aff_table_meta, &row_bytes).unwrap();
+        assert_eq!(reconstructed_row.data, row.data);
     }
 }
@@ -1,7 +1,9 @@
 use crate::component::datatype::DataType;
 use crate::component::field::Field;
+use crate::component::table::Constraint;
 use crate::component::table::Row;
 use crate::component::table::Table;
+use crate::sql::query::Node;
 use crate::storage::bytescoder;
 use crate::storage::file::File;
 use crate::storage::index::Index;
@@ -61,10 +63,50 @@ pub struct TableMeta {
     pub foreign_key: Vec<String>,
     pub reference_table: Option<String>,
     pub reference_attr: Option<String>,
+    #[serde(default)]
+    pub unique: Vec<Vec<String>>,
+    #[serde(default)]
+    pub checks: Vec<Node>,
+    #[serde(default)]
+    pub named_constraints: HashMap<String, Constraint>,
     pub row_length: u32,
     pub attrs: HashMap<String, Field>,
     pub attrs_order: Vec<String>,
+    #[serde(default)]
+    pub fields_order: Vec<String>, // in the order of the schema, while `attrs_order` is of the storage
     pub attr_offset_ranges: Vec<Vec<u32>>,
+    #[serde(default)]
+    pub indexes: Vec<IndexMeta>, // the indexes created by users, besides the one of the primary key
+}
+
+/// an index on an attribute of a table, whose keys are unique if it is `is_unique`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct IndexMeta {
+    pub name: String,
+    pub attr: String,
+    pub is_unique: bool,
+}
+
+impl IndexMeta {
+    pub fn new(name: &str, attr: &str, is_unique: bool) -> IndexMeta {
+        IndexMeta {
+            name: name.to_string(),
+            attr: attr.to_string(),
+            is_unique,
+        }
+    }
+}
+
+impl TableMeta {
+    /// all the indexes of the table, where the index of a primary key of one attribute is named after it
+    pub fn get_indexes(&self) -> Vec<IndexMeta> {
+        let mut indexes = vec![];
+        if self.primary_key.len() == 1 {
+            indexes.push(IndexMeta::new(&self.primary_key[0], &self.primary_key[0], true));
+        }
+        indexes.extend(self.indexes.iter().cloned());
+        indexes
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -94,6 +136,7 @@ pub enum DiskError {
     BytesError,
     DuplicatedKey,
     IndexKeyNotFound,
+    IndexNotExists,
 }
 
 impl From<io::Error> for DiskError {
@@ -153,6 +196,7 @@ impl fmt::Display for DiskError {
             DiskError::IndexKeyNotFound => {
                 write!(f, "Attempting to access or delete a key which does not exist in index.")
             }
+            DiskError::IndexNotExists => write!(f, "The table does not have specified index."),
         }
     }
 }
@@ -238,11 +282,31 @@ impl DiskInterface {
         Ok(File::drop_table(username, db_name, table_name, base_path)?)
     }
 
+    pub fn alter_table(
+        username: &str,
+        db_name: &str,
+        table: &Table,
+        file_base_path: Option<&str>,
+    ) -> Result<(), DiskError> {
+        let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
+        File::alter_table(username, db_name, table, base_path)
+    }
+
+    pub fn alter_indexes(
+        username: &str,
+        db_name: &str,
+        table: &Table,
+        file_base_path: Option<&str>,
+    ) -> Result<(), DiskError> {
+        let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
+        File::alter_indexes(username, db_name, table, base_path)
+    }
+
     pub fn append_rows(
         username: &str,
         db_name: &str,
         table_name: &str,
-        rows: &Vec<Row>,
+        rows: &[Row],
         file_base_path: Option<&str>,
     ) -> Result<(), DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
@@ -253,7 +317,7 @@ impl DiskInterface {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
+        row_range: &[u32],
         file_base_path: Option<&str>,
     ) -> Result<Vec<Row>, DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
@@ -264,7 +328,7 @@ impl DiskInterface {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
+        row_range: &[u32],
         file_base_path: Option<&str>,
     ) -> Result<(), DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
@@ -275,8 +339,8 @@ impl DiskInterface {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
-        new_rows: &Vec<Row>,
+        row_range: &[u32],
+        new_rows: &[Row],
         file_base_path: Option<&str>,
     ) -> Result<(), DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
@@ -295,6 +359,17 @@ impl DiskInterface {
         Ok(File::get_num_rows(username, db_name, table_name, base_path)?)
     }
 
+    pub fn get_valid_flags(
+        username: &str,
+        db_name: &str,
+        table_name: &str,
+        row_range: &[u32],
+        file_base_path: Option<&str>,
+    ) -> Result<Vec<bool>, DiskError> {
+        let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
+        File::get_valid_flags(username, db_name, table_name, row_range, base_path)
+    }
+
     pub fn storage_hierarchy_check(
         base_path: &str,
         username: Option<&str>,
@@ -390,6 +465,9 @@ impl DiskInterface {
             return Err(DiskError::TableNotExists);
         }
 
+        // complete or undo the migration of the table which was interrupted, before its files are used
+        File::recover_alter_table(username.unwrap(), db_name.unwrap(), table_name.unwrap(), base_path)?;
+
         // check if table bin exists
         let table_bin_path = format!("{}/{}.bin", db_path, table_name.unwrap());
         if !Path::new(&table_bin_path).exists() {
@@ -422,11 +500,12 @@ impl DiskInterface {
         username: &str,
         db_name: &str,
         table_name: &str,
+        index_name: &str,
         file_base_path: Option<&str>,
     ) -> Result<Index, DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
         let table_meta = DiskInterface::load_table_meta(username, db_name, table_name, Some(base_path))?;
-        let mut index = Index::new(table_meta)?;
+        let mut index = DiskInterface::new_index(table_meta, index_name)?;
         index.build_from_bin(base_path)?;
 
         Ok(index)
@@ -436,11 +515,12 @@ impl DiskInterface {
         username: &str,
         db_name: &str,
         table_name: &str,
+        index_name: &str,
         file_base_path: Option<&str>,
     ) -> Result<Index, DiskError> {
         let base_path = file_base_path.unwrap_or(dotenv!("FILE_BASE_PATH"));
         let table_meta = DiskInterface::load_table_meta(username, db_name, table_name, Some(base_path))?;
-        let mut index = Index::new(table_meta)?;
+        let mut index = DiskInterface::new_index(table_meta, index_name)?;
         index.load(base_path)?;
 
         Ok(index)
@@ -452,6 +532,15 @@ impl DiskInterface {
 
         Ok(())
     }
+
+    fn new_index(table_meta: TableMeta, index_name: &str) -> Result<Index, DiskError> {
+        let index_meta = table_meta
+            .get_indexes()
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or(DiskError::IndexNotExists)?;
+        Index::new(table_meta, index_meta)
+    }
 }
 
 // #[cfg(test)]
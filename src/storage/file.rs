@@ -2,7 +2,7 @@ use crate::component::table::Row;
 use crate::component::table::Table;
 use crate::storage::bytescoder::BytesCoder;
 use crate::storage::diskinterface::{
-    DbInfo, DbsJson, DiskError, DiskInterface, TableMeta, TablesJson, UsernameInfo, UsernamesJson,
+    DbInfo, DbsJson, DiskError, DiskInterface, IndexMeta, TableMeta, TablesJson, UsernameInfo, UsernamesJson,
 };
 use std::fs;
 use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
@@ -257,7 +257,46 @@ impl File {
             }
         }
 
-        // create new table json instance
+        let new_table_meta = File::build_table_meta(username, db_name, table);
+
+        // create corresponding bin for the table, which is empty
+        let table_bin_path = format!("{}/{}/{}/{}", base_path, username, db_name, new_table_meta.path_bin);
+        let mut table_bin_file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(table_bin_path)?;
+        table_bin_file.write_all("".as_bytes())?;
+
+        // perform equivalent operation on table tsv
+        if dotenv!("ENABLE_TSV") == "true" {
+            // create corresponding tsv for the table, with the title line
+            let table_tsv_path = format!("{}/{}/{}/{}", base_path, username, db_name, new_table_meta.path_tsv);
+            let mut table_tsv_file = fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(table_tsv_path)?;
+            table_tsv_file.write_all(new_table_meta.attrs_order.join("\t").as_bytes())?;
+        }
+
+        // insert the new table record into `tables.json`
+        tables_json.tables.push(new_table_meta);
+
+        // save `tables.json`
+        let mut tables_file = fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(tables_json_path)?;
+        tables_file.write_all(serde_json::to_string_pretty(&tables_json)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// build the metadata of the table, with the storing order and the layout of its rows
+    fn build_table_meta(username: &str, db_name: &str, table: &Table) -> TableMeta {
         let mut new_table_meta = TableMeta {
             name: table.name.to_string(),
             username: username.to_string(),
@@ -268,10 +307,15 @@ impl File {
             foreign_key: table.foreign_key.clone(),
             reference_table: table.reference_table.clone(),
             reference_attr: table.reference_attr.clone(),
+            unique: table.unique.clone(),
+            checks: table.checks.clone(),
+            named_constraints: table.named_constraints.clone(),
             row_length: 0,
             attrs_order: vec![],
+            fields_order: table.field_order.clone(),
             attrs: table.fields.clone(),
             attr_offset_ranges: vec![],
+            indexes: table.indexes.clone(),
         };
 
         // determine storing order of attrs in .tsv and .bin
@@ -304,42 +348,10 @@ impl File {
                 .push(vec![curr_offset, curr_offset + attr_size]);
             curr_offset += attr_size;
         }
-        new_table_meta.row_length = curr_offset;
-
-        // create corresponding bin for the table, which is empty
-        let table_bin_path = format!("{}/{}/{}/{}", base_path, username, db_name, new_table_meta.path_bin);
-        let mut table_bin_file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(table_bin_path)?;
-        table_bin_file.write_all("".as_bytes())?;
-
-        // perform equivalent operation on table tsv
-        if dotenv!("ENABLE_TSV") == "true" {
-            // create corresponding tsv for the table, with the title line
-            let table_tsv_path = format!("{}/{}/{}/{}", base_path, username, db_name, new_table_meta.path_tsv);
-            let mut table_tsv_file = fs::OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(table_tsv_path)?;
-            table_tsv_file.write_all(new_table_meta.attrs_order.join("\t").as_bytes())?;
-        }
+        // the null bitmap is at the tail of a row
+        new_table_meta.row_length = curr_offset + BytesCoder::null_bitmap_size(new_table_meta.attrs_order.len() - 1);
 
-        // insert the new table record into `tables.json`
-        tables_json.tables.push(new_table_meta);
-
-        // save `tables.json`
-        let mut tables_file = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(tables_json_path)?;
-        tables_file.write_all(serde_json::to_string_pretty(&tables_json)?.as_bytes())?;
-
-        Ok(())
+        new_table_meta
     }
 
     /// get the list of tables in a database
@@ -415,11 +427,22 @@ impl File {
             .tables
             .iter()
             .position(|table_meta| &table_meta.name == table_name);
-        match idx_to_remove {
+        let table_meta = match idx_to_remove {
             Some(idx) => tables_json.tables.remove(idx),
             None => return Err(DiskError::TableNotExists),
         };
 
+        // remove corresponding index files, so a table created again with the same name won't use them
+        for index in table_meta.get_indexes() {
+            let index_path = format!(
+                "{}/{}/{}/{}_{}.idx",
+                base_path, username, db_name, table_name, index.name
+            );
+            if Path::new(&index_path).exists() {
+                fs::remove_file(&index_path)?;
+            }
+        }
+
         // remove corresponding bin file
         let table_bin_path = format!("{}/{}/{}/{}.bin", base_path, username, db_name, table_name);
         if Path::new(&table_bin_path).exists() {
@@ -446,11 +469,215 @@ impl File {
         Ok(())
     }
 
+    /// migrate the table to its new schema, such as after `alter table`
+    ///
+    /// Each row in storage is re-encoded in the new layout: the dropped attributes are removed, and
+    /// the added ones take their default values or null. A deleted row stays deleted at the same row
+    /// id. The new bin, tsv and `tables.json` are written to temporary files first, and then renamed
+    /// over the old ones, where the migration is committed by renaming `tables.json`, so a failed
+    /// migration leaves the table unchanged or is completed by `recover_alter_table`. The indexes are
+    /// removed after the commit since their keys are in the old layout, and could be built again from the
+    /// bin.
+    pub fn alter_table(username: &str, db_name: &str, table: &Table, base_path: &str) -> Result<(), DiskError> {
+        // perform storage check toward table level
+        DiskInterface::storage_hierarchy_check(base_path, Some(username), Some(db_name), Some(&table.name))?;
+
+        // load current tables from `tables.json`
+        let tables_json_path = format!("{}/{}/{}/{}", base_path, username, db_name, "tables.json");
+        let tables_file = fs::File::open(&tables_json_path)?;
+        let mut tables_json: TablesJson = serde_json::from_reader(tables_file)?;
+
+        // locate meta of target table
+        let idx_target = tables_json
+            .tables
+            .iter()
+            .position(|table_meta| table_meta.name == table.name)
+            .ok_or(DiskError::TableNotExists)?;
+        let old_table_meta = &tables_json.tables[idx_target];
+        let new_table_meta = File::build_table_meta(username, db_name, table);
+
+        // load the whole table bin
+        let table_bin_path = format!("{}/{}/{}/{}.bin", base_path, username, db_name, table.name);
+        let mut chunk_bytes = vec![];
+        fs::File::open(&table_bin_path)?.read_to_end(&mut chunk_bytes)?;
+
+        // re-encode the rows in the new layout
+        let mut new_chunk_bytes = vec![];
+        let mut new_lines = vec![new_table_meta.attrs_order.join("\t")];
+        for row_bytes in chunk_bytes.chunks(old_table_meta.row_length as usize) {
+            if row_bytes.len() != old_table_meta.row_length as usize {
+                return Err(DiskError::RangeExceedLatestRecord);
+            }
+            // a deleted row is kept as an invalid row, so the row ids are not changed
+            if row_bytes[0] == 0 {
+                new_chunk_bytes.extend_from_slice(&vec![0; new_table_meta.row_length as usize]);
+                let mut raw_row = vec!["0".to_string()];
+                raw_row.extend(new_table_meta.attrs_order[1..].iter().map(|_| "\\N".to_string()));
+                new_lines.push(raw_row.join("\t"));
+                continue;
+            }
+            let old_row = BytesCoder::bytes_to_row(old_table_meta, &row_bytes.to_vec())?;
+            let mut new_row = Row::new();
+            for attr in new_table_meta.attrs_order[1..].iter() {
+                let value = match old_table_meta.attrs.contains_key(attr) {
+                    true => old_row.data.get(attr).cloned(),
+                    false => new_table_meta.attrs[attr].default.clone(),
+                };
+                if let Some(value) = value {
+                    new_row.data.insert(attr.clone(), value);
+                }
+            }
+            new_chunk_bytes.extend_from_slice(&BytesCoder::row_to_bytes(&new_table_meta, &new_row)?);
+
+            let mut raw_row = vec!["1".to_string()];
+            for attr in new_table_meta.attrs_order[1..].iter() {
+                // a null is written as `\N`
+                raw_row.push(new_row.data.get(attr).cloned().unwrap_or("\\N".to_string()));
+            }
+            new_lines.push(raw_row.join("\t"));
+        }
+
+        // the new `tables.json` is written aside before the other files, and renaming it over the old one
+        // commits the migration, so the old files are kept until then
+        let stale_indexes = old_table_meta.get_indexes();
+        tables_json.tables[idx_target] = new_table_meta;
+        let tables_json_tmp_path = File::tables_json_tmp_path(username, db_name, &table.name, base_path);
+        fs::write(
+            &tables_json_tmp_path,
+            serde_json::to_string_pretty(&tables_json)?.as_bytes(),
+        )?;
+        fs::write(format!("{}.tmp", table_bin_path), &new_chunk_bytes)?;
+
+        // perform equivalent operation on table tsv
+        if dotenv!("ENABLE_TSV") == "true" {
+            let table_tsv_path = format!("{}/{}/{}/{}.tsv", base_path, username, db_name, table.name);
+            fs::write(format!("{}.tmp", table_tsv_path), new_lines.join("\n").as_bytes())?;
+        }
+
+        fs::rename(&tables_json_tmp_path, &tables_json_path)?;
+        File::finish_alter_table(username, db_name, &table.name, &stale_indexes, base_path)
+    }
+
+    /// complete or undo the migration of the table which was interrupted, such as by a crash
+    ///
+    /// A migration which didn't rename its `tables.json` has its temporary files removed, and the old
+    /// files are still in use. A migration which did is completed with its other files.
+    pub fn recover_alter_table(
+        username: &str,
+        db_name: &str,
+        table_name: &str,
+        base_path: &str,
+    ) -> Result<(), DiskError> {
+        let db_path = format!("{}/{}/{}", base_path, username, db_name);
+        let tables_json_tmp_path = File::tables_json_tmp_path(username, db_name, table_name, base_path);
+        let table_bin_tmp_path = format!("{}/{}.bin.tmp", db_path, table_name);
+        let table_tsv_tmp_path = format!("{}/{}.tsv.tmp", db_path, table_name);
+
+        if Path::new(&tables_json_tmp_path).exists() {
+            for tmp_path in [tables_json_tmp_path, table_bin_tmp_path, table_tsv_tmp_path].iter() {
+                if Path::new(tmp_path).exists() {
+                    fs::remove_file(tmp_path)?;
+                }
+            }
+        } else if Path::new(&table_bin_tmp_path).exists() || Path::new(&table_tsv_tmp_path).exists() {
+            // the bin may be renamed before the tsv, and the indexes in storage may be built on the old bin
+            let tables_json_path = format!("{}/{}", db_path, "tables.json");
+            let tables_file = fs::File::open(&tables_json_path)?;
+            let tables_json: TablesJson = serde_json::from_reader(tables_file)?;
+            let table_meta = tables_json
+                .tables
+                .iter()
+                .find(|table_meta| table_meta.name == table_name)
+                .ok_or(DiskError::TableNotExists)?;
+            File::finish_alter_table(username, db_name, table_name, &table_meta.get_indexes(), base_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// replace the old bin and tsv of the table with the ones of the committed migration, and remove the
+    /// stale indexes, whose keys are in the old layout
+    fn finish_alter_table(
+        username: &str,
+        db_name: &str,
+        table_name: &str,
+        stale_indexes: &[IndexMeta],
+        base_path: &str,
+    ) -> Result<(), DiskError> {
+        let db_path = format!("{}/{}/{}", base_path, username, db_name);
+        for extension in ["bin", "tsv"].iter() {
+            let path = format!("{}/{}.{}", db_path, table_name, extension);
+            let tmp_path = format!("{}.tmp", path);
+            if Path::new(&tmp_path).exists() {
+                fs::rename(tmp_path, path)?;
+            }
+        }
+
+        for index in stale_indexes {
+            let index_path = format!("{}/{}_{}.idx", db_path, table_name, index.name);
+            if Path::new(&index_path).exists() {
+                fs::remove_file(&index_path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the temporary `tables.json` of the migration of the table
+    fn tables_json_tmp_path(username: &str, db_name: &str, table_name: &str, base_path: &str) -> String {
+        format!("{}/{}/{}/tables.json.{}.tmp", base_path, username, db_name, table_name)
+    }
+
+    /// update the indexes of the table in `tables.json`, such as after `create index` or `drop index`
+    ///
+    /// Only the meta of the table is written, and the rows and the other indexes are untouched. The files
+    /// of the removed indexes are deleted once the new meta is in place, while the new indexes are left
+    /// to be built from the bin.
+    pub fn alter_indexes(username: &str, db_name: &str, table: &Table, base_path: &str) -> Result<(), DiskError> {
+        // perform storage check toward table level
+        DiskInterface::storage_hierarchy_check(base_path, Some(username), Some(db_name), Some(&table.name))?;
+
+        // load current tables from `tables.json`
+        let tables_json_path = format!("{}/{}/{}/{}", base_path, username, db_name, "tables.json");
+        let tables_file = fs::File::open(&tables_json_path)?;
+        let mut tables_json: TablesJson = serde_json::from_reader(tables_file)?;
+
+        // locate meta of target table
+        let table_meta = tables_json
+            .tables
+            .iter_mut()
+            .find(|table_meta| table_meta.name == table.name)
+            .ok_or(DiskError::TableNotExists)?;
+        let old_indexes = table_meta.get_indexes();
+        table_meta.indexes = table.indexes.clone();
+        let new_indexes = table_meta.get_indexes();
+
+        let tables_json_tmp_path = format!("{}.tmp", tables_json_path);
+        fs::write(
+            &tables_json_tmp_path,
+            serde_json::to_string_pretty(&tables_json)?.as_bytes(),
+        )?;
+        fs::rename(tables_json_tmp_path, tables_json_path)?;
+
+        // an index dropped and created again on another attribute is removed as well
+        for index in old_indexes.iter().filter(|index| !new_indexes.contains(index)) {
+            let index_path = format!(
+                "{}/{}/{}/{}_{}.idx",
+                base_path, username, db_name, table.name, index.name
+            );
+            if Path::new(&index_path).exists() {
+                fs::remove_file(&index_path)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn append_rows(
         username: &str,
         db_name: &str,
         table_name: &str,
-        rows: &Vec<Row>,
+        rows: &[Row],
         base_path: &str,
     ) -> Result<(), DiskError> {
         // perform storage check toward table level
@@ -492,7 +719,8 @@ impl File {
                 // set `__valid__` to 1
                 let mut raw_row = vec!["1".to_string()];
                 for attr in table_meta_target.attrs_order[1..].iter() {
-                    raw_row.push(row.data.get(attr).ok_or_else(|| DiskError::AttrNotExists)?.clone());
+                    // a null is written as `\N`
+                    raw_row.push(row.data.get(attr).cloned().unwrap_or("\\N".to_string()));
                 }
                 chunk += &("\n".to_string() + &raw_row.join("\t"));
             }
@@ -510,7 +738,7 @@ impl File {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
+        row_range: &[u32],
         base_path: &str,
     ) -> Result<Vec<Row>, DiskError> {
         // perform storage check toward table level
@@ -591,7 +819,7 @@ impl File {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
+        row_range: &[u32],
         base_path: &str,
     ) -> Result<(), DiskError> {
         // perform storage check toward table level
@@ -689,8 +917,8 @@ impl File {
         username: &str,
         db_name: &str,
         table_name: &str,
-        row_range: &Vec<u32>,
-        new_rows: &Vec<Row>,
+        row_range: &[u32],
+        new_rows: &[Row],
         base_path: &str,
     ) -> Result<(), DiskError> {
         if row_range[1] - row_range[0] != new_rows.len() as u32 {
@@ -757,7 +985,8 @@ impl File {
                 // set `__valid__` to 1
                 let mut raw_row = vec!["1".to_string()];
                 for attr in table_meta_target.attrs_order[1..].iter() {
-                    raw_row.push(row.data.get(attr).ok_or_else(|| DiskError::AttrNotExists)?.clone());
+                    // a null is written as `\N`
+                    raw_row.push(row.data.get(attr).cloned().unwrap_or("\\N".to_string()));
                 }
                 modified_content.push(raw_row.join("\t"));
             }
@@ -827,6 +1056,52 @@ impl File {
 
         Ok((table_bin_file_meta.len() / table_meta_target.row_length as u64) as u32)
     }
+
+    pub fn get_valid_flags(
+        username: &str,
+        db_name: &str,
+        table_name: &str,
+        row_range: &[u32],
+        base_path: &str,
+    ) -> Result<Vec<bool>, DiskError> {
+        // perform storage check toward table level
+        DiskInterface::storage_hierarchy_check(base_path, Some(username), Some(db_name), Some(table_name))?;
+
+        // load current tables from `tables.json`
+        let tables_json_path = format!("{}/{}/{}/{}", base_path, username, db_name, "tables.json");
+        let tables_file = fs::File::open(&tables_json_path)?;
+        let tables_json: TablesJson = serde_json::from_reader(tables_file)?;
+
+        // locate meta of target table
+        let idx_target = tables_json
+            .tables
+            .iter()
+            .position(|table_meta| table_meta.name == table_name);
+
+        let table_meta_target: &TableMeta = match idx_target {
+            Some(idx) => &tables_json.tables[idx],
+            None => return Err(DiskError::TableNotExists),
+        };
+
+        // open table bin for read
+        let table_bin_path = format!("{}/{}/{}/{}.bin", base_path, username, db_name, table_name);
+        let table_bin_file = fs::File::open(&table_bin_path)?;
+        let mut buffered = BufReader::new(table_bin_file);
+
+        // read the `__valid__` byte of each row
+        let mut flags: Vec<bool> = vec![];
+        for row_id in row_range[0]..row_range[1] {
+            buffered.seek(SeekFrom::Start(row_id as u64 * table_meta_target.row_length as u64))?;
+            let mut valid_byte = [0; 1];
+            match buffered.read_exact(&mut valid_byte) {
+                Ok(_) => (),
+                Err(_) => return Err(DiskError::RangeExceedLatestRecord),
+            };
+            flags.push(valid_byte[0] != 0);
+        }
+
+        Ok(flags)
+    }
 }
 
 #[cfg(test)]
@@ -1208,11 +1483,16 @@ mod tests {
                 foreign_key: vec![],
                 reference_table: None,
                 reference_attr: None,
+                unique: vec![],
+                checks: vec![],
+                named_constraints: HashMap::new(),
                 attr_offset_ranges: vec![vec![0, 1], vec![1, 5], vec![5, 55], vec![55, 95], vec![95, 115]],
-                row_length: 115,
+                row_length: 116, // with 1 byte null bitmap
                 // ignore attrs checking
                 attrs_order: vec![],
+                fields_order: vec![],
                 attrs: HashMap::new(),
+                indexes: vec![],
             },
             TableMeta {
                 name: "Hotels".to_string(),
@@ -1224,11 +1504,16 @@ mod tests {
                 foreign_key: vec![],
                 reference_table: None,
                 reference_attr: None,
+                unique: vec![],
+                checks: vec![],
+                named_constraints: HashMap::new(),
                 attr_offset_ranges: vec![vec![0, 1], vec![1, 5], vec![5, 55], vec![55, 95], vec![95, 115]],
-                row_length: 115,
+                row_length: 116, // with 1 byte null bitmap
                 // ignore attrs checking
                 attrs_order: vec![],
+                fields_order: vec![],
                 attrs: HashMap::new(),
+                indexes: vec![],
             },
         ];
 
@@ -1410,10 +1695,10 @@ mod tests {
         DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
 
         let data = vec![
-            ("AffID", "1"),
-            ("AffName", "Tom"),
-            ("AffEmail", "tom@foo.com"),
-            ("AffPhoneNum", "+886900000001"),
+            ("AffID", Some("1")),
+            ("AffName", Some("Tom")),
+            ("AffEmail", Some("tom@foo.com")),
+            ("AffPhoneNum", Some("+886900000001")),
         ];
         aff_table.insert_row(data).unwrap();
 
@@ -1441,17 +1726,17 @@ mod tests {
         }
 
         let data = vec![
-            ("AffID", "2"),
-            ("AffName", "Ben"),
-            ("AffEmail", "ben@foo.com"),
-            ("AffPhoneNum", "+886900000002"),
+            ("AffID", Some("2")),
+            ("AffName", Some("Ben")),
+            ("AffEmail", Some("ben@foo.com")),
+            ("AffPhoneNum", Some("+886900000002")),
         ];
         aff_table.insert_row(data).unwrap();
         let data = vec![
-            ("AffID", "3"),
-            ("AffName", "Leo"),
-            ("AffEmail", "leo@dee.com"),
-            ("AffPhoneNum", "+886900000003"),
+            ("AffID", Some("3")),
+            ("AffName", Some("Leo")),
+            ("AffEmail", Some("leo@dee.com")),
+            ("AffPhoneNum", Some("+886900000003")),
         ];
         aff_table.insert_row(data).unwrap();
 
@@ -1459,7 +1744,7 @@ mod tests {
             "crazyguy",
             "BookerDB",
             "Affiliates",
-            &aff_table.rows[1..].iter().cloned().collect(),
+            &aff_table.rows[1..],
             Some(file_base_path),
         )
         .unwrap();
@@ -1486,7 +1771,7 @@ mod tests {
         );
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![0, 1], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 1], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 1);
 
@@ -1495,7 +1780,7 @@ mod tests {
         }
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![0, 3], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 3], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 3);
 
@@ -1506,7 +1791,7 @@ mod tests {
         }
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![1, 3], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[1, 3], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 2);
 
@@ -1517,26 +1802,24 @@ mod tests {
         }
 
         assert_eq!(
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![2, 4], Some(file_base_path))
-                .unwrap_err(),
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[2, 4], Some(file_base_path)).unwrap_err(),
             DiskError::RangeExceedLatestRecord
         );
 
-        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![1, 2], Some(file_base_path)).unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[1, 2], Some(file_base_path)).unwrap();
 
         assert_eq!(
-            DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![1, 2], Some(file_base_path))
+            DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[1, 2], Some(file_base_path))
                 .unwrap_err(),
             DiskError::RangeContainsDeletedRecord,
         );
 
         assert_eq!(
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![0, 2], Some(file_base_path))
-                .unwrap_err(),
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 2], Some(file_base_path)).unwrap_err(),
             DiskError::RangeContainsDeletedRecord,
         );
 
-        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![0, 1], Some(file_base_path)).unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[0, 1], Some(file_base_path)).unwrap();
 
         if dotenv!("ENABLE_TSV") == "true" {
             let aff_tsv_content: Vec<String> = fs::read_to_string(&format!(
@@ -1555,26 +1838,26 @@ mod tests {
         }
 
         let data = vec![
-            ("AffID", "4"),
-            ("AffName", "John"),
-            ("AffEmail", "john@dee.com"),
-            ("AffPhoneNum", "+886900000004"),
+            ("AffID", Some("4")),
+            ("AffName", Some("John")),
+            ("AffEmail", Some("john@dee.com")),
+            ("AffPhoneNum", Some("+886900000004")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "5"),
-            ("AffName", "Ray"),
-            ("AffEmail", "ray@dee.com"),
-            ("AffPhoneNum", "+886900000005"),
+            ("AffID", Some("5")),
+            ("AffName", Some("Ray")),
+            ("AffEmail", Some("ray@dee.com")),
+            ("AffPhoneNum", Some("+886900000005")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "6"),
-            ("AffName", "Bryn"),
-            ("AffEmail", "bryn@dee.com"),
-            ("AffPhoneNum", "+886900000006"),
+            ("AffID", Some("6")),
+            ("AffName", Some("Bryn")),
+            ("AffEmail", Some("bryn@dee.com")),
+            ("AffPhoneNum", Some("+886900000006")),
         ];
         aff_table.insert_row(data).unwrap();
 
@@ -1582,7 +1865,7 @@ mod tests {
             "crazyguy",
             "BookerDB",
             "Affiliates",
-            &aff_table.rows[3..].iter().cloned().collect(),
+            &aff_table.rows[3..],
             Some(file_base_path),
         )
         .unwrap();
@@ -1601,16 +1884,15 @@ mod tests {
         }
 
         assert_eq!(
-            DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![5, 7], Some(file_base_path))
+            DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[5, 7], Some(file_base_path))
                 .unwrap_err(),
             DiskError::RangeExceedLatestRecord
         );
 
-        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![5, 6], Some(file_base_path)).unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[5, 6], Some(file_base_path)).unwrap();
 
         assert_eq!(
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![5, 6], Some(file_base_path))
-                .unwrap_err(),
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[5, 6], Some(file_base_path)).unwrap_err(),
             DiskError::RangeContainsDeletedRecord,
         );
 
@@ -1620,7 +1902,7 @@ mod tests {
         );
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![2, 5], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[2, 5], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 3);
 
@@ -1637,14 +1919,14 @@ mod tests {
             "crazyguy",
             "BookerDB",
             "Affiliates",
-            &vec![2, 5],
-            &aff_table.rows[2..5].iter().cloned().collect(),
+            &[2, 5],
+            &aff_table.rows[2..5],
             Some(file_base_path),
         )
         .unwrap();
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![2, 5], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[2, 5], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 3);
 
@@ -1659,8 +1941,8 @@ mod tests {
                 "crazyguy",
                 "BookerDB",
                 "Affiliates",
-                &vec![1, 4],
-                &aff_table.rows[1..4].iter().cloned().collect(),
+                &[1, 4],
+                &aff_table.rows[1..4],
                 Some(file_base_path)
             )
             .unwrap_err(),
@@ -1668,18 +1950,18 @@ mod tests {
         );
 
         let data = vec![
-            ("AffID", "7"),
-            ("AffName", "Eric"),
-            ("AffEmail", "eric@doo.com"),
-            ("AffPhoneNum", "+886900000007"),
+            ("AffID", Some("7")),
+            ("AffName", Some("Eric")),
+            ("AffEmail", Some("eric@doo.com")),
+            ("AffPhoneNum", Some("+886900000007")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "8"),
-            ("AffName", "Vinc"),
-            ("AffEmail", "vinc@doo.com"),
-            ("AffPhoneNum", "+886900000008"),
+            ("AffID", Some("8")),
+            ("AffName", Some("Vinc")),
+            ("AffEmail", Some("vinc@doo.com")),
+            ("AffPhoneNum", Some("+886900000008")),
         ];
         aff_table.insert_row(data).unwrap();
 
@@ -1687,7 +1969,7 @@ mod tests {
             "crazyguy",
             "BookerDB",
             "Affiliates",
-            &aff_table.rows[6..].iter().cloned().collect(),
+            &aff_table.rows[6..],
             Some(file_base_path),
         )
         .unwrap();
@@ -1697,8 +1979,8 @@ mod tests {
                 "crazyguy",
                 "BookerDB",
                 "Affiliates",
-                &vec![6, 10],
-                &aff_table.rows[1..4].iter().cloned().collect(),
+                &[6, 10],
+                &aff_table.rows[1..4],
                 Some(file_base_path)
             )
             .unwrap_err(),
@@ -1710,8 +1992,8 @@ mod tests {
                 "crazyguy",
                 "BookerDB",
                 "Affiliates",
-                &vec![6, 9],
-                &aff_table.rows[1..4].iter().cloned().collect(),
+                &[6, 9],
+                &aff_table.rows[1..4],
                 Some(file_base_path)
             )
             .unwrap_err(),
@@ -1724,7 +2006,7 @@ mod tests {
         );
 
         let rows: Vec<Row> =
-            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &vec![6, 8], Some(file_base_path)).unwrap();
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[6, 8], Some(file_base_path)).unwrap();
 
         assert_eq!(rows.len(), 2);
 
@@ -1734,4 +2016,327 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    pub fn test_alter_table() {
+        let file_base_path = "data11";
+        if Path::new(file_base_path).exists() {
+            fs::remove_dir_all(file_base_path).unwrap();
+        }
+
+        DiskInterface::create_file_base(Some(file_base_path)).unwrap();
+        DiskInterface::create_username("crazyguy", Some(file_base_path)).unwrap();
+        DiskInterface::create_db("crazyguy", "BookerDB", Some(file_base_path)).unwrap();
+
+        let mut aff_table = Table::new("Affiliates");
+        aff_table.insert_new_field(Field::new_all(
+            "AffID",
+            DataType::Int,
+            true,
+            None,
+            field::Checker::None,
+            false,
+        ));
+        aff_table.insert_new_field(Field::new("AffName", DataType::Varchar(40)));
+        aff_table.insert_new_field(Field::new("AffPhoneNum", DataType::Varchar(20)));
+        aff_table.primary_key.push("AffID".to_string());
+
+        DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("1")),
+                ("AffName", Some("Tom")),
+                ("AffPhoneNum", Some("+886900000001")),
+            ])
+            .unwrap();
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("2")),
+                ("AffName", Some("Ann")),
+                ("AffPhoneNum", None),
+            ])
+            .unwrap();
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("3")),
+                ("AffName", Some("Bob")),
+                ("AffPhoneNum", Some("+886900000003")),
+            ])
+            .unwrap();
+        DiskInterface::append_rows(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            &aff_table.rows,
+            Some(file_base_path),
+        )
+        .unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[1, 2], Some(file_base_path)).unwrap();
+
+        // drop `AffPhoneNum`, add `AffEmail` with a default, and widen `AffName`
+        aff_table.drop_field("AffPhoneNum").unwrap();
+        aff_table
+            .add_field(Field::new_all(
+                "AffEmail",
+                DataType::Varchar(50),
+                false,
+                Some("none".to_string()),
+                field::Checker::None,
+                false,
+            ))
+            .unwrap();
+        aff_table
+            .alter_field(Field::new("AffName", DataType::Char(60)))
+            .unwrap();
+        DiskInterface::alter_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+
+        let meta = DiskInterface::load_table_meta("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap();
+        assert_eq!(
+            meta.attrs_order,
+            vec![
+                "__valid__".to_string(),
+                "AffID".to_string(),
+                "AffEmail".to_string(),
+                "AffName".to_string()
+            ]
+        );
+        assert_eq!(
+            meta.fields_order,
+            vec!["AffID".to_string(), "AffName".to_string(), "AffEmail".to_string()]
+        );
+        assert_eq!(
+            meta.attr_offset_ranges,
+            vec![vec![0, 1], vec![1, 5], vec![5, 55], vec![55, 115]]
+        );
+        assert_eq!(meta.row_length, 116);
+
+        // the deleted row stays deleted at the same row id
+        assert_eq!(
+            DiskInterface::get_num_rows("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap(),
+            3
+        );
+        assert_eq!(
+            DiskInterface::get_valid_flags("crazyguy", "BookerDB", "Affiliates", &[0, 3], Some(file_base_path))
+                .unwrap(),
+            vec![true, false, true]
+        );
+        let rows =
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[2, 3], Some(file_base_path)).unwrap();
+        assert_eq!(rows[0].data["AffID"], "3".to_string());
+        assert_eq!(rows[0].data["AffName"], "Bob".to_string());
+        assert_eq!(rows[0].data["AffEmail"], "none".to_string());
+        assert!(!rows[0].data.contains_key("AffPhoneNum"));
+
+        if dotenv!("ENABLE_TSV") == "true" {
+            let aff_tsv_content: Vec<String> = fs::read_to_string(format!(
+                "{}/{}/{}/{}",
+                file_base_path, "crazyguy", "BookerDB", "Affiliates.tsv"
+            ))
+            .unwrap()
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect();
+
+            assert_eq!(aff_tsv_content[0], "__valid__\tAffID\tAffEmail\tAffName".to_string());
+            assert_eq!(aff_tsv_content[1], "1\t1\tnone\tTom".to_string());
+            assert_eq!(aff_tsv_content[2], "0\t\\N\t\\N\t\\N".to_string());
+        }
+
+        // no temporary file is left
+        let paths: Vec<String> = fs::read_dir(format!("{}/{}/{}", file_base_path, "crazyguy", "BookerDB"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(paths.iter().all(|path| !path.ends_with(".tmp")));
+
+        aff_table.name = "Hotels".to_string();
+        assert_eq!(
+            DiskInterface::alter_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap_err(),
+            DiskError::TableNotExists
+        );
+    }
+
+    #[test]
+    pub fn test_recover_alter_table() {
+        let file_base_path = "data15";
+        if Path::new(file_base_path).exists() {
+            fs::remove_dir_all(file_base_path).unwrap();
+        }
+
+        DiskInterface::create_file_base(Some(file_base_path)).unwrap();
+        DiskInterface::create_username("crazyguy", Some(file_base_path)).unwrap();
+        DiskInterface::create_db("crazyguy", "BookerDB", Some(file_base_path)).unwrap();
+
+        let mut aff_table = Table::new("Affiliates");
+        aff_table.insert_new_field(Field::new_all(
+            "AffID",
+            DataType::Int,
+            true,
+            None,
+            field::Checker::None,
+            false,
+        ));
+        aff_table.insert_new_field(Field::new("AffName", DataType::Varchar(40)));
+        aff_table.primary_key.push("AffID".to_string());
+
+        DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+        aff_table
+            .insert_row(vec![("AffID", Some("1")), ("AffName", Some("Tom"))])
+            .unwrap();
+        DiskInterface::append_rows(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            &aff_table.rows,
+            Some(file_base_path),
+        )
+        .unwrap();
+
+        let db_path = format!("{}/{}/{}", file_base_path, "crazyguy", "BookerDB");
+        let file_paths: Vec<String> = ["bin", "tsv"]
+            .iter()
+            .map(|extension| format!("{}/Affiliates.{}", db_path, extension))
+            .filter(|path| Path::new(path).exists())
+            .collect();
+        let old_contents: Vec<Vec<u8>> = file_paths.iter().map(|path| fs::read(path).unwrap()).collect();
+        let index_path = format!("{}/Affiliates_AffID.idx", db_path);
+
+        // interrupted before the commit, the old files are still in use
+        fs::write(format!("{}/tables.json.Affiliates.tmp", db_path), "{}").unwrap();
+        fs::write(format!("{}/Affiliates.bin.tmp", db_path), "").unwrap();
+        let rows =
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 1], Some(file_base_path)).unwrap();
+        assert_eq!(rows[0].data["AffName"], "Tom".to_string());
+        assert!(!Path::new(&format!("{}/tables.json.Affiliates.tmp", db_path)).exists());
+        assert!(!Path::new(&format!("{}/Affiliates.bin.tmp", db_path)).exists());
+
+        // interrupted after the commit, the new files replace the old ones, and the stale index is removed
+        aff_table
+            .add_field(Field::new_all(
+                "AffEmail",
+                DataType::Varchar(50),
+                false,
+                Some("none".to_string()),
+                field::Checker::None,
+                false,
+            ))
+            .unwrap();
+        DiskInterface::alter_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+        for (path, old_content) in file_paths.iter().zip(old_contents.iter()) {
+            fs::rename(path, format!("{}.tmp", path)).unwrap();
+            fs::write(path, old_content).unwrap();
+        }
+        fs::write(&index_path, "").unwrap();
+        let rows =
+            DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 1], Some(file_base_path)).unwrap();
+        assert_eq!(rows[0].data["AffName"], "Tom".to_string());
+        assert_eq!(rows[0].data["AffEmail"], "none".to_string());
+        assert!(!Path::new(&index_path).exists());
+        let paths: Vec<String> = fs::read_dir(&db_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(paths.iter().all(|path| !path.ends_with(".tmp")));
+
+        // interrupted after the bin is replaced, the tsv is still replaced
+        if dotenv!("ENABLE_TSV") == "true" {
+            let tsv_path = format!("{}/Affiliates.tsv", db_path);
+            let old_tsv_content = fs::read(&tsv_path).unwrap();
+            aff_table
+                .add_field(Field::new("AffPhoneNum", DataType::Varchar(20)))
+                .unwrap();
+            DiskInterface::alter_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+            let new_tsv_content = fs::read(&tsv_path).unwrap();
+            fs::rename(&tsv_path, format!("{}.tmp", tsv_path)).unwrap();
+            fs::write(&tsv_path, old_tsv_content).unwrap();
+            let rows =
+                DiskInterface::fetch_rows("crazyguy", "BookerDB", "Affiliates", &[0, 1], Some(file_base_path)).unwrap();
+            assert!(!rows[0].data.contains_key("AffPhoneNum"));
+            assert_eq!(fs::read(&tsv_path).unwrap(), new_tsv_content);
+            assert!(!Path::new(&format!("{}.tmp", tsv_path)).exists());
+        }
+    }
+
+    #[test]
+    pub fn test_alter_indexes() {
+        let file_base_path = "data14";
+        if Path::new(file_base_path).exists() {
+            fs::remove_dir_all(file_base_path).unwrap();
+        }
+
+        DiskInterface::create_file_base(Some(file_base_path)).unwrap();
+        DiskInterface::create_username("crazyguy", Some(file_base_path)).unwrap();
+        DiskInterface::create_db("crazyguy", "BookerDB", Some(file_base_path)).unwrap();
+
+        let mut aff_table = Table::new("Affiliates");
+        aff_table.insert_new_field(Field::new_all(
+            "AffID",
+            DataType::Int,
+            true,
+            None,
+            field::Checker::None,
+            false,
+        ));
+        aff_table.insert_new_field(Field::new("AffName", DataType::Varchar(40)));
+        aff_table.insert_new_field(Field::new("AffPhoneNum", DataType::Varchar(20)));
+        aff_table.primary_key.push("AffID".to_string());
+        aff_table.indexes.push(IndexMeta::new("i1", "AffName", false));
+
+        DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("1")),
+                ("AffName", Some("Tom")),
+                ("AffPhoneNum", Some("+886900000001")),
+            ])
+            .unwrap();
+        DiskInterface::append_rows(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            &aff_table.rows,
+            Some(file_base_path),
+        )
+        .unwrap();
+        for index_name in ["AffID", "i1"].iter() {
+            let index = DiskInterface::build_index_from_table_bin(
+                "crazyguy",
+                "BookerDB",
+                "Affiliates",
+                index_name,
+                Some(file_base_path),
+            )
+            .unwrap();
+            DiskInterface::save_index(&index, Some(file_base_path)).unwrap();
+        }
+        let bin_path = format!("{}/{}/{}/{}", file_base_path, "crazyguy", "BookerDB", "Affiliates.bin");
+        let bin_content = fs::read(&bin_path).unwrap();
+
+        // drop `i1`, and create `i2` on another attribute
+        aff_table.indexes = vec![IndexMeta::new("i2", "AffPhoneNum", true)];
+        DiskInterface::alter_indexes("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+
+        let meta = DiskInterface::load_table_meta("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap();
+        assert_eq!(meta.indexes, vec![IndexMeta::new("i2", "AffPhoneNum", true)]);
+        assert_eq!(fs::read(&bin_path).unwrap(), bin_content);
+
+        // only the index dropped is removed, and the new one is left to be built
+        let index_path = |name: &str| {
+            format!(
+                "{}/{}/{}/{}_{}.idx",
+                file_base_path, "crazyguy", "BookerDB", "Affiliates", name
+            )
+        };
+        assert!(Path::new(&index_path("AffID")).exists());
+        assert!(!Path::new(&index_path("i1")).exists());
+        assert!(!Path::new(&index_path("i2")).exists());
+        assert!(DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", "AffID", Some(file_base_path)).is_ok());
+
+        aff_table.name = "Hotels".to_string();
+        assert_eq!(
+            DiskInterface::alter_indexes("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap_err(),
+            DiskError::TableNotExists
+        );
+    }
 }
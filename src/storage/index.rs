@@ -1,16 +1,28 @@
-extern crate byteorder;
-
 use crate::component::table::Row;
+use crate::index::btree::BPlusTree;
+use crate::index::tree::Tree;
 use crate::storage::bytescoder::BytesCoder;
-use crate::storage::diskinterface::{DiskError, DiskInterface, TableMeta};
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use crate::storage::diskinterface::{DiskError, DiskInterface, IndexMeta, TableMeta};
 use std::fs;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufReader, Read};
 use std::path::Path;
 
+/// the sizes of a page pointer and a record pointer in the index file
+const PTR_SIZE: usize = 4;
+const ROW_PTR_SIZE: usize = 8;
+
+/// the size of the row id appended to a key of an index which is not unique
+const ROW_ID_SIZE: usize = 4;
+
+/// An index on an attribute of a table, stored in `{table}_{index}.idx`
+///
+/// The key of a row is its value of the attribute, and a row whose value is null is not in the index.
+/// The row id is appended to the key if the index is not unique, so the rows of the same value are
+/// different keys next to each other.
 pub struct Index {
     table_meta: TableMeta,
-    index_data: Vec<RowPair>,
+    index_meta: IndexMeta,
+    tree: Option<Box<BPlusTree<Vec<u8>>>>,
     num_rows: u32, // row number of the table including deleted
 }
 
@@ -25,28 +37,28 @@ impl RowPair {
     pub fn new(row: u32, key_value: Vec<u8>) -> Self {
         RowPair { row, key_value }
     }
-
-    pub fn to_bytes(&self) -> Result<Vec<u8>, DiskError> {
-        let mut bs: Vec<u8> = vec![];
-        bs.write_u32::<BigEndian>(self.row)?;
-        bs.extend_from_slice(&self.key_value);
-
-        Ok(bs)
-    }
 }
 
 #[allow(dead_code)]
 impl Index {
     //// construct a new Index
-    pub fn new(table_meta: TableMeta) -> Result<Index, DiskError> {
+    pub fn new(table_meta: TableMeta, index_meta: IndexMeta) -> Result<Index, DiskError> {
+        if !table_meta.attrs.contains_key(&index_meta.attr) {
+            return Err(DiskError::AttrNotExists);
+        }
         Ok(Index {
             table_meta,
-            index_data: vec![],
+            index_meta,
+            tree: None,
             num_rows: 0,
         })
     }
 
-    /// build index from table bin file
+    pub fn name(&self) -> &str {
+        &self.index_meta.name
+    }
+
+    /// build index from table bin file into a new index file
     pub fn build_from_bin(&mut self, base_path: &str) -> Result<(), DiskError> {
         // perform storage check toward table level
         DiskInterface::storage_hierarchy_check(
@@ -68,31 +80,35 @@ impl Index {
         let mut chunk_bytes = vec![];
         buffered.read_to_end(&mut chunk_bytes)?;
 
-        // parse chunk of bytes to vector of rows
-        let mut new_index_data: Vec<RowPair> = vec![];
-        let mut num_rows: u32 = 0;
+        // insert the key of each row into a new tree
+        let datatype = &self.table_meta.attrs[&self.index_meta.attr].datatype;
+        let mut key_size = DiskInterface::get_datatype_size(datatype) as usize;
+        if !self.index_meta.is_unique {
+            key_size += ROW_ID_SIZE;
+        }
+        self.tree = Some(BPlusTree::create(
+            &self.index_path(base_path),
+            datatype.clone(),
+            PTR_SIZE,
+            key_size,
+            ROW_PTR_SIZE,
+        )?);
+        self.num_rows = 0;
         for (row_id, row_bytes) in chunk_bytes.chunks(self.table_meta.row_length as usize).enumerate() {
             // ignore deleted rows
             if row_bytes[0] == 1 as u8 {
-                new_index_data.push(RowPair::new(
-                    row_id as u32,
-                    row_bytes[self.table_meta.attr_offset_ranges[1][0] as usize
-                        ..self.table_meta.attr_offset_ranges[1][1] as usize]
-                        .to_vec(),
-                ));
+                let row = BytesCoder::bytes_to_row(&self.table_meta, &row_bytes.to_vec())?;
+                self.insert(row_id as u32, &row)?;
             }
-            num_rows += 1;
+            self.num_rows = row_id as u32 + 1;
         }
 
-        new_index_data.sort_by(|rp1, rp2| rp1.key_value.cmp(&rp2.key_value));
-
-        self.index_data = new_index_data;
-        self.num_rows = num_rows;
-
         Ok(())
     }
 
-    /// save(overwrite) index table into index file
+    /// save index table into index file
+    ///
+    /// The pages of the tree have been written when they were modified, so they are only flushed.
     pub fn save(&self, base_path: &str) -> Result<(), DiskError> {
         // perform storage check toward table level
         DiskInterface::storage_hierarchy_check(
@@ -103,32 +119,10 @@ impl Index {
         )
         .map_err(|e| e)?;
 
-        // create chunk of bytes to be written
-        let mut chunk_bytes = vec![];
-        for rp in self.index_data.iter() {
-            chunk_bytes.extend_from_slice(&rp.to_bytes()?);
-        }
-
-        // write chunk of bytes to index bin
-        let index_bin_path = format!(
-            "{}/{}/{}/{}_{}.idx",
-            base_path,
-            self.table_meta.username,
-            self.table_meta.db_name,
-            self.table_meta.name,
-            self.table_meta.primary_key[0]
-        );
-        let mut index_bin_file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(index_bin_path)?;
-        index_bin_file.write_all(&chunk_bytes)?;
-
-        Ok(())
+        self.tree()?.sync()
     }
 
-    /// Load index from storage
+    /// Load index from storage, which only reads the root of the tree
     pub fn load(&mut self, base_path: &str) -> Result<(), DiskError> {
         // perform storage check toward table level
         DiskInterface::storage_hierarchy_check(
@@ -139,35 +133,12 @@ impl Index {
         )
         .map_err(|e| e)?;
 
-        let index_bin_path = format!(
-            "{}/{}/{}/{}_{}.idx",
-            base_path,
-            self.table_meta.username,
-            self.table_meta.db_name,
-            self.table_meta.name,
-            self.table_meta.primary_key[0]
-        );
+        let index_bin_path = self.index_path(base_path);
         if !Path::new(&index_bin_path).exists() {
             return Err(DiskError::TableIdxFileNotExists);
         }
-        let index_bin_file = fs::File::open(&index_bin_path)?;
-        let mut buffered = BufReader::new(index_bin_file);
-
-        let mut chunk_bytes = vec![];
-        buffered.read_to_end(&mut chunk_bytes)?;
 
-        // parse chunk of bytes to vector of rows
-        let mut index_data: Vec<RowPair> = vec![];
-        for rp_bytes in chunk_bytes
-            .chunks((self.table_meta.attr_offset_ranges[1][1] - self.table_meta.attr_offset_ranges[1][0] + 4) as usize)
-        {
-            index_data.push(RowPair::new(
-                (&rp_bytes[..4]).read_u32::<BigEndian>()?,
-                rp_bytes[4..].to_vec(),
-            ));
-        }
-
-        self.index_data = index_data;
+        self.tree = Some(BPlusTree::open(&index_bin_path)?);
         self.num_rows = DiskInterface::get_num_rows(
             &self.table_meta.username,
             &self.table_meta.db_name,
@@ -178,45 +149,70 @@ impl Index {
         Ok(())
     }
 
-    /// insert a row-key pair into the index
-    pub fn insert(&mut self, row: &Row) -> Result<(), DiskError> {
-        let new_row_pair = RowPair::new(
-            self.num_rows.clone(),
-            BytesCoder::attr_to_bytes(
-                &self.table_meta.attrs[&self.table_meta.primary_key[0]].datatype,
-                row.data
-                    .get(&self.table_meta.primary_key[0])
-                    .ok_or_else(|| DiskError::AttrNotExists)?,
-            )?,
-        );
-        match self
-            .index_data
-            .binary_search_by(|rp| rp.key_value.cmp(&new_row_pair.key_value))
-        {
-            Ok(_pos) => return Err(DiskError::DuplicatedKey),
-            Err(pos) => {
-                self.index_data.insert(pos, new_row_pair);
-                self.num_rows += 1;
+    /// row-key pairs of the index in the order of the keys
+    pub fn entries(&self) -> Result<Vec<RowPair>, DiskError> {
+        Ok(self
+            .tree()?
+            .entries()?
+            .into_iter()
+            .map(|(key_value, row)| RowPair::new(row.0, key_value))
+            .collect())
+    }
+
+    /// insert the key of the row at `row_id` into the index
+    ///
+    /// A row whose value is null is not inserted. A unique index rejects a value it already has.
+    pub fn insert(&mut self, row_id: u32, row: &Row) -> Result<(), DiskError> {
+        if let Some(key_val) = self.key_value(row_id, row)? {
+            let tree = self.tree_mut()?;
+            if tree.search(key_val.clone())?.is_some() {
+                return Err(DiskError::DuplicatedKey);
             }
+            tree.insert(key_val, (row_id, 0))?;
         }
+        self.num_rows = self.num_rows.max(row_id + 1);
 
         Ok(())
     }
 
-    /// delete a row-key pair from the index
-    pub fn delete(&mut self, row: &Row) -> Result<(), DiskError> {
-        let key_val = BytesCoder::attr_to_bytes(
-            &self.table_meta.attrs[&self.table_meta.primary_key[0]].datatype,
-            row.data
-                .get(&self.table_meta.primary_key[0])
-                .ok_or_else(|| DiskError::AttrNotExists)?,
-        )?;
-        match self.index_data.binary_search_by(|rp| rp.key_value.cmp(&key_val)) {
-            Ok(pos) => self.index_data.remove(pos),
-            Err(_pos) => return Err(DiskError::IndexKeyNotFound),
+    /// delete the key of the row at `row_id` from the index
+    pub fn delete(&mut self, row_id: u32, row: &Row) -> Result<(), DiskError> {
+        let key_val = match self.key_value(row_id, row)? {
+            Some(key_val) => key_val,
+            None => return Ok(()),
         };
+        match self.tree_mut()?.delete(key_val)? {
+            Some(_) => Ok(()),
+            None => Err(DiskError::IndexKeyNotFound),
+        }
+    }
 
-        Ok(())
+    /// the key of the row in the order-preserving encoding, `None` if the value is null
+    fn key_value(&self, row_id: u32, row: &Row) -> Result<Option<Vec<u8>>, DiskError> {
+        let value = match row.data.get(&self.index_meta.attr) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let mut key_val = BytesCoder::attr_to_key(&self.table_meta.attrs[&self.index_meta.attr].datatype, value)?;
+        if !self.index_meta.is_unique {
+            key_val.extend_from_slice(&row_id.to_be_bytes());
+        }
+        Ok(Some(key_val))
+    }
+
+    fn index_path(&self, base_path: &str) -> String {
+        format!(
+            "{}/{}/{}/{}_{}.idx",
+            base_path, self.table_meta.username, self.table_meta.db_name, self.table_meta.name, self.index_meta.name
+        )
+    }
+
+    fn tree(&self) -> Result<&BPlusTree<Vec<u8>>, DiskError> {
+        self.tree.as_deref().ok_or(DiskError::TableIdxFileNotExists)
+    }
+
+    fn tree_mut(&mut self) -> Result<&mut BPlusTree<Vec<u8>>, DiskError> {
+        self.tree.as_deref_mut().ok_or(DiskError::TableIdxFileNotExists)
     }
 }
 
@@ -284,69 +280,69 @@ mod tests {
         DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
 
         let data = vec![
-            ("AffID", "2"),
-            ("AffName", "Tom"),
-            ("AffEmail", "tom@foo.com"),
-            ("AffPhoneNum", "+886900000001"),
+            ("AffID", Some("2")),
+            ("AffName", Some("Tom")),
+            ("AffEmail", Some("tom@foo.com")),
+            ("AffPhoneNum", Some("+886900000001")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "7"),
-            ("AffName", "Ben"),
-            ("AffEmail", "ben@foo.com"),
-            ("AffPhoneNum", "+886900000002"),
+            ("AffID", Some("7")),
+            ("AffName", Some("Ben")),
+            ("AffEmail", Some("ben@foo.com")),
+            ("AffPhoneNum", Some("+886900000002")),
         ];
         aff_table.insert_row(data).unwrap();
 
         // to be deleted
         let data = vec![
-            ("AffID", "6"),
-            ("AffName", "Leo"),
-            ("AffEmail", "leo@dee.com"),
-            ("AffPhoneNum", "+886900000003"),
+            ("AffID", Some("6")),
+            ("AffName", Some("Leo")),
+            ("AffEmail", Some("leo@dee.com")),
+            ("AffPhoneNum", Some("+886900000003")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "1"),
-            ("AffName", "John"),
-            ("AffEmail", "john@dee.com"),
-            ("AffPhoneNum", "+886900000004"),
+            ("AffID", Some("1")),
+            ("AffName", Some("John")),
+            ("AffEmail", Some("john@dee.com")),
+            ("AffPhoneNum", Some("+886900000004")),
         ];
         aff_table.insert_row(data).unwrap();
 
         // to be deleted
         let data = vec![
-            ("AffID", "4"),
-            ("AffName", "Ray"),
-            ("AffEmail", "ray@dee.com"),
-            ("AffPhoneNum", "+886900000005"),
+            ("AffID", Some("4")),
+            ("AffName", Some("Ray")),
+            ("AffEmail", Some("ray@dee.com")),
+            ("AffPhoneNum", Some("+886900000005")),
         ];
         aff_table.insert_row(data).unwrap();
 
         // to be deleted
         let data = vec![
-            ("AffID", "5"),
-            ("AffName", "Bryn"),
-            ("AffEmail", "bryn@dee.com"),
-            ("AffPhoneNum", "+886900000006"),
+            ("AffID", Some("5")),
+            ("AffName", Some("Bryn")),
+            ("AffEmail", Some("bryn@dee.com")),
+            ("AffPhoneNum", Some("+886900000006")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "8"),
-            ("AffName", "Eric"),
-            ("AffEmail", "eric@doo.com"),
-            ("AffPhoneNum", "+886900000007"),
+            ("AffID", Some("8")),
+            ("AffName", Some("Eric")),
+            ("AffEmail", Some("eric@doo.com")),
+            ("AffPhoneNum", Some("+886900000007")),
         ];
         aff_table.insert_row(data).unwrap();
 
         let data = vec![
-            ("AffID", "3"),
-            ("AffName", "Vinc"),
-            ("AffEmail", "vinc@doo.com"),
-            ("AffPhoneNum", "+886900000008"),
+            ("AffID", Some("3")),
+            ("AffName", Some("Vinc")),
+            ("AffEmail", Some("vinc@doo.com")),
+            ("AffPhoneNum", Some("+886900000008")),
         ];
         aff_table.insert_row(data).unwrap();
 
@@ -354,71 +350,243 @@ mod tests {
             "crazyguy",
             "BookerDB",
             "Affiliates",
-            &aff_table.rows[..].iter().cloned().collect(),
+            &aff_table.rows[..],
             Some(file_base_path),
         )
         .unwrap();
 
-        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![2, 3], Some(file_base_path)).unwrap();
-        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &vec![4, 6], Some(file_base_path)).unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[2, 3], Some(file_base_path)).unwrap();
+        DiskInterface::delete_rows("crazyguy", "BookerDB", "Affiliates", &[4, 6], Some(file_base_path)).unwrap();
+        // keep the table the same as the storage, so the deleted keys could be inserted again
+        aff_table.delete_rows(&[2, 4, 5].iter().cloned().collect());
 
-        let index =
-            DiskInterface::build_index_from_table_bin("crazyguy", "BookerDB", "Affiliates", Some(file_base_path))
-                .unwrap();
+        let index = DiskInterface::build_index_from_table_bin(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            "AffID",
+            Some(file_base_path),
+        )
+        .unwrap();
 
-        assert_eq!(index.index_data.len(), 5);
+        let index_data = index.entries().unwrap();
+        assert_eq!(index_data.len(), 5);
         assert_eq!(index.num_rows, 8);
 
-        for i in 1..index.index_data.len() {
-            assert!(index.index_data[i - 1].key_value < index.index_data[i].key_value);
+        for i in 1..index_data.len() {
+            assert!(index_data[i - 1].key_value < index_data[i].key_value);
         }
 
-        let index_data = index.index_data.to_vec();
-
         DiskInterface::save_index(&index, Some(file_base_path)).unwrap();
-        let mut index = DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap();
+        let mut index =
+            DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", "AffID", Some(file_base_path)).unwrap();
 
-        assert_eq!(index_data, index.index_data);
+        assert_eq!(index_data, index.entries().unwrap());
 
         let data = vec![
-            ("AffID", "5"),
-            ("AffName", "Allie"),
-            ("AffEmail", "allie@doo.com"),
-            ("AffPhoneNum", "+886900000005"),
+            ("AffID", Some("5")),
+            ("AffName", Some("Allie")),
+            ("AffEmail", Some("allie@doo.com")),
+            ("AffPhoneNum", Some("+886900000005")),
         ];
         aff_table.insert_row(data).unwrap();
 
-        index.insert(&aff_table.rows[aff_table.rows.len() - 1]).unwrap();
+        index.insert(8, &aff_table.rows[8]).unwrap();
 
-        assert_eq!(index.index_data.len(), 6);
-        for i in 1..index.index_data.len() {
-            assert!(index.index_data[i - 1].key_value < index.index_data[i].key_value);
+        let index_data = index.entries().unwrap();
+        assert_eq!(index_data.len(), 6);
+        for i in 1..index_data.len() {
+            assert!(index_data[i - 1].key_value < index_data[i].key_value);
         }
         assert_eq!(index.num_rows, 9);
 
         assert_eq!(
-            index.insert(&aff_table.rows[aff_table.rows.len() - 1]).unwrap_err(),
-            DiskError::DuplicatedKey,
+            index.insert(8, &aff_table.rows[8]).unwrap_err(),
+            DiskError::DuplicatedKey
         );
 
-        index.delete(&aff_table.rows[aff_table.rows.len() - 2]).unwrap();
+        index.delete(7, &aff_table.rows[7]).unwrap();
 
-        assert_eq!(index.index_data.len(), 5);
-        for i in 1..index.index_data.len() {
-            assert!(index.index_data[i - 1].key_value < index.index_data[i].key_value);
+        let index_data = index.entries().unwrap();
+        assert_eq!(index_data.len(), 5);
+        for i in 1..index_data.len() {
+            assert!(index_data[i - 1].key_value < index_data[i].key_value);
         }
         assert_eq!(index.num_rows, 9);
 
         assert_eq!(
-            index.delete(&aff_table.rows[aff_table.rows.len() - 2]).unwrap_err(),
-            DiskError::IndexKeyNotFound,
+            index.delete(7, &aff_table.rows[7]).unwrap_err(),
+            DiskError::IndexKeyNotFound
         );
 
-        let index_data = index.index_data.to_vec();
-
         DiskInterface::save_index(&index, Some(file_base_path)).unwrap();
-        let index = DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap();
+        let mut index =
+            DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", "AffID", Some(file_base_path)).unwrap();
+
+        assert_eq!(index_data, index.entries().unwrap());
+
+        // a negative key is ordered before the positive ones
+        let data = vec![
+            ("AffID", Some("-2")),
+            ("AffName", Some("Neg")),
+            ("AffEmail", Some("neg@doo.com")),
+            ("AffPhoneNum", Some("+886900000009")),
+        ];
+        aff_table.insert_row(data).unwrap();
+        let row = index.num_rows;
+        index.insert(row, &aff_table.rows[aff_table.rows.len() - 1]).unwrap();
+
+        let index_data = index.entries().unwrap();
+        assert_eq!(index_data.len(), 6);
+        assert_eq!(index_data[0].row, row);
+        assert_eq!(
+            index_data[0].key_value,
+            BytesCoder::attr_to_key(&DataType::Int, "-2").unwrap()
+        );
+        for i in 1..index_data.len() {
+            assert!(index_data[i - 1].key_value < index_data[i].key_value);
+        }
+    }
+
+    #[test]
+    pub fn test_secondary_index() {
+        let file_base_path = "data13";
+        if Path::new(file_base_path).exists() {
+            fs::remove_dir_all(file_base_path).unwrap();
+        }
+
+        DiskInterface::create_file_base(Some(file_base_path)).unwrap();
+        DiskInterface::create_username("crazyguy", Some(file_base_path)).unwrap();
+        DiskInterface::create_db("crazyguy", "BookerDB", Some(file_base_path)).unwrap();
+
+        let mut aff_table = Table::new("Affiliates");
+        aff_table.fields.insert(
+            "AffID".to_string(),
+            Field::new_all("AffID", DataType::Int, true, None, field::Checker::None, false),
+        );
+        aff_table.fields.insert(
+            "AffName".to_string(),
+            Field::new_all(
+                "AffName",
+                DataType::Varchar(40),
+                true,
+                None,
+                field::Checker::None,
+                false,
+            ),
+        );
+        aff_table.fields.insert(
+            "AffEmail".to_string(),
+            Field::new_all(
+                "AffEmail",
+                DataType::Varchar(50),
+                false,
+                None,
+                field::Checker::None,
+                false,
+            ),
+        );
+        aff_table.primary_key.push("AffID".to_string());
+        aff_table.indexes = vec![
+            IndexMeta::new("name_idx", "AffName", false),
+            IndexMeta::new("email_idx", "AffEmail", true),
+        ];
+
+        DiskInterface::create_table("crazyguy", "BookerDB", &aff_table, Some(file_base_path)).unwrap();
+
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("1")),
+                ("AffName", Some("Tom")),
+                ("AffEmail", Some("tom@foo.com")),
+            ])
+            .unwrap();
+        aff_table
+            .insert_row(vec![("AffID", Some("2")), ("AffName", Some("Ben")), ("AffEmail", None)])
+            .unwrap();
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("3")),
+                ("AffName", Some("Tom")),
+                ("AffEmail", Some("bob@foo.com")),
+            ])
+            .unwrap();
+        aff_table
+            .insert_row(vec![("AffID", Some("4")), ("AffName", Some("Amy")), ("AffEmail", None)])
+            .unwrap();
+        DiskInterface::append_rows(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            &aff_table.rows,
+            Some(file_base_path),
+        )
+        .unwrap();
+
+        let rows = |index: &Index| -> Vec<u32> { index.entries().unwrap().iter().map(|pair| pair.row).collect() };
+
+        // the rows of the same value are in the order of the row ids
+        let mut name_index = DiskInterface::build_index_from_table_bin(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            "name_idx",
+            Some(file_base_path),
+        )
+        .unwrap();
+        assert_eq!(rows(&name_index), vec![3, 1, 0, 2]);
+
+        // the rows whose value is null are not in the index
+        let mut email_index = DiskInterface::build_index_from_table_bin(
+            "crazyguy",
+            "BookerDB",
+            "Affiliates",
+            "email_idx",
+            Some(file_base_path),
+        )
+        .unwrap();
+        assert_eq!(rows(&email_index), vec![2, 0]);
+
+        aff_table
+            .insert_row(vec![
+                ("AffID", Some("5")),
+                ("AffName", Some("Tom")),
+                ("AffEmail", Some("bob@foo.com")),
+            ])
+            .unwrap_err();
+        let mut row = Row::new();
+        row.data.insert("AffID".to_string(), "5".to_string());
+        row.data.insert("AffName".to_string(), "Tom".to_string());
+        row.data.insert("AffEmail".to_string(), "bob@foo.com".to_string());
+        assert_eq!(email_index.insert(4, &row).unwrap_err(), DiskError::DuplicatedKey);
+        name_index.insert(4, &row).unwrap();
+        name_index.delete(0, &aff_table.rows[0]).unwrap();
+        assert_eq!(
+            name_index.delete(0, &aff_table.rows[0]).unwrap_err(),
+            DiskError::IndexKeyNotFound
+        );
+        assert_eq!(rows(&name_index), vec![3, 1, 2, 4]);
+
+        DiskInterface::save_index(&name_index, Some(file_base_path)).unwrap();
+        DiskInterface::save_index(&email_index, Some(file_base_path)).unwrap();
+        let name_index =
+            DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", "name_idx", Some(file_base_path)).unwrap();
+        assert_eq!(rows(&name_index), vec![3, 1, 2, 4]);
+
+        assert_eq!(
+            DiskInterface::load_index("crazyguy", "BookerDB", "Affiliates", "no_idx", Some(file_base_path))
+                .err()
+                .unwrap(),
+            DiskError::IndexNotExists
+        );
 
-        assert_eq!(index_data, index.index_data);
+        // the index files are removed with the table
+        DiskInterface::drop_table("crazyguy", "BookerDB", "Affiliates", Some(file_base_path)).unwrap();
+        assert!(!Path::new(&format!("{}/crazyguy/BookerDB/Affiliates_name_idx.idx", file_base_path)).exists());
+        assert!(!Path::new(&format!(
+            "{}/crazyguy/BookerDB/Affiliates_email_idx.idx",
+            file_base_path
+        ))
+        .exists());
     }
 }
@@ -1,6 +1,10 @@
 use crate::component::datatype::DataType;
-use crate::index::tree::NodeType;
+use crate::index::tree::{NodeType, PagePtr, RowPtr};
 use crate::storage::bytescoder::BytesCoder;
+use crate::storage::diskinterface::DiskError;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
 
 trait IndexPage {
     fn new(
@@ -18,12 +22,12 @@ trait FilePage {
     fn new(pid: u32, block_length: usize) -> Self;
 }
 
-struct IndexInternalPage {
+pub struct IndexInternalPage {
     header: HeaderBytes,
     content: ContentBytes,
 }
 
-struct IndexLeafPage {
+pub struct IndexLeafPage {
     header: HeaderBytes,
     content: ContentBytes,
 }
@@ -33,7 +37,7 @@ struct DataFilePage {
     content: ContentBytes,
 }
 
-trait Header {
+pub trait Header {
     fn to_bytes(&self) -> HeaderBytes;
     fn from_bytes(header_bytes: &HeaderBytes) -> Self;
 }
@@ -45,31 +49,103 @@ struct FileHeader {
     block_length: usize,
 }
 
+/// The header of the index file, in the page 0, which locates the root of the tree
+pub const INDEX_FILE_HEADER_SIZE: usize = 20;
+pub struct IndexFileHeader {
+    pub root: PagePtr,
+    pub height: u32,
+    pub next_pid: PagePtr,
+    pub key_type: DataType,
+    pub ptr_size: usize,
+    pub key_size: usize,
+    pub row_ptr_size: usize,
+}
+
 pub const INDEX_INTERNAL_HEADER_SIZE: usize = 20;
-struct IndexInternalHeader {
-    pid: u32,
-    capacity: usize,
-    node_type: NodeType,
-    key_type: DataType,
-    ptr_size: usize,
-    key_size: usize,
+pub struct IndexInternalHeader {
+    pub pid: u32,
+    pub capacity: usize,
+    pub node_type: NodeType,
+    pub key_type: DataType,
+    pub ptr_size: usize,
+    pub key_size: usize,
+    pub num_keys: usize,
 }
 
 pub const INDEX_LEAF_HEADER_SIZE: usize = 20;
-struct IndexLeafHeader {
-    pid: u32,
-    capacity: usize,
-    node_type: NodeType,
-    key_type: DataType,
-    ptr_size: usize,
-    key_size: usize,
-    row_ptr_size: usize,
+pub struct IndexLeafHeader {
+    pub pid: u32,
+    pub capacity: usize,
+    pub node_type: NodeType,
+    pub key_type: DataType,
+    pub ptr_size: usize,
+    pub key_size: usize,
+    pub row_ptr_size: usize,
+    pub num_keys: usize,
 }
 
-type Bytes = Vec<u8>;
-struct HeaderBytes(Bytes);
+pub type Bytes = Vec<u8>;
+/// the header, the keys, the record pointers, and the previous and the next page pointers of a leaf
+pub type LeafEntries = (IndexLeafHeader, Vec<Bytes>, Vec<RowPtr>, PagePtr, PagePtr);
+pub struct HeaderBytes(pub Bytes);
 struct ContentBytes(Bytes);
 
+/// A file of pages in `PAGE_SIZE`, where the page `pid` is at the offset `pid * PAGE_SIZE`
+pub struct PageFile {
+    file: fs::File,
+    page_size: usize,
+}
+
+impl PageFile {
+    /// create an empty file of pages, or truncate the existing one
+    pub fn create(path: &str) -> Result<PageFile, DiskError> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(PageFile {
+            file,
+            page_size: get_page_size(),
+        })
+    }
+
+    pub fn open(path: &str) -> Result<PageFile, DiskError> {
+        let file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(PageFile {
+            file,
+            page_size: get_page_size(),
+        })
+    }
+
+    pub fn read_page(&self, pid: PagePtr) -> Result<Bytes, DiskError> {
+        let mut bytes = vec![0; self.page_size];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(pid as u64 * self.page_size as u64))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// write the bytes to the page, with the rest of the page padded
+    pub fn write_page(&self, pid: PagePtr, bytes: &[u8]) -> Result<(), DiskError> {
+        if bytes.len() > self.page_size {
+            return Err(DiskError::BytesError);
+        }
+        let mut page = bytes.to_vec();
+        page.resize(self.page_size, 0);
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(pid as u64 * self.page_size as u64))?;
+        file.write_all(&page)?;
+        Ok(())
+    }
+
+    pub fn sync(&self) -> Result<(), DiskError> {
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
+
 impl IndexPage for IndexInternalPage {
     fn new(
         pid: u32,
@@ -87,6 +163,7 @@ impl IndexPage for IndexInternalPage {
             key_type,
             ptr_size,
             key_size,
+            num_keys: 0,
         };
 
         let content = Vec::with_capacity(key_size * capacity + ptr_size * (capacity + 1));
@@ -117,6 +194,7 @@ impl IndexPage for IndexLeafPage {
             ptr_size,
             key_size,
             row_ptr_size,
+            num_keys: 0,
         };
 
         let content = Vec::with_capacity((key_size + row_ptr_size) * capacity + ptr_size * 2);
@@ -128,6 +206,120 @@ impl IndexPage for IndexLeafPage {
     }
 }
 
+impl IndexInternalPage {
+    /// the page of an internal node, whose content is `|P0|K1||P1|K2|| ... ||Pn|`
+    ///
+    /// Each key should be in `key_size` bytes, and there is one more pointer than the keys.
+    pub fn from_entries(header: IndexInternalHeader, keys: &[Bytes], ptrs: &[PagePtr]) -> Result<Self, DiskError> {
+        let mut content: Bytes = vec![];
+        content.write_uint::<BigEndian>(ptrs[0] as u64, header.ptr_size)?;
+        for (key, ptr) in keys.iter().zip(ptrs[1..].iter()) {
+            if key.len() != header.key_size {
+                return Err(DiskError::BytesError);
+            }
+            content.extend_from_slice(key);
+            content.write_uint::<BigEndian>(*ptr as u64, header.ptr_size)?;
+        }
+        Ok(Self {
+            header: header.to_bytes(),
+            content: ContentBytes(content),
+        })
+    }
+
+    /// the header, the keys and the pointers of the page
+    pub fn entries(&self) -> Result<(IndexInternalHeader, Vec<Bytes>, Vec<PagePtr>), DiskError> {
+        let header = IndexInternalHeader::from_bytes(&self.header);
+        let mut keys = vec![];
+        let mut ptrs = vec![];
+        let mut cursor = &self.content.0[..];
+        ptrs.push(cursor.read_uint::<BigEndian>(header.ptr_size)? as PagePtr);
+        for _ in 0..header.num_keys {
+            let (key, rest) = cursor.split_at(header.key_size);
+            keys.push(key.to_vec());
+            cursor = rest;
+            ptrs.push(cursor.read_uint::<BigEndian>(header.ptr_size)? as PagePtr);
+        }
+        Ok((header, keys, ptrs))
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = self.header.0.clone();
+        bytes.extend_from_slice(&self.content.0);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            header: HeaderBytes(bytes[..INDEX_INTERNAL_HEADER_SIZE].to_vec()),
+            content: ContentBytes(bytes[INDEX_INTERNAL_HEADER_SIZE..].to_vec()),
+        }
+    }
+}
+
+impl IndexLeafPage {
+    /// the page of a leaf, whose content is `|P0||R0|K0||R1|K1|| ... ||Rn|Kn||P1|`
+    ///
+    /// Each key should be in `key_size` bytes. A record pointer is its page pointer in `ptr_size`
+    /// bytes followed by its offset in the rest of `row_ptr_size` bytes.
+    pub fn from_entries(
+        header: IndexLeafHeader,
+        keys: &[Bytes],
+        rows: &[RowPtr],
+        prev: PagePtr,
+        next: PagePtr,
+    ) -> Result<Self, DiskError> {
+        let offset_size = header.row_ptr_size - header.ptr_size;
+        let mut content: Bytes = vec![];
+        content.write_uint::<BigEndian>(prev as u64, header.ptr_size)?;
+        for (key, row) in keys.iter().zip(rows.iter()) {
+            if key.len() != header.key_size {
+                return Err(DiskError::BytesError);
+            }
+            content.write_uint::<BigEndian>(row.0 as u64, header.ptr_size)?;
+            content.write_uint::<BigEndian>(row.1 as u64, offset_size)?;
+            content.extend_from_slice(key);
+        }
+        content.write_uint::<BigEndian>(next as u64, header.ptr_size)?;
+        Ok(Self {
+            header: header.to_bytes(),
+            content: ContentBytes(content),
+        })
+    }
+
+    /// decode the entries of the page
+    pub fn entries(&self) -> Result<LeafEntries, DiskError> {
+        let header = IndexLeafHeader::from_bytes(&self.header);
+        let offset_size = header.row_ptr_size - header.ptr_size;
+        let mut keys = vec![];
+        let mut rows = vec![];
+        let mut cursor = &self.content.0[..];
+        let prev = cursor.read_uint::<BigEndian>(header.ptr_size)? as PagePtr;
+        for _ in 0..header.num_keys {
+            let page = cursor.read_uint::<BigEndian>(header.ptr_size)? as PagePtr;
+            let offset = cursor.read_uint::<BigEndian>(offset_size)? as usize;
+            rows.push((page, offset));
+            let (key, rest) = cursor.split_at(header.key_size);
+            keys.push(key.to_vec());
+            cursor = rest;
+        }
+        let next = cursor.read_uint::<BigEndian>(header.ptr_size)? as PagePtr;
+        Ok((header, keys, rows, prev, next))
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = self.header.0.clone();
+        bytes.extend_from_slice(&self.content.0);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            header: HeaderBytes(bytes[..INDEX_LEAF_HEADER_SIZE].to_vec()),
+            content: ContentBytes(bytes[INDEX_LEAF_HEADER_SIZE..].to_vec()),
+        }
+    }
+}
+
 impl FilePage for DataFilePage {
     fn new(pid: u32, block_length: usize) -> Self {
         let capacity = get_file_capacity(&block_length);
@@ -148,14 +340,52 @@ impl FilePage for DataFilePage {
 }
 
 fn get_file_capacity(block_length: &usize) -> usize {
-    let page_size = match dotenv!("PAGE_SIZE").parse::<usize>() {
-        Ok(s) => s,
-        Err(_) => 4096,
-    };
+    let page_size = get_page_size();
 
     (page_size - FILE_HEADER_SIZE) / block_length
 }
 
+pub fn get_page_size() -> usize {
+    dotenv!("PAGE_SIZE").parse::<usize>().unwrap_or(4096)
+}
+
+fn node_type_to_byte(node_type: &NodeType) -> u8 {
+    match node_type {
+        NodeType::Internal => 0,
+        NodeType::Leaf => 1,
+    }
+}
+
+fn byte_to_node_type(byte: u8) -> NodeType {
+    match byte {
+        0 => NodeType::Internal,
+        _ => NodeType::Leaf,
+    }
+}
+
+/// a data type in 2 bytes, the kind and the length
+fn datatype_to_bytes(datatype: &DataType) -> [u8; 2] {
+    match datatype {
+        DataType::Char(length) => [0, *length],
+        DataType::Double => [1, 0],
+        DataType::Float => [2, 0],
+        DataType::Int => [3, 0],
+        DataType::Varchar(length) => [4, *length],
+        DataType::Url => [5, 0],
+    }
+}
+
+fn bytes_to_datatype(bytes: &[u8]) -> DataType {
+    match bytes[0] {
+        0 => DataType::Char(bytes[1]),
+        1 => DataType::Double,
+        2 => DataType::Float,
+        3 => DataType::Int,
+        4 => DataType::Varchar(bytes[1]),
+        _ => DataType::Url,
+    }
+}
+
 impl Header for FileHeader {
     fn to_bytes(&self) -> HeaderBytes {
         let mut bytes: Bytes = vec![];
@@ -188,58 +418,91 @@ impl Header for FileHeader {
     }
 }
 
+impl Header for IndexFileHeader {
+    /// `|root(4)|height(4)|next_pid(4)|key_type(2)|ptr_size(1)|key_size(2)|row_ptr_size(1)|`
+    fn to_bytes(&self) -> HeaderBytes {
+        let mut bytes: Bytes = vec![];
+        bytes.write_u32::<BigEndian>(self.root).unwrap();
+        bytes.write_u32::<BigEndian>(self.height).unwrap();
+        bytes.write_u32::<BigEndian>(self.next_pid).unwrap();
+        bytes.extend_from_slice(&datatype_to_bytes(&self.key_type));
+        bytes.write_u8(self.ptr_size as u8).unwrap();
+        bytes.write_u16::<BigEndian>(self.key_size as u16).unwrap();
+        bytes.write_u8(self.row_ptr_size as u8).unwrap();
+        bytes.resize(INDEX_FILE_HEADER_SIZE, 0);
+        HeaderBytes(bytes)
+    }
+
+    fn from_bytes(header_bytes: &HeaderBytes) -> Self {
+        let bytes = &header_bytes.0;
+        Self {
+            root: (&bytes[0..4]).read_u32::<BigEndian>().unwrap(),
+            height: (&bytes[4..8]).read_u32::<BigEndian>().unwrap(),
+            next_pid: (&bytes[8..12]).read_u32::<BigEndian>().unwrap(),
+            key_type: bytes_to_datatype(&bytes[12..14]),
+            ptr_size: bytes[14] as usize,
+            key_size: (&bytes[15..17]).read_u16::<BigEndian>().unwrap() as usize,
+            row_ptr_size: bytes[17] as usize,
+        }
+    }
+}
+
 impl Header for IndexInternalHeader {
+    /// `|pid(4)|capacity(2)|node_type(1)|key_type(2)|ptr_size(1)|key_size(2)|num_keys(2)|`
     fn to_bytes(&self) -> HeaderBytes {
         let mut bytes: Bytes = vec![];
-        // TODO
+        bytes.write_u32::<BigEndian>(self.pid).unwrap();
+        bytes.write_u16::<BigEndian>(self.capacity as u16).unwrap();
+        bytes.write_u8(node_type_to_byte(&self.node_type)).unwrap();
+        bytes.extend_from_slice(&datatype_to_bytes(&self.key_type));
+        bytes.write_u8(self.ptr_size as u8).unwrap();
+        bytes.write_u16::<BigEndian>(self.key_size as u16).unwrap();
+        bytes.write_u16::<BigEndian>(self.num_keys as u16).unwrap();
+        bytes.resize(INDEX_INTERNAL_HEADER_SIZE, 0);
         HeaderBytes(bytes)
     }
 
     fn from_bytes(header_bytes: &HeaderBytes) -> Self {
-        // TODO
         let bytes = &header_bytes.0;
-        let pid = 0;
-        let capacity = 0;
-        let node_type = NodeType::Internal;
-        let key_type = DataType::Int;
-        let ptr_size = 0;
-        let key_size = 0;
         Self {
-            pid,
-            capacity,
-            node_type,
-            key_type,
-            ptr_size,
-            key_size,
+            pid: (&bytes[0..4]).read_u32::<BigEndian>().unwrap(),
+            capacity: (&bytes[4..6]).read_u16::<BigEndian>().unwrap() as usize,
+            node_type: byte_to_node_type(bytes[6]),
+            key_type: bytes_to_datatype(&bytes[7..9]),
+            ptr_size: bytes[9] as usize,
+            key_size: (&bytes[10..12]).read_u16::<BigEndian>().unwrap() as usize,
+            num_keys: (&bytes[12..14]).read_u16::<BigEndian>().unwrap() as usize,
         }
     }
 }
 
 impl Header for IndexLeafHeader {
+    /// `|pid(4)|capacity(2)|node_type(1)|key_type(2)|ptr_size(1)|key_size(2)|num_keys(2)|row_ptr_size(1)|`
     fn to_bytes(&self) -> HeaderBytes {
         let mut bytes: Bytes = vec![];
-        // TODO
+        bytes.write_u32::<BigEndian>(self.pid).unwrap();
+        bytes.write_u16::<BigEndian>(self.capacity as u16).unwrap();
+        bytes.write_u8(node_type_to_byte(&self.node_type)).unwrap();
+        bytes.extend_from_slice(&datatype_to_bytes(&self.key_type));
+        bytes.write_u8(self.ptr_size as u8).unwrap();
+        bytes.write_u16::<BigEndian>(self.key_size as u16).unwrap();
+        bytes.write_u16::<BigEndian>(self.num_keys as u16).unwrap();
+        bytes.write_u8(self.row_ptr_size as u8).unwrap();
+        bytes.resize(INDEX_LEAF_HEADER_SIZE, 0);
         HeaderBytes(bytes)
     }
 
     fn from_bytes(header_bytes: &HeaderBytes) -> Self {
-        // TODO
         let bytes = &header_bytes.0;
-        let pid = 0;
-        let capacity = 0;
-        let node_type = NodeType::Leaf;
-        let key_type = DataType::Int;
-        let ptr_size = 0;
-        let key_size = 0;
-        let row_ptr_size = 0;
         Self {
-            pid,
-            capacity,
-            node_type,
-            key_type,
-            ptr_size,
-            key_size,
-            row_ptr_size,
+            pid: (&bytes[0..4]).read_u32::<BigEndian>().unwrap(),
+            capacity: (&bytes[4..6]).read_u16::<BigEndian>().unwrap() as usize,
+            node_type: byte_to_node_type(bytes[6]),
+            key_type: bytes_to_datatype(&bytes[7..9]),
+            ptr_size: bytes[9] as usize,
+            key_size: (&bytes[10..12]).read_u16::<BigEndian>().unwrap() as usize,
+            num_keys: (&bytes[12..14]).read_u16::<BigEndian>().unwrap() as usize,
+            row_ptr_size: bytes[14] as usize,
         }
     }
 }